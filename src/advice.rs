@@ -0,0 +1,133 @@
+//! Second thoughts about a deck before it's registered: whether it can be played at all, whether
+//! it holds cards that do the same job, and whether a deck already saved beats it on every card.
+//! None of these stop a deck from being saved; they're shown so the user can think again.
+
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    data::Data,
+    decks::SavedDecks,
+    game::Card,
+    legality::{DeckLegality, LegalityError},
+};
+
+/// Something worth pointing out about a deck.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeckWarning {
+    /// The deck breaks the game's limits on rare cards, or holds a card twice.
+    Illegal(LegalityError),
+    /// Two different cards in the deck have the same values and suit, so one of them could be
+    /// swapped for something that covers other sides.
+    SameValues(String, String),
+    /// A saved deck, named here, has the same cards, maybe in another order.
+    SameCardsAs(String),
+    /// A saved deck, named here, has a card at least as high on every side for each card in this
+    /// deck, and is higher somewhere.
+    DominatedBy(String),
+}
+impl Display for DeckWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeckWarning::Illegal(e) => write!(f, "It can't be played: {}", e),
+            DeckWarning::SameValues(first, second) => write!(
+                f,
+                "{} and {} have the same values, so one of them adds nothing the other doesn't",
+                first, second
+            ),
+            DeckWarning::SameCardsAs(deck) => {
+                write!(f, "Your deck {} already has the same cards", deck)
+            }
+            DeckWarning::DominatedBy(deck) => write!(
+                f,
+                "Your deck {} is at least as strong on every side of every card under the basic rules",
+                deck
+            ),
+        }
+    }
+}
+
+/// Everything worth pointing out about `deck` before saving it as `name`. A saved deck with the
+/// same name is left out of the comparisons, since the new deck replaces it.
+pub fn check_deck(
+    data: &Data,
+    name: &str,
+    deck: &[i32; 5],
+    saved_decks: &SavedDecks,
+) -> Vec<DeckWarning> {
+    let mut warnings = vec![];
+    if let Err(e) = DeckLegality::standard().check(data, deck) {
+        warnings.push(DeckWarning::Illegal(e));
+    }
+
+    let cards = match cards_of(data, deck) {
+        Some(cards) => cards,
+        // An unknown card was already reported as making the deck illegal
+        None => return warnings,
+    };
+    for (i, (&first_id, first)) in deck.iter().zip(&cards).enumerate() {
+        for (&second_id, second) in deck.iter().zip(&cards).skip(i + 1) {
+            if first_id != second_id
+                && first.dominates(second)
+                && second.dominates(first)
+                && first.suit == second.suit
+            {
+                warnings.push(DeckWarning::SameValues(
+                    data.card_display_name(first_id).to_string(),
+                    data.card_display_name(second_id).to_string(),
+                ));
+            }
+        }
+    }
+
+    let mut sorted = *deck;
+    sorted.sort_unstable();
+    let mut deck_names = saved_decks.get_deck_names();
+    deck_names.sort();
+    for deck_name in deck_names.into_iter().filter(|deck_name| deck_name != name) {
+        let saved = saved_decks.get_deck(&deck_name).unwrap();
+        let mut saved_sorted = saved;
+        saved_sorted.sort_unstable();
+        if saved_sorted == sorted {
+            warnings.push(DeckWarning::SameCardsAs(deck_name));
+        } else if cards_of(data, &saved).is_some_and(|saved| deck_dominates(&saved, &cards)) {
+            warnings.push(DeckWarning::DominatedBy(deck_name));
+        }
+    }
+    warnings
+}
+
+fn cards_of(data: &Data, deck: &[i32; 5]) -> Option<Vec<Card>> {
+    deck.iter().map(|&id| data.get_card(id).copied()).collect()
+}
+
+/// Whether the cards in `stronger` can be paired off with the ones in `weaker` so that each
+/// dominates its partner (see [`Card::dominates`]), with at least one pair differing. Under the
+/// basic rules, that makes `weaker` never the better deck to bring.
+pub fn deck_dominates(stronger: &[Card], weaker: &[Card]) -> bool {
+    if stronger.len() != weaker.len() {
+        return false;
+    }
+    let mut used = vec![false; stronger.len()];
+    pair_off(stronger, weaker, &mut used, false)
+}
+
+/// Tries every card in `stronger` not used yet against the first card left in `weaker`, then
+/// pairs off the rest the same way. `differs` says whether a pair so far wasn't identical.
+fn pair_off(stronger: &[Card], weaker: &[Card], used: &mut [bool], differs: bool) -> bool {
+    let (target, rest) = match weaker.split_first() {
+        Some(split) => split,
+        None => return differs,
+    };
+    for i in 0..stronger.len() {
+        if used[i] || !stronger[i].dominates(target) {
+            continue;
+        }
+        used[i] = true;
+        let identical = target.dominates(&stronger[i]);
+        if pair_off(stronger, rest, used, differs || !identical) {
+            return true;
+        }
+        used[i] = false;
+    }
+    false
+}
@@ -0,0 +1,193 @@
+//! Looking back over a match: stepping backward and forward through its moves, trying another move
+//! at any point to see where it leads, and searching whichever position is on the board, all
+//! without entering the match again. It works on a copy, so the match itself isn't changed, and
+//! can be opened after the match or from the move prompt during it.
+
+use inquire::Text;
+use triple_triad_solver::{
+    data::Data,
+    game::{self, Game, GameMove, Player},
+    search::{GamePlayer, SearchConfig, SearchableGame, WinState},
+};
+
+use crate::PossiblePlacement;
+
+const HELP: &str = "back [n], forward [n], start, end, a move like `3 B1` to try it, eval, export, actual (back to the match's moves), quit";
+
+/// Where the analysis is in the moves it's stepping through.
+struct Timeline {
+    /// The moves the match actually had.
+    actual: Vec<GameMove>,
+    /// The moves being stepped through: the actual ones, or the ones tried instead from some
+    /// point on.
+    line: Vec<GameMove>,
+    /// How many moves of `line` are on the board.
+    cursor: usize,
+    /// Whose turn it is after all of the actual moves.
+    to_move_after: Player,
+}
+impl Timeline {
+    /// Whose turn it is in the position on the board.
+    fn to_move(&self) -> Player {
+        match (self.line.get(self.cursor), self.cursor.checked_sub(1)) {
+            (Some(next), _) => next.player,
+            (None, Some(last)) => self.line[last].player.other(),
+            (None, None) => self.to_move_after,
+        }
+    }
+
+    /// How many moves the line being stepped through shares with the match's.
+    fn shared_moves(&self) -> usize {
+        self.line
+            .iter()
+            .zip(&self.actual)
+            .take_while(|(tried, actual)| tried == actual)
+            .count()
+    }
+
+    fn describe(&self) -> String {
+        let shared = self.shared_moves();
+        let mut description = format!("After move {} of {}", self.cursor, self.line.len());
+        if shared < self.line.len() {
+            description.push_str(&format!(
+                " (trying other moves from move {} on)",
+                shared + 1
+            ));
+        }
+        description
+    }
+}
+
+/// Opens the analysis on a copy of `game`, with `to_move` having the next move after the moves
+/// played so far. Returns when the player quits.
+pub fn explore(
+    game: &Game,
+    to_move: Player,
+    data: &Data,
+    npc_name: &str,
+    search_config: &SearchConfig,
+    lines_shown: usize,
+) {
+    let mut game = game.clone();
+    let actual = game.played_moves();
+    let mut timeline = Timeline {
+        line: actual.clone(),
+        cursor: actual.len(),
+        actual,
+        to_move_after: to_move,
+    };
+    let mut possible_moves = Vec::with_capacity(100);
+
+    println!("Analyzing the match. Commands: {}.", HELP);
+    loop {
+        print!("{}", game.display_with_hand_panel(Player::Red, data));
+        println!("{}.", timeline.describe());
+
+        let input = Text::new("Analysis:")
+            .with_help_message(HELP)
+            .prompt()
+            .unwrap();
+        let mut words = input.split_whitespace();
+        let count = |word: Option<&str>| word.and_then(|n| n.parse::<usize>().ok()).unwrap_or(1);
+        match words.next() {
+            None | Some("quit") | Some("q") => return,
+            Some("back") | Some("b") => {
+                let steps = count(words.next()).min(timeline.cursor);
+                game.undo_last_moves(steps);
+                timeline.cursor -= steps;
+            }
+            Some("forward") | Some("f") => {
+                step_forward(&mut game, &mut timeline, count(words.next()));
+            }
+            Some("start") => {
+                game.undo_last_moves(timeline.cursor);
+                timeline.cursor = 0;
+            }
+            Some("end") => {
+                let steps = timeline.line.len() - timeline.cursor;
+                step_forward(&mut game, &mut timeline, steps);
+            }
+            Some("actual") => {
+                // Back to where the tried moves left the match's, then along the match's to
+                // the same move as before, or as far as it goes
+                let target = timeline.cursor.min(timeline.actual.len());
+                let shared = timeline.shared_moves();
+                game.undo_last_moves(timeline.cursor.saturating_sub(shared));
+                timeline.cursor = timeline.cursor.min(shared);
+                timeline.line = timeline.actual.clone();
+                let steps = target - timeline.cursor;
+                step_forward(&mut game, &mut timeline, steps);
+            }
+            Some("export") => crate::export_board(&game, data, npc_name),
+            Some("eval") | Some("e") => evaluate(
+                &game,
+                timeline.to_move(),
+                data,
+                npc_name,
+                search_config,
+                lines_shown,
+            ),
+            Some(_) => {
+                game.get_possible_moves(timeline.to_move(), &mut possible_moves);
+                match crate::parse_move_notation(&input, &possible_moves) {
+                    Some(idx) => {
+                        let mv = possible_moves[idx];
+                        if timeline.line.get(timeline.cursor) != Some(&mv) {
+                            timeline.line.truncate(timeline.cursor);
+                            timeline.line.push(mv);
+                        }
+                        game.apply_move(&mv);
+                        timeline.cursor += 1;
+                    }
+                    None => println!("That isn't a command or a legal move. {}.", HELP),
+                }
+            }
+        }
+    }
+}
+
+fn step_forward(game: &mut Game, timeline: &mut Timeline, steps: usize) {
+    for _ in 0..steps {
+        match timeline.line.get(timeline.cursor) {
+            Some(mv) => game.apply_move(mv),
+            None => break,
+        }
+        timeline.cursor += 1;
+    }
+}
+
+/// Searches the position on the board for `player`, printing the best moves the way the match's
+/// recommendations are.
+fn evaluate(
+    game: &Game,
+    player: Player,
+    data: &Data,
+    npc_name: &str,
+    search_config: &SearchConfig,
+    lines_shown: usize,
+) {
+    if !matches!(game.win_state(), WinState::NotFinished) {
+        return println!("The match is over at this point.");
+    }
+    println!("Searching (Ctrl-C to stop early)...");
+    let (lines, _) = crate::search_interruptibly(search_config, |config| {
+        crate::recommended_lines(game, player, config, lines_shown)
+    });
+    println!(
+        "Best moves for {}:",
+        match player {
+            Player::Blue => "you",
+            Player::Red => data.npc_display_name(npc_name),
+        }
+    );
+    for (rank, line) in lines.iter().enumerate() {
+        println!(
+            "  {}. {} in the {} ({}). ({})",
+            rank + 1,
+            game.player_hand_card_name(player, line.mv.card_idx, data),
+            PossiblePlacement(line.mv.placement),
+            game::cell_code(line.mv.placement),
+            crate::describe_score(line.score, line.proven)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Everything a player has set up, in one file to move to another computer or share: the active
+//! profile's decks and match history, the settings, and the notes on NPCs. Importing an archive
+//! merges it into what's already here rather than replacing it; see [`SavedDecks::merge`],
+//! [`MatchStats::merge`], [`Settings::merge`], and [`NpcNotes::merge`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use thiserror::Error;
+
+use crate::{
+    data::Data,
+    decks::{DeckMerge, SavedDeckError, SavedDecks},
+    notes::{NpcNotes, NpcNotesError},
+    settings::{Settings, SettingsError},
+    stats::{MatchStats, MatchStatsError},
+    store,
+};
+
+/// The archive format written by this version. Older formats are still read; newer ones aren't,
+/// since they may hold things this version would silently drop. Version 2 added the NPC notes.
+pub const ARCHIVE_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("Could not read/write the archive")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Could not parse the archive")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("The archive was made by a newer version of the solver (format {0}, this version reads up to {ARCHIVE_VERSION})")]
+    TooNew(u32),
+
+    #[error("Could not save the imported decks")]
+    Decks(#[from] SavedDeckError),
+
+    #[error("Could not save the imported match history")]
+    Stats(#[from] MatchStatsError),
+
+    #[error("Could not save the imported settings")]
+    Settings(#[from] SettingsError),
+
+    #[error("Could not save the imported NPC notes")]
+    Notes(#[from] NpcNotesError),
+}
+
+#[derive(Serialize)]
+struct ArchiveRef<'a> {
+    version: u32,
+    solver_version: &'a str,
+    exported: DateTime<Utc>,
+    settings: &'a Settings,
+    decks: &'a SavedDecks,
+    stats: &'a MatchStats,
+    notes: &'a NpcNotes,
+}
+
+#[derive(Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    /// The version of the solver that made the archive.
+    pub solver_version: String,
+    pub exported: DateTime<Utc>,
+    settings: Settings,
+    decks: SavedDecks,
+    stats: MatchStats,
+    #[serde(default)]
+    notes: Option<NpcNotes>,
+}
+
+/// What importing an archive added.
+pub struct ImportSummary {
+    pub decks: DeckMerge,
+    pub matches: usize,
+    pub notes: usize,
+}
+
+pub fn export(
+    path: &Path,
+    settings: &Settings,
+    decks: &SavedDecks,
+    stats: &MatchStats,
+    notes: &NpcNotes,
+) -> Result<(), ArchiveError> {
+    let archive = ArchiveRef {
+        version: ARCHIVE_VERSION,
+        solver_version: env!("CARGO_PKG_VERSION"),
+        exported: Utc::now(),
+        settings,
+        decks,
+        stats,
+        notes,
+    };
+    store::save(path, &serde_json::to_vec_pretty(&archive)?)?;
+    Ok(())
+}
+
+impl Archive {
+    pub fn load(path: &Path) -> Result<Archive, ArchiveError> {
+        let text = std::fs::read_to_string(path)?;
+
+        // Check the version first, so a newer archive is reported as that rather than as whatever
+        // it has that this version can't parse
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = serde_json::from_str(&text)?;
+        if version > ARCHIVE_VERSION {
+            return Err(ArchiveError::TooNew(version));
+        }
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub fn deck_count(&self) -> usize {
+        self.decks.get_deck_count()
+    }
+
+    pub fn match_count(&self) -> usize {
+        self.stats.get_match_count()
+    }
+
+    /// Merges the archive into the current profile, leaving out decks with cards that aren't in
+    /// `data`. With `take_preferences`, the archive's settings replace the current ones as
+    /// [`Settings::merge`] describes.
+    pub fn import(
+        self,
+        data: &Data,
+        settings: &mut Settings,
+        decks: &mut SavedDecks,
+        stats: &mut MatchStats,
+        notes: &mut NpcNotes,
+        take_preferences: bool,
+    ) -> Result<ImportSummary, ArchiveError> {
+        let deck_merge = decks.merge(self.decks, |id| data.get_card(id).is_some())?;
+        let matches = stats.merge(self.stats)?;
+        settings.merge(self.settings, take_preferences)?;
+        let imported_notes = match self.notes {
+            Some(imported) => notes.merge(imported)?,
+            None => 0,
+        };
+        Ok(ImportSummary {
+            decks: deck_merge,
+            matches,
+            notes: imported_notes,
+        })
+    }
+}
@@ -0,0 +1,228 @@
+// The `bench` subcommand: runs a fixed set of positions through the alpha-beta search and the Monte
+// Carlo simulations and reports how fast they go, so changes to the search can be measured instead
+// of guessed at. The positions are built in and the simulations are seeded, so runs on the same
+// machine do the same work and can be compared directly. Build with `--release` for real numbers.
+// Each search is also run again as plain alpha-beta, without the killer-move and history ordering
+// or the transposition table, to show how many nodes they save.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use triple_triad_solver::{
+    game::{Game, GameMove, Player},
+    scenario::Scenario,
+    search::{
+        self, EvalWeights, GamePlayer, SearchConfig, SearchableGame, TranspositionTable, WinState,
+    },
+};
+
+const SEED: u64 = 0x5eed;
+const PLAYOUTS: usize = 200_000;
+
+// Written like the files in scenarios/, without expectations since only the timing matters
+const POSITIONS: [&str; 3] = [
+    r#"
+        name = "Opening, no rules"
+        first = "Blue"
+        blue = ["1-4-8-8", "8-2-3-8", "6-A-2-1", "4-7-A-3", "2-5-5-8"]
+        red = ["5-1-3-A", "7-7-2-1", "2-8-8-4", "3-3-7-6", "A-2-1-6"]
+        [expect]
+    "#,
+    r#"
+        name = "Opening, Ascension and Fallen Ace"
+        rules = ["Ascension", "Fallen Ace"]
+        first = "Red"
+        blue = ["1-4-8-8 P", "8-2-3-8 P", "6-A-2-1", "4-7-A-3 B", "2-5-5-8"]
+        red = ["5-1-3-A P", "7-7-2-1 B", "2-8-8-4 B", "3-3-7-6", "A-2-1-6 P"]
+        [expect]
+    "#,
+    r#"
+        name = "Two moves in, Reverse"
+        rules = ["Reverse"]
+        first = "Blue"
+        blue = ["1-4-8-8", "8-2-3-8", "6-A-2-1", "4-7-A-3", "2-5-5-8"]
+        red = ["5-1-3-A", "7-7-2-1", "2-8-8-4", "3-3-7-6", "A-2-1-6"]
+        moves = ["6-A-2-1@B2", "2-8-8-4@A2"]
+        [expect]
+    "#,
+];
+
+/// Counts the moves the search plays while passing everything through to the game.
+struct Counted<'a> {
+    game: Game,
+    nodes: &'a AtomicU64,
+}
+impl<'a> SearchableGame for Counted<'a> {
+    type Move = GameMove;
+    type Player = Player;
+
+    fn get_possible_moves(&self, player: Player, buffer: &mut Vec<GameMove>) {
+        self.game.get_possible_moves(player, buffer)
+    }
+
+    fn evaluate_current_position_for(&self, player: Player) -> f64 {
+        self.game.evaluate_current_position_for(player)
+    }
+
+    fn win_state(&self) -> WinState<Self> {
+        match self.game.win_state() {
+            WinState::NotFinished => WinState::NotFinished,
+            WinState::Tie => WinState::Tie,
+            WinState::Winner(player) => WinState::Winner(player),
+        }
+    }
+
+    fn truncate_history_and_clone(&self) -> Self {
+        Counted {
+            game: self.game.truncate_history_and_clone(),
+            nodes: self.nodes,
+        }
+    }
+
+    fn chance_outcomes(&self, moves: &[GameMove], outcomes: &mut Vec<(Vec<GameMove>, f64)>) {
+        self.game.chance_outcomes(moves, outcomes)
+    }
+
+    fn is_perfect_information(&self) -> bool {
+        self.game.is_perfect_information()
+    }
+
+    fn history_key(&self, mv: &GameMove) -> Option<usize> {
+        self.game.history_key(mv)
+    }
+
+    fn position_key(&self, to_move: Player) -> Option<u64> {
+        self.game.position_key(to_move)
+    }
+
+    fn set_eval_weights(&mut self, weights: &EvalWeights) {
+        self.game.set_eval_weights(weights)
+    }
+
+    fn material_lead(&self, player: Player) -> f64 {
+        self.game.material_lead(player)
+    }
+
+    fn apply_move(&mut self, mv: &GameMove) {
+        self.nodes.fetch_add(1, Ordering::Relaxed);
+        self.game.apply_move(mv)
+    }
+
+    fn undo_last_moves(&mut self, n: usize) {
+        self.game.undo_last_moves(n)
+    }
+}
+
+/// Searches `game` `depth` moves ahead, returning the best moves, their score, and how many nodes
+/// and how long it took. `table` is cleared first, so every search starts with an empty one, unless
+/// it's `None` for plain alpha-beta.
+fn search(
+    game: &Game,
+    player: Player,
+    depth: usize,
+    table: Option<&Arc<TranspositionTable>>,
+) -> (Vec<GameMove>, f64, u64, Duration) {
+    let nodes = AtomicU64::new(0);
+    let counted = Counted {
+        game: game.truncate_history_and_clone(),
+        nodes: &nodes,
+    };
+    if let Some(table) = table {
+        table.clear();
+    }
+    let config = SearchConfig {
+        move_ordering: table.is_some(),
+        transposition_table: table.cloned(),
+        ..SearchConfig::default()
+    };
+    let start = Instant::now();
+    let (best_moves, score) = search::search_to_depth(&counted, player, depth, &config);
+    let elapsed = start.elapsed();
+    (best_moves, score, nodes.into_inner(), elapsed)
+}
+
+pub fn run() {
+    let mut total_nodes = 0;
+    let mut total_plain_nodes = 0;
+    let mut total_plain_time = Duration::ZERO;
+    let table = Arc::new(TranspositionTable::default());
+    let mut total_search_time = Duration::ZERO;
+    let mut total_playout_time = Duration::ZERO;
+
+    for position in POSITIONS {
+        let scenario = toml::from_str::<Scenario>(position).unwrap();
+        let game = Game::from_scenario(&scenario).unwrap();
+        let player = scenario.to_move();
+        println!("{}", scenario.name);
+
+        // Searching every depth up to the end of the match, like iterative deepening would
+        let moves_left = 9 - scenario.moves.len();
+        for depth in 1..=moves_left {
+            let (best_moves, score, nodes, elapsed) = search(&game, player, depth, Some(&table));
+            let (plain_best_moves, plain_score, plain_nodes, plain_elapsed) =
+                search(&game, player, depth, None);
+            total_nodes += nodes;
+            total_plain_nodes += plain_nodes;
+            total_plain_time += plain_elapsed;
+            total_search_time += elapsed;
+            println!(
+                "  depth {}: {:>10?}, {:>11} nodes ({:>10.0}/s, {:>5.1}% fewer than plain), {} best moves scoring {}",
+                depth,
+                elapsed,
+                nodes,
+                nodes as f64 / elapsed.as_secs_f64(),
+                percent_fewer(nodes, plain_nodes),
+                best_moves.len(),
+                score
+            );
+            if score != plain_score || best_moves.len() != plain_best_moves.len() {
+                println!(
+                    "    plain alpha-beta: {} best moves scoring {}",
+                    plain_best_moves.len(),
+                    plain_score
+                );
+            }
+        }
+
+        let config = SearchConfig {
+            monte_carlo_iterations: PLAYOUTS,
+            verbose: false,
+            seed: Some(SEED),
+            ..SearchConfig::default()
+        };
+        // The ratio is for the player who just moved, so the opponent of the one to move
+        let start = Instant::now();
+        let win_ratio = search::monte_carlo_win_ratio(&game, player.other(), &config);
+        let elapsed = start.elapsed();
+        total_playout_time += elapsed;
+        println!(
+            "  {} playouts: {:?} ({:.0}/s), win ratio {:.4}",
+            PLAYOUTS,
+            elapsed,
+            PLAYOUTS as f64 / elapsed.as_secs_f64(),
+            win_ratio
+        );
+    }
+
+    let playouts = PLAYOUTS * POSITIONS.len();
+    println!(
+        "Total: {} nodes in {:?} ({:.0}/s; {} in {:?} with plain alpha-beta, {:.1}% fewer), {} playouts in {:?} ({:.0}/s)",
+        total_nodes,
+        total_search_time,
+        total_nodes as f64 / total_search_time.as_secs_f64(),
+        total_plain_nodes,
+        total_plain_time,
+        percent_fewer(total_nodes, total_plain_nodes),
+        playouts,
+        total_playout_time,
+        playouts as f64 / total_playout_time.as_secs_f64()
+    );
+}
+
+fn percent_fewer(nodes: u64, plain_nodes: u64) -> f64 {
+    100.0 * (1.0 - nodes as f64 / plain_nodes.max(1) as f64)
+}
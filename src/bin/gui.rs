@@ -0,0 +1,709 @@
+// Graphical frontend, built with the `gui` feature. It plays matches against NPCs like the terminal
+// version: cards are placed by dragging them from a hand onto the board (or by clicking a card and
+// then a cell), and the recommendation for the player's move is worked out in the background.
+
+use chrono::Utc;
+use eframe::egui::{self, Color32, RichText};
+use std::sync::{
+    mpsc::{self, Receiver},
+    Arc, Mutex,
+};
+use triple_triad_solver::{
+    data::{self, Data},
+    decks::SavedDecks,
+    dirs::DataDirs,
+    game::{self, CardId, CellChange, Game, GameMove, HandCardStatus, Player, Rules, GLOSSARY},
+    legality::DeckLegality,
+    notes::NpcNotes,
+    profiles::Profiles,
+    search::{
+        self, CancelToken, FirstMove, GamePlayer, OutcomeRates, ProgressReporter, SearchConfig,
+        SearchProgress, SearchableGame, WinState,
+    },
+    settings::Settings,
+    stats::{MatchRecord, MatchResult, MatchStats},
+};
+
+const CELL_SIZE: f32 = 96.0;
+
+/// A card being dragged out of a hand, identified by its hand slot.
+struct DraggedCard(usize);
+
+/// The best move for the player, as found by the search: the move, its score, the Monte Carlo win
+/// ratio if there was a tie to break, and the NPC's likely reply followed by the player's answer.
+type Recommendation = (Option<GameMove>, (f64, Option<OutcomeRates>), Vec<GameMove>);
+
+struct Setup {
+    npc_filter: String,
+    npc_name: Option<String>,
+    deck_name: Option<String>,
+    first_player: Player,
+    /// Whether each of [`Rules::NAMES`] is in effect, starting from the selected NPC's rules.
+    rules: [bool; 9],
+}
+
+struct Match {
+    game: Game,
+    npc_name: String,
+    deck_name: String,
+    current_player: Player,
+    selected_slot: Option<usize>,
+    log: Vec<String>,
+    recommendation: Option<Recommendation>,
+    search: Option<Receiver<Recommendation>>,
+    /// Stops the search running in the background, which then settles for the best move so far.
+    cancel: CancelToken,
+    /// The search's latest progress update.
+    progress: Arc<Mutex<Option<SearchProgress>>>,
+    result: Option<MatchResult>,
+}
+
+struct App {
+    data: Data,
+    settings: Settings,
+    saved_decks: SavedDecks,
+    match_stats: MatchStats,
+    npc_notes: NpcNotes,
+    setup: Setup,
+    current_match: Option<Match>,
+    error: Option<String>,
+}
+
+fn main() -> eframe::Result {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let data_dirs = DataDirs::from_args(&mut args).unwrap();
+
+    let settings = Settings::new(&data_dirs).unwrap();
+    data::set_download_network(&settings.download_proxy, &settings.download_ca_certificate);
+    let data = Data::new(
+        &data_dirs,
+        &settings.data_source_url,
+        settings.language,
+        false,
+    )
+    .unwrap();
+    let profiles = Profiles::new(&data_dirs).unwrap();
+    let saved_decks = SavedDecks::new(&profiles.active_dir()).unwrap();
+    let match_stats = MatchStats::new(&profiles.active_dir()).unwrap();
+    let npc_notes = NpcNotes::new(data_dirs.config_dir()).unwrap();
+
+    let app = App {
+        setup: Setup {
+            npc_filter: String::new(),
+            npc_name: None,
+            deck_name: saved_decks.get_deck_names_by_use().into_iter().next(),
+            // The setup always asks, so "ask every match" just starts on the player
+            first_player: match settings.first_player.first_move() {
+                FirstMove::Known(player) => player,
+                FirstMove::Random => Player::Blue,
+            },
+            rules: [false; 9],
+        },
+        data,
+        settings,
+        saved_decks,
+        match_stats,
+        npc_notes,
+        current_match: None,
+        error: None,
+    };
+
+    eframe::run_native(
+        "Triple Triad Solver",
+        eframe::NativeOptions {
+            viewport: egui::ViewportBuilder::default().with_inner_size([1100.0, 720.0]),
+            ..Default::default()
+        },
+        Box::new(|_| Ok(Box::new(app))),
+    )
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if let Some(error) = &self.error {
+            egui::TopBottomPanel::top("error").show(ctx, |ui| {
+                ui.colored_label(Color32::LIGHT_RED, error);
+            });
+        }
+
+        if self.current_match.is_some() {
+            self.show_match(ctx);
+        } else {
+            self.show_setup(ctx);
+        }
+    }
+}
+
+impl App {
+    fn show_setup(&mut self, ctx: &egui::Context) {
+        let data = &self.data;
+        let saved_decks = &self.saved_decks;
+        let npc_notes = &self.npc_notes;
+        let setup = &mut self.setup;
+        let mut start = false;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("New match");
+            if saved_decks.get_deck_count() == 0 {
+                ui.label(
+                    "Register a deck in the terminal version first; you need one to play an NPC.",
+                );
+                return;
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Search NPCs:");
+                ui.text_edit_singleline(&mut setup.npc_filter);
+            });
+            let mut npc_names = data
+                .npcs_by_name
+                .keys()
+                .filter(|name| data.npc_matches(name, &setup.npc_filter))
+                .collect::<Vec<_>>();
+            npc_names.sort_by_key(|name| data.npc_display_name(name));
+            egui::ScrollArea::vertical()
+                .max_height(260.0)
+                .show(ui, |ui| {
+                    for name in npc_names {
+                        let npc = &data.npcs_by_name[name];
+                        let label = match &npc.location {
+                            Some(location) => {
+                                format!("{} — {}", data.npc_display_name(name), location)
+                            }
+                            None => data.npc_display_name(name).to_string(),
+                        };
+                        if ui
+                            .selectable_label(setup.npc_name.as_ref() == Some(name), label)
+                            .clicked()
+                        {
+                            setup.npc_name = Some(name.clone());
+                            setup.rules = npc.rules.flags();
+                            if let Some(last_used) = saved_decks.last_used(name) {
+                                setup.deck_name = Some(last_used.deck.clone());
+                            }
+                        }
+                    }
+                });
+            if let Some(notes) = setup.npc_name.as_ref().and_then(|name| npc_notes.get(name)) {
+                ui.label(format!("Your notes: {}", notes));
+            }
+
+            ui.separator();
+            egui::ComboBox::from_label("Deck")
+                .selected_text(setup.deck_name.clone().unwrap_or_default())
+                .show_ui(ui, |ui| {
+                    for name in saved_decks.get_deck_names_by_use() {
+                        let label = if saved_decks.is_favorite(&name) {
+                            format!("★ {}", name)
+                        } else {
+                            name.clone()
+                        };
+                        ui.selectable_value(&mut setup.deck_name, Some(name), label);
+                    }
+                });
+            if let Some(last_used) = setup
+                .npc_name
+                .as_ref()
+                .and_then(|name| saved_decks.last_used(name))
+            {
+                ui.label(format!(
+                    "Last time against this NPC: {} ({})",
+                    last_used.deck, last_used.result
+                ));
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Who goes first?");
+                ui.radio_value(&mut setup.first_player, Player::Blue, "Me");
+                ui.radio_value(&mut setup.first_player, Player::Red, "The NPC");
+            });
+
+            let uses_regional_rules = setup
+                .npc_name
+                .as_ref()
+                .map(|name| data.npcs_by_name[name].uses_regional_rules)
+                .unwrap_or(false);
+            if setup.npc_name.is_some() {
+                ui.label(if uses_regional_rules {
+                    "This NPC also uses the current regional rules. Which rules are in effect?"
+                } else {
+                    "Which rules are in effect?"
+                });
+                ui.horizontal_wrapped(|ui| {
+                    for (explanation, active) in GLOSSARY.iter().zip(&mut setup.rules) {
+                        ui.checkbox(active, explanation.name)
+                            .on_hover_text(explanation.details);
+                    }
+                });
+            }
+
+            ui.separator();
+            let ready = setup.npc_name.is_some() && setup.deck_name.is_some();
+            if ui.add_enabled(ready, egui::Button::new("Start")).clicked() {
+                start = true;
+            }
+        });
+
+        if start {
+            self.start_match(ctx);
+        }
+    }
+
+    fn start_match(&mut self, ctx: &egui::Context) {
+        let npc_name = self.setup.npc_name.clone().unwrap();
+        let deck_name = self.setup.deck_name.clone().unwrap();
+        let deck = match self.saved_decks.get_deck(&deck_name) {
+            Ok(deck) => deck,
+            Err(e) => {
+                self.error = Some(format!("Could not load the deck: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = DeckLegality::standard().check(&self.data, &deck) {
+            self.error = Some(format!("{} can't be played: {}", deck_name, e));
+            return;
+        }
+
+        let mut game = Game::new(Player::Blue, Arc::clone(&self.data.cards)); // Human is always Blue vs NPCs
+        game.set_cards_in_hand(Player::Blue, &deck.map(|id| CardId::new(id).unwrap()), 5);
+        game.set_cards_for_npc(Player::Red, &self.data, &npc_name);
+        let mut rules = Rules::default();
+        for (name, _) in Rules::NAMES
+            .iter()
+            .zip(self.setup.rules)
+            .filter(|(_, active)| *active)
+        {
+            rules.add_rule_by_name(name);
+        }
+        game.set_rules(rules);
+
+        let mut current_match = Match {
+            game,
+            npc_name,
+            deck_name,
+            current_player: self.setup.first_player,
+            selected_slot: None,
+            log: Vec::new(),
+            recommendation: None,
+            search: None,
+            cancel: CancelToken::new(),
+            progress: Arc::new(Mutex::new(None)),
+            result: None,
+        };
+        current_match.start_search(ctx, &self.settings);
+        self.error = None;
+        self.current_match = Some(current_match);
+    }
+
+    fn show_match(&mut self, ctx: &egui::Context) {
+        let data = &self.data;
+        let current_match = self.current_match.as_mut().unwrap();
+        if let Some(recommendation) = current_match
+            .search
+            .as_ref()
+            .and_then(|receiver| receiver.try_recv().ok())
+        {
+            current_match.recommendation = Some(recommendation);
+            current_match.search = None;
+        }
+
+        let mut played = None;
+        let mut leave = false;
+
+        egui::TopBottomPanel::top("score").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let scores = current_match.game.scores();
+                ui.label(
+                    RichText::new(format!("You {}", scores[Player::Blue]))
+                        .color(player_color(Player::Blue))
+                        .strong(),
+                );
+                ui.label("–");
+                ui.label(
+                    RichText::new(format!(
+                        "{} {}",
+                        scores[Player::Red],
+                        data.npc_display_name(&current_match.npc_name)
+                    ))
+                    .color(player_color(Player::Red))
+                    .strong(),
+                );
+                let rules = current_match.game.rules().active_rule_names();
+                if !rules.is_empty() {
+                    ui.separator();
+                    ui.label(format!("Rules: {}", rules.join(", ")));
+                }
+                ui.separator();
+                let button = if current_match.result.is_some() {
+                    "New match"
+                } else {
+                    "Abandon match"
+                };
+                if ui.button(button).clicked() {
+                    leave = true;
+                }
+            });
+            // A reminder of what each rule in play does
+            for explanation in current_match.game.rules().explanations() {
+                ui.weak(format!("{}: {}", explanation.name, explanation.summary))
+                    .on_hover_text(explanation.details);
+            }
+        });
+
+        egui::TopBottomPanel::bottom("moves")
+            .min_height(150.0)
+            .show(ctx, |ui| {
+                match (&current_match.result, &current_match.recommendation) {
+                    (Some(result), _) => {
+                        ui.heading(match result {
+                            MatchResult::Win => "You win!",
+                            MatchResult::Tie => "Tie!",
+                            MatchResult::Loss => "You lose!",
+                        });
+                    }
+                    (None, _) if current_match.search.is_some() => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            if current_match.cancel.is_cancelled() {
+                                ui.label("Stopping the search...");
+                            } else {
+                                ui.label(match &*current_match.progress.lock().unwrap() {
+                                    Some(progress) => {
+                                        format!("Finding the best move ({})...", progress)
+                                    }
+                                    None => String::from("Finding the best move..."),
+                                });
+                                if ui
+                                    .button("Stop")
+                                    .on_hover_text("Settle for the best move found so far")
+                                    .clicked()
+                                {
+                                    current_match.cancel.cancel();
+                                }
+                            }
+                        });
+                    }
+                    (None, Some((Some(mv), (score, rates), line))) => {
+                        ui.strong(format!(
+                            "Recommended: play your {} card in {} (score {}{}).",
+                            current_match.game.player_hand_card_name(
+                                Player::Blue,
+                                mv.card_idx,
+                                data
+                            ),
+                            game::cell_code(mv.placement),
+                            score,
+                            rates
+                                .map(|rates| format!("; random playouts: {}", rates))
+                                .unwrap_or_default()
+                        ));
+                        if let Some(description) = current_match.game.describe_expected_line(
+                            line,
+                            data.npc_display_name(&current_match.npc_name),
+                            data,
+                        ) {
+                            ui.label(description);
+                        }
+                    }
+                    (None, _) => {
+                        ui.label(format!(
+                            "Enter the move {} made.",
+                            data.npc_display_name(&current_match.npc_name)
+                        ));
+                    }
+                }
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for (idx, entry) in current_match.log.iter().enumerate() {
+                            ui.label(format!("{}. {}", idx + 1, entry));
+                        }
+                    });
+            });
+
+        for (player, panel) in [
+            (Player::Blue, egui::SidePanel::left("blue_hand")),
+            (Player::Red, egui::SidePanel::right("red_hand")),
+        ] {
+            panel.min_width(230.0).show(ctx, |ui| {
+                show_hand(ui, current_match, data, player);
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            played = show_board(ui, current_match);
+        });
+
+        if let Some((slot, placement)) = played {
+            current_match.play(slot, placement, data);
+            if current_match.result.is_some() {
+                self.record_result();
+            } else {
+                current_match.start_search(ctx, &self.settings);
+            }
+        }
+        if leave {
+            if let Some(current_match) = &self.current_match {
+                current_match.cancel.cancel();
+            }
+            self.current_match = None;
+        }
+    }
+
+    fn record_result(&mut self) {
+        let current_match = self.current_match.as_ref().unwrap();
+        let record = MatchRecord {
+            date: Utc::now(),
+            deck: current_match.deck_name.clone(),
+            npc: current_match.npc_name.clone(),
+            rules: current_match
+                .game
+                .rules()
+                .active_rule_names()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            result: current_match.result.unwrap(),
+        };
+        let saved = self
+            .saved_decks
+            .record_last_used(&record.npc, &record.deck, record.result)
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                self.match_stats
+                    .record_match(record)
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = saved {
+            self.error = Some(format!("Could not save the match result: {}", e));
+        }
+    }
+}
+
+impl Match {
+    /// Starts looking for the player's best move in the background, if it's their turn.
+    fn start_search(&mut self, ctx: &egui::Context, settings: &Settings) {
+        // A search still running for an earlier position isn't needed anymore
+        self.cancel.cancel();
+        self.cancel = CancelToken::new();
+        self.progress = Arc::new(Mutex::new(None));
+        self.recommendation = None;
+        if self.current_player != Player::Blue || self.result.is_some() {
+            self.search = None;
+            return;
+        }
+
+        let game = self.game.truncate_history_and_clone();
+        let search_config = SearchConfig {
+            verbose: false,
+            cancel: Some(self.cancel.clone()),
+            progress: Some(ProgressReporter::new({
+                let progress = Arc::clone(&self.progress);
+                let ctx = ctx.clone();
+                move |update| {
+                    *progress.lock().unwrap() = Some(update.clone());
+                    ctx.request_repaint();
+                }
+            })),
+            ..settings.search_config_against(&self.npc_name)
+        };
+        let ctx = ctx.clone();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mv, score) = search::get_best_move_for_player(&game, Player::Blue, &search_config);
+            let line = mv
+                .as_ref()
+                .map(|mv| search::expected_line(&game, Player::Blue, mv, &search_config))
+                .unwrap_or_default();
+            let recommendation = (mv, score, line);
+            // The match may have been abandoned in the meantime, which is fine
+            let _ = sender.send(recommendation);
+            ctx.request_repaint();
+        });
+        self.search = Some(receiver);
+    }
+
+    fn legal_move(&self, slot: usize, placement: usize) -> Option<GameMove> {
+        let mut moves = Vec::new();
+        self.game
+            .get_possible_moves(self.current_player, &mut moves);
+        moves
+            .into_iter()
+            .find(|mv| mv.card_idx == slot && mv.placement == placement)
+    }
+
+    fn play(&mut self, slot: usize, placement: usize, data: &Data) {
+        let mv = match self.legal_move(slot, placement) {
+            Some(mv) => mv,
+            None => return,
+        };
+
+        let flips = self.game.preview_move(&mv);
+        self.log.push(format!(
+            "{}: {} to {}{}",
+            match mv.player {
+                Player::Blue => "You",
+                Player::Red => data.npc_display_name(&self.npc_name),
+            },
+            self.game
+                .player_hand_card_name(mv.player, mv.card_idx, data),
+            game::cell_code(mv.placement),
+            if flips.is_empty() {
+                String::new()
+            } else {
+                format!(", flipping {}", cell_list(&flips))
+            }
+        ));
+        self.game.apply_move(&mv);
+        self.current_player = self.current_player.other();
+        self.selected_slot = None;
+
+        self.result = match self.game.win_state() {
+            WinState::NotFinished => None,
+            WinState::Tie => Some(MatchResult::Tie),
+            WinState::Winner(Player::Blue) => Some(MatchResult::Win),
+            WinState::Winner(Player::Red) => Some(MatchResult::Loss),
+        };
+    }
+}
+
+fn player_color(player: Player) -> Color32 {
+    match player {
+        Player::Blue => Color32::LIGHT_BLUE,
+        Player::Red => Color32::LIGHT_RED,
+    }
+}
+
+fn cell_list(cells: &[usize]) -> String {
+    cells
+        .iter()
+        .map(|&pos| game::cell_code(pos))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn show_hand(ui: &mut egui::Ui, current_match: &mut Match, data: &Data, player: Player) {
+    ui.heading(match player {
+        Player::Blue => String::from("Your hand"),
+        Player::Red => data.npc_display_name(&current_match.npc_name).to_string(),
+    });
+    let playing = current_match.current_player == player && current_match.result.is_none();
+    if playing {
+        ui.label("Drag a card onto the board, or click it and then a cell.");
+    }
+    ui.separator();
+
+    for (slot, id, card, status) in current_match.game.hand_overview(player) {
+        let color = match status {
+            HandCardStatus::InHand => player_color(player),
+            HandCardStatus::Possible => Color32::YELLOW,
+            HandCardStatus::Played | HandCardStatus::RuledOut => Color32::DARK_GRAY,
+        };
+        let mut text = format!("{} ({})", data.card_display_name(id.get()), card);
+        if status != HandCardStatus::InHand {
+            text.push_str(&format!(" — {}", status.label()));
+        }
+        let text = RichText::new(text).color(color);
+
+        let can_play =
+            playing && !matches!(status, HandCardStatus::Played | HandCardStatus::RuledOut);
+        if can_play {
+            let selected = current_match.selected_slot == Some(slot);
+            let response = ui
+                .dnd_drag_source(
+                    egui::Id::new(("card", player, slot)),
+                    DraggedCard(slot),
+                    |ui| ui.selectable_label(selected, text),
+                )
+                .inner;
+            if response.clicked() {
+                current_match.selected_slot = if selected { None } else { Some(slot) };
+            }
+        } else {
+            ui.label(text);
+        }
+    }
+}
+
+/// Draws the board, returning the hand slot and cell of a card the player just placed.
+fn show_board(ui: &mut egui::Ui, current_match: &Match) -> Option<(usize, usize)> {
+    let changes = current_match.game.last_move_changes();
+    let recommended_cell = match &current_match.recommendation {
+        Some((Some(mv), _, _)) => Some(mv.placement),
+        _ => None,
+    };
+    let mut played = None;
+
+    egui::Grid::new("board").spacing([6.0, 6.0]).show(ui, |ui| {
+        for (pos, change) in changes.iter().enumerate() {
+            let stroke_color = if recommended_cell == Some(pos) {
+                Color32::GREEN
+            } else {
+                Color32::GRAY
+            };
+            let frame = egui::Frame::group(ui.style()).stroke((1.0, stroke_color));
+            let (response, dropped) = ui.dnd_drop_zone::<DraggedCard, _>(frame, |ui| {
+                ui.set_min_size(egui::vec2(CELL_SIZE, CELL_SIZE));
+                ui.vertical_centered(|ui| show_cell(ui, current_match, pos, *change))
+                    .inner
+            });
+
+            let empty = current_match.game.board_cell(pos).is_none();
+            if let Some(dragged) = dropped {
+                played = Some((dragged.0, pos));
+            } else if let Some(slot) = current_match.selected_slot.filter(|_| empty) {
+                let cell = response.response.interact(egui::Sense::click());
+                let cell = match current_match.legal_move(slot, pos) {
+                    Some(mv) => {
+                        let flips = current_match.game.preview_move(&mv);
+                        cell.on_hover_text(if flips.is_empty() {
+                            String::from("No flips")
+                        } else {
+                            format!("Flips {}: {}", flips.len(), cell_list(&flips))
+                        })
+                    }
+                    None => cell,
+                };
+                if cell.clicked() {
+                    played = Some((slot, pos));
+                }
+            }
+
+            if pos % 3 == 2 {
+                ui.end_row();
+            }
+        }
+    });
+
+    played
+}
+
+fn show_cell(
+    ui: &mut egui::Ui,
+    current_match: &Match,
+    pos: usize,
+    change: CellChange,
+) -> egui::Response {
+    match current_match.game.board_cell(pos) {
+        Some(([north, east, south, west], suit, owner)) => {
+            let style = |text: String| {
+                let text = RichText::new(text).color(player_color(owner)).size(18.0);
+                if change == CellChange::Unchanged {
+                    text
+                } else {
+                    text.strong().underline()
+                }
+            };
+            ui.label(RichText::new(format!("{}{}", change.marker(), game::cell_code(pos))).small());
+            ui.label(style(north));
+            ui.label(style(format!("{}  {}  {}", west, suit, east)));
+            ui.label(style(south))
+        }
+        None => ui.label(
+            RichText::new(game::cell_code(pos))
+                .color(Color32::DARK_GRAY)
+                .size(18.0),
+        ),
+    }
+}
@@ -0,0 +1,153 @@
+//! Searching the card list by name, rarity, suit, and values.
+
+use inquire::Text;
+use std::fmt::{Display, Formatter};
+
+use triple_triad_solver::{
+    data::Data,
+    game::{Card, Direction, Suit},
+    prompt::Select,
+};
+
+// Cards are shown in rows of this many, like a deck
+const CARDS_PER_ROW: usize = 5;
+
+#[derive(Clone, Copy)]
+enum SuitFilter {
+    Any,
+    NoSuit,
+    Suit(Suit),
+}
+impl SuitFilter {
+    fn matches(&self, card: &Card) -> bool {
+        match *self {
+            SuitFilter::Any => true,
+            SuitFilter::NoSuit => card.suit == Suit::None,
+            SuitFilter::Suit(suit) => card.suit == suit,
+        }
+    }
+}
+impl Display for SuitFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            SuitFilter::Any => write!(f, "Any suit"),
+            SuitFilter::NoSuit => write!(f, "No suit"),
+            SuitFilter::Suit(suit) => write!(f, "{:?}", suit),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum StarFilter {
+    Any,
+    Stars(u8),
+}
+impl Display for StarFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            StarFilter::Any => write!(f, "Any rarity"),
+            StarFilter::Stars(stars) => write!(f, "{}", "★".repeat(stars as usize)),
+        }
+    }
+}
+
+/// Parses up to four minimum values, in clockwise order from the top. Missing values are 0.
+fn parse_minimums(input: &str) -> Option<[i32; 4]> {
+    let mut result = [0; 4];
+    let values = input
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '-')
+        .filter(|value| !value.is_empty());
+    for (i, value) in values.enumerate() {
+        let slot = result.get_mut(i)?;
+        *slot = match value {
+            "A" | "a" => 10,
+            value => value.parse().ok()?,
+        };
+    }
+    Some(result)
+}
+
+pub fn browse_cards(data: &Data) {
+    let name = Text::new("Name contains:")
+        .with_help_message("Leave empty to match any name")
+        .prompt()
+        .unwrap();
+    let stars = Select::new(
+        "Rarity:",
+        std::iter::once(StarFilter::Any)
+            .chain((1..=5).map(StarFilter::Stars))
+            .collect(),
+    )
+    .prompt()
+    .unwrap();
+    let suit = Select::new(
+        "Suit:",
+        vec![
+            SuitFilter::Any,
+            SuitFilter::NoSuit,
+            SuitFilter::Suit(Suit::Primal),
+            SuitFilter::Suit(Suit::Scion),
+            SuitFilter::Suit(Suit::Beastman),
+            SuitFilter::Suit(Suit::Garlean),
+        ],
+    )
+    .prompt()
+    .unwrap();
+    let minimums = loop {
+        let input = Text::new("Minimum values (top, right, bottom, left):")
+            .with_help_message("For example `0 0 0 8` finds cards with a left side of 8 or more; leave empty for any")
+            .prompt()
+            .unwrap();
+        match parse_minimums(&input) {
+            Some(minimums) => break minimums,
+            None => println!("Please enter up to four numbers from 1 to 10 (or A)."),
+        }
+    };
+    let directions = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    let mut matches: Vec<(i32, &Card)> = data
+        .card_names
+        .keys()
+        .filter_map(|&id| Some((id, data.get_card(id)?)))
+        .filter(|&(id, _)| name.is_empty() || data.card_matches(id, &name))
+        .filter(|&(id, _)| match stars {
+            StarFilter::Any => true,
+            StarFilter::Stars(stars) => data
+                .card_info
+                .get(&id)
+                .is_some_and(|info| info.stars == stars),
+        })
+        .filter(|(_, card)| suit.matches(card))
+        .filter(|(_, card)| {
+            directions
+                .iter()
+                .zip(minimums)
+                .all(|(&direction, minimum)| card.value(direction) >= minimum)
+        })
+        .collect();
+    matches.sort_by_key(|&(id, _)| {
+        data.card_info
+            .get(&id)
+            .map(|info| info.number)
+            .unwrap_or(id)
+    });
+
+    if matches.is_empty() {
+        println!("No cards match.");
+        return;
+    }
+
+    println!("{} matching cards:\n", matches.len());
+    for row in matches.chunks(CARDS_PER_ROW) {
+        let mut deck = [None; CARDS_PER_ROW];
+        for (slot, (id, _)) in deck.iter_mut().zip(row) {
+            *slot = Some(*id);
+        }
+        crate::print_deck(&deck, data);
+    }
+}
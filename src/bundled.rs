@@ -0,0 +1,96 @@
+// Offline snapshot of the five sheets, compiled into the binary when the `bundled-data` feature is
+// enabled. The snapshot itself isn't part of the repository (the data belongs to Square Enix): to
+// build with it, gzip the five CSVs into `bundled/` and put the date they were taken (RFC 3339) in
+// `bundled/SNAPSHOT_DATE`.
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+use crate::data::LoadDataError;
+
+#[cfg(feature = "bundled-data")]
+const SNAPSHOT_DATE: &str = include_str!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/bundled/SNAPSHOT_DATE"
+));
+
+#[cfg(feature = "bundled-data")]
+const FILES: [(&str, &[u8]); 5] = [
+    (
+        "TripleTriadCard.csv",
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/bundled/TripleTriadCard.csv.gz"
+        )),
+    ),
+    (
+        "TripleTriadCardResident.csv",
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/bundled/TripleTriadCardResident.csv.gz"
+        )),
+    ),
+    (
+        "TripleTriad.csv",
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/bundled/TripleTriad.csv.gz"
+        )),
+    ),
+    (
+        "ENpcBase.csv",
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/bundled/ENpcBase.csv.gz"
+        )),
+    ),
+    (
+        "ENpcResident.csv",
+        include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/bundled/ENpcResident.csv.gz"
+        )),
+    ),
+];
+
+/// When the bundled snapshot was taken, or `None` if this build doesn't have one.
+pub fn snapshot_date() -> Option<DateTime<Utc>> {
+    #[cfg(feature = "bundled-data")]
+    {
+        DateTime::parse_from_rfc3339(SNAPSHOT_DATE.trim())
+            .ok()
+            .map(|date| date.with_timezone(&Utc))
+    }
+
+    #[cfg(not(feature = "bundled-data"))]
+    {
+        None
+    }
+}
+
+/// Decompresses the bundled sheets into `destination`, returning the total number of bytes written.
+pub fn extract_to(destination: &Path) -> Result<usize, LoadDataError> {
+    // A snapshot without a readable date can't be compared against the cache, so don't use it
+    if snapshot_date().is_none() {
+        return Err(LoadDataError::NoBundledData);
+    }
+
+    #[cfg(feature = "bundled-data")]
+    {
+        use flate2::read::GzDecoder;
+        use std::{fs::File, io::copy};
+
+        let mut total_bytes = 0;
+        for (fname, compressed) in FILES {
+            let mut file = File::create(destination.join(fname))?;
+            total_bytes += copy(&mut GzDecoder::new(compressed), &mut file)? as usize;
+        }
+        Ok(total_bytes)
+    }
+
+    #[cfg(not(feature = "bundled-data"))]
+    {
+        let _ = destination;
+        unreachable!()
+    }
+}
@@ -0,0 +1,104 @@
+//! Coaching in place of recommendations, for players who'd rather get better at the game than
+//! follow the solver: their moves are graded once they've picked them, or only blunders are called
+//! out.
+
+use triple_triad_solver::{
+    data::Data,
+    game::{self, Game, GameMove},
+    prompt::Confirm,
+    search::{self, SearchConfig},
+    settings::{HintLevel, Settings},
+};
+
+use crate::PossiblePlacement;
+
+/// How a move compares with the best one, both scored for the player making it.
+struct Grade {
+    best: GameMove,
+    best_score: f64,
+    score: f64,
+}
+impl Grade {
+    fn new(game: &Game, mv: GameMove, config: &SearchConfig) -> Grade {
+        let (best, (best_score, _)) = search::get_best_move_for_player(game, mv.player, config);
+        let best = best.unwrap();
+        let score = if best == mv {
+            best_score
+        } else {
+            search::score_move(game, mv.player, &mv, config)
+        };
+        Grade {
+            best,
+            best_score,
+            score,
+        }
+    }
+
+    /// How much the move gives up compared with the best one.
+    fn loss(&self) -> f64 {
+        (self.best_score - self.score).max(0.0)
+    }
+}
+
+/// Asks the player for their move, then coaches them on it the way `settings.hint_level` says,
+/// returning its index in `moves`. Blunders can be taken back when they're only warned about.
+pub fn pick_coached_move(
+    moves: &[GameMove],
+    game: &Game,
+    data: &Data,
+    search_config: &SearchConfig,
+    settings: &Settings,
+) -> usize {
+    loop {
+        println!("What do you play?");
+        let idx = crate::pick_move(moves, game, data);
+        let mv = moves[idx];
+
+        println!("Checking your move (Ctrl-C to skip)...");
+        let (grade, stopped) =
+            crate::search_interruptibly(search_config, |config| Grade::new(game, mv, config));
+        if stopped {
+            println!("Your move wasn't checked.");
+            return idx;
+        }
+
+        match settings.hint_level {
+            HintLevel::Full => {}
+            HintLevel::WarnBlunders => {
+                if grade.loss() > settings.blunder_threshold {
+                    println!(
+                        "Careful: that looks like a blunder. It scores {}, when a better move scores {}.",
+                        grade.score, grade.best_score
+                    );
+                    let play_anyway = Confirm::new("Play it anyway?")
+                        .with_default(false)
+                        .prompt()
+                        .unwrap();
+                    if !play_anyway {
+                        continue;
+                    }
+                }
+            }
+            HintLevel::GradeAfter => {
+                if grade.loss() == 0.0 {
+                    println!("Best move! (Score: {})", grade.score);
+                } else {
+                    println!(
+                        "{}: the best move was your {} card in the {} ({}). (Score: {}, yours: {})",
+                        if grade.loss() > settings.blunder_threshold {
+                            "Blunder"
+                        } else {
+                            "Inaccuracy"
+                        },
+                        game.player_hand_card_name(grade.best.player, grade.best.card_idx, data),
+                        PossiblePlacement(grade.best.placement),
+                        game::cell_code(grade.best.placement),
+                        grade.best_score,
+                        grade.score
+                    );
+                }
+            }
+        }
+        return idx;
+    }
+}
@@ -0,0 +1,119 @@
+//! Shows a deck next to an NPC's cards, with the sides of the NPC's cards the deck can beat picked
+//! out: a quick look at a matchup without simulating it.
+
+use triple_triad_solver::{
+    data::Data,
+    decks::SavedDecks,
+    game::{self, Card, Direction, RuleSet},
+    stats::MatchStats,
+};
+
+const SIDES: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+pub fn compare_with_npc(data: &Data, saved_decks: &SavedDecks, match_stats: &MatchStats) {
+    if saved_decks.get_deck_count() == 0 {
+        println!("You must have at least 1 registered deck to compare!");
+        return;
+    }
+
+    let npc_name = crate::select_npc_with(data, &crate::NpcPicker::new(saved_decks, match_stats));
+    let npc = &data.npcs_by_name[npc_name];
+    let npc_display_name = data.npc_display_name(npc_name);
+    let deck_name = crate::select_deck("Which deck?", saved_decks);
+    let deck = saved_decks.get_deck(&deck_name).unwrap();
+
+    let rules = crate::adjust_rules(&npc.rules, npc.uses_regional_rules);
+    let comparison = RuleSet::new(&rules).comparison();
+    let mine = deck
+        .iter()
+        .filter_map(|&id| data.get_card(id))
+        .collect::<Vec<_>>();
+    // A side of theirs can be beaten if one of my cards' sides beats it when placed against it
+    let beatable = |card: &Card| {
+        let mut sides = [false; 4];
+        for direction in SIDES {
+            sides[direction as usize] = mine.iter().any(|attacker| {
+                comparison.beats(attacker.value(direction.opposite()), card.value(direction))
+            });
+        }
+        sides
+    };
+
+    println!("{}:", deck_name);
+    crate::print_deck(&deck.map(Some), data);
+
+    let mut beaten = 0;
+    let mut total = 0;
+    let mut summary = vec![];
+    let groups = [
+        (format!("{}'s fixed cards:", npc_display_name), &npc.fixed),
+        (
+            format!(
+                "{}'s variable cards ({} of them are drawn):",
+                npc_display_name, npc.draws
+            ),
+            &npc.variable,
+        ),
+    ];
+    for (heading, cards) in groups {
+        if cards.is_empty() {
+            continue;
+        }
+        println!("{}", heading);
+        for row in cards.chunks(5) {
+            let mut ids = [None; 5];
+            let mut highlights = [[false; 4]; 5];
+            for (i, id) in row.iter().map(|id| id.get()).enumerate() {
+                let card = match data.get_card(id) {
+                    Some(card) => card,
+                    None => continue,
+                };
+                ids[i] = Some(id);
+                highlights[i] = beatable(card);
+
+                let sides = SIDES
+                    .iter()
+                    .filter(|&&direction| highlights[i][direction as usize])
+                    .map(|direction| direction.name())
+                    .collect::<Vec<_>>();
+                beaten += sides.len();
+                total += SIDES.len();
+                summary.push(format!(
+                    "  {}: {}",
+                    data.card_display_name(id),
+                    if sides.is_empty() {
+                        String::from("none")
+                    } else {
+                        sides.join(", ")
+                    }
+                ));
+            }
+            crate::print_deck_highlighted(&ids, data, &highlights);
+        }
+    }
+
+    let active_rules = rules.active_rule_names();
+    println!(
+        "Sides of {}'s cards your deck can beat{}{}:",
+        npc_display_name,
+        if active_rules.is_empty() {
+            String::new()
+        } else {
+            format!(" under {}", active_rules.join(", "))
+        },
+        if game::color_enabled() {
+            " (in green above)"
+        } else {
+            ""
+        }
+    );
+    for line in summary {
+        println!("{}", line);
+    }
+    println!("That's {} of their {} sides.", beaten, total);
+}
@@ -0,0 +1,1017 @@
+// Getting the sheets into the local cache: downloading them from a data source, copying them from
+// a local directory, or extracting the bundled snapshot, and keeping them up to date. None of this
+// is available in WebAssembly builds, which are given the sheets directly instead.
+
+use chrono::{DateTime, Utc};
+use inquire::Text;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use reqwest::{
+    blocking::{Client, Response},
+    header, Certificate, NoProxy, Proxy,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    fs::File,
+    io::{BufReader, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{
+    load_card_names, load_npc_names, load_sheets, Data, Language, LoadDataError, Sheet, SheetTimes,
+};
+use crate::{bundled, dirs::DataDirs, exd, prompt::Confirm};
+fn append_path<P: AsRef<Path>>(p: &Path, fname: P) -> PathBuf {
+    let mut result = p.to_path_buf();
+    result.push(fname);
+    result
+}
+
+const CACHE_METADATA_PATH: &str = "cache_meta.json";
+
+/// Sheets are read in chunks this big, fewer reads than the default on a slow disk.
+const READ_BUFFER_SIZE: usize = 1 << 16;
+
+// Downloads are written here and only moved into the cache once they've all been parsed successfully
+const STAGING_DIR: &str = "incoming";
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+const DOWNLOAD_ATTEMPTS: usize = 3;
+const DOWNLOAD_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How downloads reach the network, set from the settings with [`set_download_network`].
+struct DownloadNetwork {
+    proxy: String,
+    ca_certificate: String,
+}
+static DOWNLOAD_NETWORK: Mutex<DownloadNetwork> = Mutex::new(DownloadNetwork {
+    proxy: String::new(),
+    ca_certificate: String::new(),
+});
+
+/// Sends downloads through `proxy` (a URL like `http://proxy.example.com:8080`, which can include a
+/// username and password) and also trusts the certificates in the PEM file `ca_certificate`, for
+/// networks that inspect HTTPS traffic. Either can be empty: without a proxy, the `HTTPS_PROXY`,
+/// `HTTP_PROXY`, and `ALL_PROXY` environment variables are used, and without a certificate only the
+/// system's trusted certificates are.
+pub fn set_download_network(proxy: &str, ca_certificate: &str) {
+    let mut network = DOWNLOAD_NETWORK.lock().unwrap();
+    network.proxy = proxy.trim().to_string();
+    network.ca_certificate = ca_certificate.trim().to_string();
+}
+
+const REQUIRED_PATHS: [&str; 5] = [
+    "TripleTriadCard.csv",
+    "TripleTriadCardResident.csv",
+    "TripleTriad.csv",
+    "ENpcBase.csv",
+    "ENpcResident.csv",
+];
+
+// Sheets with extra details that the solver can do without, so they're fetched on a best-effort
+// basis and sources that don't have them can still be used
+const OPTIONAL_PATHS: [&str; 5] = [
+    "TripleTriadCardObtain.csv",
+    "Level.csv",
+    "Map.csv",
+    "PlaceName.csv",
+    "Item.csv",
+];
+
+// Sheets that have a separate copy for each language, holding the card and NPC names
+const LOCALIZED_SHEETS: [&str; 2] = ["TripleTriadCard", "ENpcResident"];
+
+impl Language {
+    /// File name of a sheet in this language. English sheets have no suffix, other languages
+    /// follow SaintCoinach's naming, like `TripleTriadCard.de.csv`.
+    fn sheet_file_name(&self, sheet: &str) -> String {
+        match *self {
+            Language::English => format!("{}.csv", sheet),
+            Language::German => format!("{}.de.csv", sheet),
+            Language::French => format!("{}.fr.csv", sheet),
+            Language::Japanese => format!("{}.ja.csv", sheet),
+        }
+    }
+
+    /// The language's code in the raw game files.
+    fn exd_code(&self) -> u8 {
+        match *self {
+            Language::Japanese => 1,
+            Language::English => 2,
+            Language::German => 3,
+            Language::French => 4,
+        }
+    }
+}
+
+/// Somewhere the card and NPC sheets can be downloaded or copied from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DataSource {
+    /// A GitHub repository laid out like xivapi/ffxiv-datamining, with the sheets in `csv/`.
+    GithubRepo {
+        owner: String,
+        repo: String,
+        branch: String,
+    },
+    /// Any URL that serves the sheets when their file names are appended to it.
+    Url(String),
+    /// A local directory holding either the sheets as CSVs (like SaintCoinach's `rawexd` output), or
+    /// the raw `.exh`/`.exd` files extracted from the game.
+    LocalDirectory(PathBuf),
+    /// The snapshot compiled into the binary with the `bundled-data` feature.
+    Bundled,
+}
+impl DataSource {
+    /// Sources that are tried, in order, when the user hasn't configured one.
+    pub fn defaults() -> Vec<DataSource> {
+        vec![
+            DataSource::GithubRepo {
+                owner: "xivapi".to_string(),
+                repo: "ffxiv-datamining".to_string(),
+                branch: "master".to_string(),
+            },
+            DataSource::Url(
+                "https://cdn.jsdelivr.net/gh/xivapi/ffxiv-datamining@master/csv/".to_string(),
+            ),
+        ]
+    }
+
+    /// Parses a URL, a path to an existing local directory, or an `owner/repo` GitHub repository name.
+    pub fn parse(input: &str) -> Option<DataSource> {
+        let input = input.trim();
+        if input.starts_with("http://") || input.starts_with("https://") {
+            Some(DataSource::Url(input.to_string()))
+        } else if !input.is_empty() && Path::new(input).is_dir() {
+            Some(DataSource::LocalDirectory(PathBuf::from(input)))
+        } else {
+            match input.split('/').collect::<Vec<_>>()[..] {
+                [owner, repo] if !owner.is_empty() && !repo.is_empty() => {
+                    Some(DataSource::GithubRepo {
+                        owner: owner.to_string(),
+                        repo: repo.to_string(),
+                        branch: "master".to_string(),
+                    })
+                }
+                _ => None,
+            }
+        }
+    }
+
+    fn base_url(&self) -> Option<String> {
+        match self {
+            DataSource::GithubRepo {
+                owner,
+                repo,
+                branch,
+            } => Some(format!(
+                "https://raw.githubusercontent.com/{}/{}/{}/csv/",
+                owner, repo, branch
+            )),
+            DataSource::Url(url) if url.ends_with('/') => Some(url.clone()),
+            DataSource::Url(url) => Some(format!("{}/", url)),
+            DataSource::LocalDirectory(_) | DataSource::Bundled => None,
+        }
+    }
+
+    /// Writes every required sheet into `destination`, returning the total number of bytes and the
+    /// version marker of each file.
+    fn fetch_into(
+        &self,
+        destination: &Path,
+    ) -> Result<(usize, HashMap<String, String>), LoadDataError> {
+        match (self, self.base_url()) {
+            (DataSource::LocalDirectory(dir), _) => {
+                let mut total_bytes = 0;
+                for fname in REQUIRED_PATHS {
+                    let sheet = fname.trim_end_matches(".csv");
+                    total_bytes += copy_local_sheet(
+                        dir,
+                        sheet,
+                        Language::English,
+                        &append_path(destination, fname),
+                    )?;
+                }
+                Ok((total_bytes, local_versions(dir)))
+            }
+            (DataSource::Bundled, _) => Ok((bundled::extract_to(destination)?, bundled_versions())),
+            (_, Some(base_url)) => download_to(&base_url, destination),
+            (_, None) => unreachable!(),
+        }
+    }
+
+    /// Writes a single sheet into `destination`, returning the size of the written file. The bundled
+    /// snapshot only has the required English sheets.
+    fn fetch_sheet(
+        &self,
+        sheet: &str,
+        language: Language,
+        destination: &Path,
+    ) -> Result<usize, LoadDataError> {
+        let fname = language.sheet_file_name(sheet);
+        let path = append_path(destination, &fname);
+        match (self, self.base_url()) {
+            (DataSource::LocalDirectory(dir), _) => copy_local_sheet(dir, sheet, language, &path),
+            (_, Some(base_url)) => {
+                let (text, _) = fetch_with_retry(&http_client()?, &(base_url + &fname))?;
+                std::fs::write(path, &text)?;
+                Ok(text.len())
+            }
+            (_, None) => Err(LoadDataError::MissingSheet(
+                sheet.to_string(),
+                PathBuf::from(self.to_string()),
+            )),
+        }
+    }
+
+    /// Writes the localized copies of the sheets holding card and NPC names into `destination`.
+    fn fetch_localized(&self, language: Language, destination: &Path) -> Result<(), LoadDataError> {
+        if matches!(self, DataSource::Bundled) {
+            return Err(LoadDataError::NoLocalizedNames(self.to_string()));
+        }
+        for sheet in LOCALIZED_SHEETS {
+            self.fetch_sheet(sheet, language, destination)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the current version marker of each required sheet, without fetching it.
+    fn current_versions(&self) -> Result<HashMap<String, String>, LoadDataError> {
+        match (self, self.base_url()) {
+            (DataSource::LocalDirectory(dir), _) => Ok(local_versions(dir)),
+            (DataSource::Bundled, _) => Ok(bundled_versions()),
+            (_, Some(base_url)) => remote_versions(&base_url),
+            (_, None) => unreachable!(),
+        }
+    }
+}
+impl Display for DataSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataSource::GithubRepo {
+                owner,
+                repo,
+                branch,
+            } => write!(f, "github.com/{}/{} ({})", owner, repo, branch),
+            DataSource::Url(url) => write!(f, "{}", url),
+            DataSource::LocalDirectory(dir) => write!(f, "{}", dir.display()),
+            DataSource::Bundled => match bundled::snapshot_date() {
+                Some(date) => write!(f, "the built-in snapshot from {}", date.date_naive()),
+                None => write!(f, "the built-in snapshot"),
+            },
+        }
+    }
+}
+
+fn download_to(
+    base_url: &str,
+    destination: &Path,
+) -> Result<(usize, HashMap<String, String>), LoadDataError> {
+    let client = http_client()?;
+    let results: Vec<(usize, Option<String>)> = REQUIRED_PATHS
+        .map(|fname| (fname, client.clone(), append_path(destination, fname)))
+        .par_iter()
+        .map(|(fname, client, destination)| {
+            let mut url = base_url.to_string();
+            url.push_str(fname);
+
+            let (text, version) = fetch_with_retry(client, &url)?;
+            let mut file = File::create(destination)?;
+            file.write_all(text.as_bytes())?;
+
+            Ok((text.len(), version))
+        })
+        .collect::<Result<_, LoadDataError>>()?;
+
+    let total_bytes = results.iter().map(|(len, _)| len).sum();
+    let versions = REQUIRED_PATHS
+        .iter()
+        .zip(results)
+        .filter_map(|(fname, (_, version))| version.map(|v| (fname.to_string(), v)))
+        .collect();
+    Ok((total_bytes, versions))
+}
+
+fn remote_versions(base_url: &str) -> Result<HashMap<String, String>, LoadDataError> {
+    let client = http_client()?;
+    let results: Vec<Option<String>> = REQUIRED_PATHS
+        .map(|fname| (fname, client.clone()))
+        .par_iter()
+        .map(|(fname, client)| {
+            let mut url = base_url.to_string();
+            url.push_str(fname);
+
+            let response = client.head(&url).send()?;
+            if !response.status().is_success() {
+                Err(LoadDataError::DownloadFailed(url, response.status().into()))
+            } else {
+                Ok(version_marker(&response))
+            }
+        })
+        .collect::<Result<_, LoadDataError>>()?;
+
+    Ok(REQUIRED_PATHS
+        .iter()
+        .zip(results)
+        .filter_map(|(fname, version)| version.map(|v| (fname.to_string(), v)))
+        .collect())
+}
+
+fn http_client() -> Result<Client, LoadDataError> {
+    let network = DOWNLOAD_NETWORK.lock().unwrap();
+    let mut builder = Client::builder().timeout(DOWNLOAD_TIMEOUT);
+    if !network.proxy.is_empty() {
+        let proxy = Proxy::all(&network.proxy)
+            .map_err(|_| LoadDataError::InvalidProxy(network.proxy.clone()))?;
+        builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+    }
+    if !network.ca_certificate.is_empty() {
+        let path = PathBuf::from(&network.ca_certificate);
+        let certificates = std::fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|pem| Certificate::from_pem_bundle(&pem).map_err(|e| e.to_string()))
+            .and_then(|certificates| match certificates.is_empty() {
+                true => Err("no certificates in the file".to_string()),
+                false => Ok(certificates),
+            })
+            .map_err(|reason| LoadDataError::CaCertificate(path, reason))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    Ok(builder.build()?)
+}
+
+/// Downloads a file, retrying with exponential backoff on network errors and server errors. Client
+/// errors (like a 404) fail immediately since retrying won't help.
+fn fetch_with_retry(client: &Client, url: &str) -> Result<(String, Option<String>), LoadDataError> {
+    let mut backoff = DOWNLOAD_INITIAL_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let result = client
+            .get(url)
+            .send()
+            .map_err(LoadDataError::from)
+            .and_then(|response| {
+                if !response.status().is_success() {
+                    Err(LoadDataError::DownloadFailed(
+                        url.to_string(),
+                        response.status().into(),
+                    ))
+                } else {
+                    let version = version_marker(&response);
+                    Ok((response.text()?, version))
+                }
+            });
+
+        let retryable = match result {
+            Err(LoadDataError::DownloadFailed(_, status)) => status >= 500,
+            Err(LoadDataError::NetworkError(_)) => true,
+            _ => false,
+        };
+        if !retryable || attempt >= DOWNLOAD_ATTEMPTS {
+            return result;
+        }
+
+        std::thread::sleep(backoff);
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+/// Uses the ETag if the server sent one, or the modification date otherwise.
+fn version_marker(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get(header::ETAG)
+        .or_else(|| response.headers().get(header::LAST_MODIFIED))
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Records where the cached sheets came from and which version of each was downloaded.
+#[derive(Serialize, Deserialize)]
+struct CacheMetadata {
+    source: DataSource,
+    downloaded: DateTime<Utc>,
+    versions: HashMap<String, String>,
+    /// How many lines each required sheet had when it was downloaded, to catch cached copies that
+    /// were cut short later. Missing for caches from before it was recorded.
+    #[serde(default)]
+    rows: HashMap<String, usize>,
+}
+impl CacheMetadata {
+    fn load(cache_path: &Path) -> Option<CacheMetadata> {
+        let file = File::open(append_path(cache_path, CACHE_METADATA_PATH)).ok()?;
+        serde_json::from_reader(file).ok()
+    }
+
+    fn save(&self, cache_path: &Path) -> Result<(), LoadDataError> {
+        let file = File::create(append_path(cache_path, CACHE_METADATA_PATH))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// What changed when the cached data was refreshed.
+pub struct DataUpdate {
+    pub new_cards: usize,
+    pub new_npcs: usize,
+}
+
+/// Copies one sheet out of a local directory, converting it from the raw game format if there's no
+/// CSV version. Returns the size of the written file.
+fn copy_local_sheet(
+    dir: &Path,
+    sheet: &str,
+    language: Language,
+    destination: &Path,
+) -> Result<usize, LoadDataError> {
+    let csv_path = append_path(dir, language.sheet_file_name(sheet));
+    if csv_path.exists() {
+        Ok(std::fs::copy(csv_path, destination)? as usize)
+    } else if let Some(sheet_dir) = exd::find_sheet_dir(dir, sheet) {
+        exd::export_sheet_csv(
+            &sheet_dir,
+            sheet,
+            language.exd_code(),
+            &sheet_column_names(sheet),
+            destination,
+        )?;
+        Ok(std::fs::metadata(destination)?.len() as usize)
+    } else {
+        Err(LoadDataError::MissingSheet(
+            sheet.to_string(),
+            dir.to_path_buf(),
+        ))
+    }
+}
+
+/// Uses each sheet's modification time as its version in a local directory.
+fn local_versions(dir: &Path) -> HashMap<String, String> {
+    REQUIRED_PATHS
+        .iter()
+        .filter_map(|fname| {
+            let sheet = fname.trim_end_matches(".csv");
+            let path = Some(append_path(dir, fname))
+                .filter(|path| path.exists())
+                .or_else(|| {
+                    exd::find_sheet_dir(dir, sheet)
+                        .map(|sheet_dir| append_path(&sheet_dir, format!("{}.exh", sheet)))
+                })?;
+            let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+            Some((
+                fname.to_string(),
+                DateTime::<Utc>::from(modified).to_rfc3339(),
+            ))
+        })
+        .collect()
+}
+
+/// Every bundled sheet has the snapshot date as its version.
+fn bundled_versions() -> HashMap<String, String> {
+    bundled::snapshot_date()
+        .map(|date| {
+            REQUIRED_PATHS
+                .iter()
+                .map(|fname| (fname.to_string(), date.to_rfc3339()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names for the columns of the raw sheets, in order, matching the headers of the datamined CSVs.
+/// Only the columns the solver reads need to be right; the rest are left blank.
+fn sheet_column_names(sheet: &str) -> Vec<String> {
+    let named = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    match sheet {
+        "TripleTriadCard" => named(&["Name", "", "", "", "", "", "", "", "Description"]),
+        "TripleTriadCardResident" => named(&[
+            "",
+            "Top",
+            "Bottom",
+            "Left",
+            "Right",
+            "TripleTriadCardRarity",
+            "TripleTriadCardType",
+            "SaleValue",
+            "SortKey",
+            "Order",
+            "UIPriority",
+            "AcquisitionType",
+            "Acquisition",
+            "Location",
+            "Quest",
+        ]),
+        "TripleTriadCardObtain" => named(&["Icon", "Text"]),
+        "Level" => named(&[
+            "X",
+            "Y",
+            "Z",
+            "Yaw",
+            "Radius",
+            "Type",
+            "Object",
+            "Map",
+            "EventId",
+            "Territory",
+        ]),
+        "Map" => named(&[
+            "MapCondition",
+            "PriorityCategoryUI",
+            "PriorityUI",
+            "MapIndex",
+            "Hierarchy",
+            "MapMarkerRange",
+            "Id",
+            "SizeFactor",
+            "Offset{X}",
+            "Offset{Y}",
+            "PlaceName{Region}",
+            "PlaceName",
+            "PlaceName{Sub}",
+            "DiscoveryIndex",
+            "DiscoveryFlag",
+            "TerritoryType",
+        ]),
+        "PlaceName" => named(&["Name"]),
+        "Item" => named(&[
+            "Singular",
+            "Adjective",
+            "Plural",
+            "PossessivePronoun",
+            "StartsWithVowel",
+            "",
+            "Pronoun",
+            "Article",
+            "Description",
+            "Name",
+        ]),
+        "TripleTriad" => (0..5)
+            .map(|i| format!("TripleTriadCard{{Fixed}}[{}]", i))
+            .chain((0..5).map(|i| format!("TripleTriadCard{{Variable}}[{}]", i)))
+            .chain((0..2).map(|i| format!("TripleTriadRule[{}]", i)))
+            .chain(
+                ["UsesRegionalRules", "Fee", "PreviousQuestJoin"]
+                    .iter()
+                    .map(|name| name.to_string()),
+            )
+            .chain((0..3).map(|i| format!("PreviousQuest[{}]", i)))
+            .chain(
+                [
+                    "StartTime",
+                    "EndTime",
+                    "DefaultTalk{Challenge}",
+                    "DefaultTalk{Unavailable}",
+                    "DefaultTalk{NPCWin}",
+                    "DefaultTalk{Draw}",
+                    "DefaultTalk{PCWin}",
+                    "",
+                ]
+                .iter()
+                .map(|name| name.to_string()),
+            )
+            .chain((0..4).map(|i| format!("Item{{PossibleReward}}[{}]", i)))
+            .collect(),
+        "ENpcBase" => vec!["EventHandler".to_string(), "Important".to_string()]
+            .into_iter()
+            .chain((0..32).map(|i| format!("ENpcData[{}]", i)))
+            .collect(),
+        "ENpcResident" => named(&[
+            "Singular",
+            "Adjective",
+            "Plural",
+            "PossessivePronoun",
+            "StartsWithVowel",
+            "",
+            "Pronoun",
+            "Article",
+            "Title",
+            "Map",
+        ]),
+        _ => vec![],
+    }
+}
+
+impl Data {
+    /// Loads the cached card and NPC data, downloading it first if there isn't any. With `verbose`,
+    /// also prints how long each sheet took to load.
+    pub fn new(
+        data_dirs: &DataDirs,
+        data_source_url: &str,
+        language: Language,
+        verbose: bool,
+    ) -> Result<Self, LoadDataError> {
+        let cache_path = data_dirs.cache_dir();
+        let required_paths = REQUIRED_PATHS.map(|fname| append_path(cache_path, fname));
+        let cache_exists = required_paths.iter().all(|p| p.exists());
+
+        // Use the bundled snapshot if it's newer than whatever was downloaded before
+        if let Some(snapshot_date) = bundled::snapshot_date() {
+            let cache_date = CacheMetadata::load(cache_path).map(|metadata| metadata.downloaded);
+            if !cache_exists || cache_date.is_some_and(|date| date < snapshot_date) {
+                std::fs::create_dir_all(cache_path)?;
+                try_download(&DataSource::Bundled, cache_path);
+            }
+        }
+
+        if !required_paths.iter().all(|p| p.exists()) {
+            std::fs::create_dir_all(cache_path)?;
+
+            println!("This is the first time the solver has run on this computer, and it needs to download Triple Triad card and NPC data.");
+            download_from_any_source(cache_path, data_source_url);
+        }
+
+        println!("Loading all card and NPC data...");
+        let start = Instant::now();
+        let times = verbose.then(SheetTimes::default);
+        let mut result = match load_cache(cache_path, times.as_ref()) {
+            Ok(data) => data,
+            Err(e) => {
+                println!(
+                    "The cached card and NPC data is damaged ({}), maybe because a download was interrupted.",
+                    e
+                );
+                let refetch = Confirm::new("Delete it and fetch the data again?")
+                    .with_default(true)
+                    .prompt()
+                    .unwrap();
+                if !refetch {
+                    return Err(e);
+                }
+                clear_cache(cache_path)?;
+                let from_snapshot = bundled::snapshot_date().is_some()
+                    && try_download(&DataSource::Bundled, cache_path);
+                if !from_snapshot {
+                    download_from_any_source(cache_path, data_source_url);
+                }
+                if let Some(times) = &times {
+                    times.lock().unwrap().clear();
+                }
+                load_cache(cache_path, times.as_ref())?
+            }
+        };
+        result.set_language(data_dirs, data_source_url, language);
+        println!("Loaded data in {:?}", Instant::now() - start);
+        match result.dropped_npcs.len() {
+            0 => {}
+            1 => println!(
+                "1 NPC was left out of the data; run `triple_triad_solver diagnostics` to see which and why."
+            ),
+            count => println!(
+                "{} NPCs were left out of the data; run `triple_triad_solver diagnostics` to see which and why.",
+                count
+            ),
+        }
+        if let Some(times) = times {
+            // Slowest first. They overlap, so they add up to more than the total.
+            let mut times = times.into_inner().unwrap();
+            times.sort_by(|(_, a), (_, b)| b.cmp(a));
+            for (sheet, time) in times {
+                println!("  {}: {:?}", sheet, time);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Switches the language card and NPC names are shown in, fetching the localized sheets if they
+    /// aren't cached yet. Falls back to English names if they can't be found.
+    pub fn set_language(
+        &mut self,
+        data_dirs: &DataDirs,
+        data_source_url: &str,
+        language: Language,
+    ) {
+        self.language = Language::English;
+        self.localized_card_names.clear();
+        self.localized_npc_names.clear();
+
+        if language != Language::English {
+            let cache_path = data_dirs.cache_dir();
+            let cached = LOCALIZED_SHEETS
+                .iter()
+                .all(|sheet| append_path(cache_path, language.sheet_file_name(sheet)).exists());
+            let result = if cached {
+                Ok(())
+            } else {
+                let source = update_source(cache_path, data_source_url);
+                println!("Fetching {} names from {}...", language, source);
+                source.fetch_localized(language, cache_path)
+            };
+
+            match result.and_then(|_| self.load_localized_names(cache_path, language)) {
+                Ok(()) => self.language = language,
+                Err(e) => println!(
+                    "Couldn't load {} names ({}), showing English names instead.",
+                    language, e
+                ),
+            }
+        }
+
+        self.build_aliases();
+    }
+
+    fn load_localized_names(
+        &mut self,
+        base_path: &Path,
+        language: Language,
+    ) -> Result<(), LoadDataError> {
+        let card_names = load_card_names(Sheet::open(append_path(
+            base_path,
+            language.sheet_file_name("TripleTriadCard"),
+        ))?)?;
+        self.localized_card_names = card_names
+            .into_iter()
+            .filter(|(id, name)| !name.is_empty() && self.card_names.contains_key(id))
+            .collect();
+
+        let mut npc_names = load_npc_names(
+            Sheet::open(append_path(
+                base_path,
+                language.sheet_file_name("ENpcResident"),
+            ))?,
+            &self
+                .npcs_by_name
+                .values()
+                .map(|npc| npc.resident_id)
+                .collect(),
+        )?;
+        self.localized_npc_names = self
+            .npcs_by_name
+            .iter()
+            .filter_map(|(name, npc)| Some((name.clone(), npc_names.remove(&npc.resident_id)?)))
+            .collect();
+
+        Ok(())
+    }
+
+    /// Compares the cached sheets against the data source they were downloaded from, and
+    /// re-downloads and reloads them if any changed. Returns `None` if the cache is up to date.
+    pub fn check_for_updates(
+        &mut self,
+        data_dirs: &DataDirs,
+        data_source_url: &str,
+    ) -> Result<Option<DataUpdate>, LoadDataError> {
+        let cache_path = data_dirs.cache_dir();
+        let metadata = CacheMetadata::load(cache_path);
+        let source = update_source(cache_path, data_source_url);
+
+        println!("Checking {} for updates...", source);
+        let remote = source.current_versions()?;
+        let up_to_date = metadata.is_some_and(|metadata| {
+            !remote.is_empty()
+                && REQUIRED_PATHS
+                    .iter()
+                    .all(|fname| remote.get(*fname) == metadata.versions.get(*fname))
+        });
+        if up_to_date {
+            return Ok(None);
+        }
+
+        if !try_download(&source, cache_path) {
+            return Err(LoadDataError::UpdateFailed);
+        }
+
+        let mut updated = load_all_data(cache_path, None)?;
+        let new_cards = updated
+            .card_names
+            .keys()
+            .filter(|id| !self.card_names.contains_key(id))
+            .count();
+        let new_npcs = updated
+            .npcs_by_name
+            .keys()
+            .filter(|name| !self.npcs_by_name.contains_key(*name))
+            .count();
+
+        // The localized names may be out of date too
+        for language in Language::ALL {
+            for sheet in LOCALIZED_SHEETS {
+                let path = append_path(cache_path, language.sheet_file_name(sheet));
+                if language != Language::English && path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+            }
+        }
+        updated.set_language(data_dirs, data_source_url, self.language);
+
+        *self = updated;
+        Ok(Some(DataUpdate {
+            new_cards,
+            new_npcs,
+        }))
+    }
+}
+
+/// Where updates (and localized names) should come from: the source the cache was filled from, or
+/// the configured one if that was the bundled snapshot, which never changes.
+fn update_source(cache_path: &Path, data_source_url: &str) -> DataSource {
+    CacheMetadata::load(cache_path)
+        .map(|metadata| metadata.source)
+        .filter(|source| !matches!(source, DataSource::Bundled))
+        .or_else(|| DataSource::parse(data_source_url))
+        .unwrap_or_else(|| DataSource::defaults().remove(0))
+}
+
+fn try_download(source: &DataSource, cache_path: &Path) -> bool {
+    println!("Fetching data from {}...", source);
+    let start = Instant::now();
+    match download_and_install(source, cache_path) {
+        Ok(total_bytes) => {
+            let duration = Instant::now() - start;
+            let kib_per_ms = (total_bytes as f64 / 1024f64) / (duration.as_millis() as f64);
+            println!(
+                "Fetched card and NPC data in {:?} ({:.2} KiB/sec)",
+                duration,
+                kib_per_ms * 1000f64
+            );
+            true
+        }
+        Err(e) => {
+            println!("Fetching data from {} failed: {}", source, e);
+            false
+        }
+    }
+}
+
+/// Fetches every sheet into a staging directory and checks that they load before replacing the
+/// cached copies, so a failed or partial download never leaves the cache half-updated.
+fn download_and_install(source: &DataSource, cache_path: &Path) -> Result<usize, LoadDataError> {
+    let staging_path = append_path(cache_path, STAGING_DIR);
+    if staging_path.exists() {
+        std::fs::remove_dir_all(&staging_path)?;
+    }
+    std::fs::create_dir_all(&staging_path)?;
+
+    let result = source
+        .fetch_into(&staging_path)
+        .and_then(|(total_bytes, versions)| {
+            let optional_bytes: usize = OPTIONAL_PATHS
+                .iter()
+                .filter_map(|fname| {
+                    let sheet = fname.trim_end_matches(".csv");
+                    source
+                        .fetch_sheet(sheet, Language::English, &staging_path)
+                        .ok()
+                })
+                .sum();
+            load_all_data(&staging_path, None)?;
+            Ok((total_bytes + optional_bytes, versions))
+        });
+    let (total_bytes, versions) = match result {
+        Ok(downloaded) => downloaded,
+        Err(e) => {
+            std::fs::remove_dir_all(&staging_path)?;
+            return Err(e);
+        }
+    };
+
+    for fname in REQUIRED_PATHS {
+        std::fs::rename(
+            append_path(&staging_path, fname),
+            append_path(cache_path, fname),
+        )?;
+    }
+    // Don't keep optional sheets from a previous source around if this one doesn't have them. The
+    // snapshot never has them, so the ones already cached are kept when installing it.
+    let keep_cached = matches!(source, DataSource::Bundled);
+    for fname in OPTIONAL_PATHS {
+        let staged = append_path(&staging_path, fname);
+        let cached = append_path(cache_path, fname);
+        if staged.exists() {
+            std::fs::rename(staged, cached)?;
+        } else if cached.exists() && !keep_cached {
+            std::fs::remove_file(cached)?;
+        }
+    }
+    std::fs::remove_dir_all(&staging_path)?;
+
+    let mut rows = HashMap::new();
+    for fname in REQUIRED_PATHS {
+        rows.insert(
+            fname.to_string(),
+            count_rows(&append_path(cache_path, fname))?,
+        );
+    }
+    CacheMetadata {
+        source: source.clone(),
+        downloaded: Utc::now(),
+        versions,
+        rows,
+    }
+    .save(cache_path)?;
+
+    Ok(total_bytes)
+}
+
+fn count_rows(path: &Path) -> Result<usize, LoadDataError> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    let mut rows = 0;
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(rows),
+            Ok(read) => rows += buffer[..read].iter().filter(|&&byte| byte == b'\n').count(),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Loads the cached sheets, first checking that none of them has fewer rows than were downloaded.
+fn load_cache(cache_path: &Path, times: Option<&SheetTimes>) -> Result<Data, LoadDataError> {
+    if let Some(metadata) = CacheMetadata::load(cache_path) {
+        metadata
+            .rows
+            .par_iter()
+            .try_for_each(|(fname, &expected)| {
+                let rows = count_rows(&append_path(cache_path, fname))?;
+                if rows != expected {
+                    return Err(LoadDataError::Truncated {
+                        sheet: fname.trim_end_matches(".csv").to_string(),
+                        rows,
+                        expected,
+                    });
+                }
+                Ok(())
+            })?;
+    }
+    load_all_data(cache_path, times)
+}
+
+/// Deletes the cached sheets and their metadata, so they'll be fetched again from scratch.
+fn clear_cache(cache_path: &Path) -> Result<(), LoadDataError> {
+    let localized = Language::ALL.iter().flat_map(|language| {
+        LOCALIZED_SHEETS
+            .iter()
+            .map(move |sheet| language.sheet_file_name(sheet))
+    });
+    let fnames = REQUIRED_PATHS
+        .iter()
+        .chain(&OPTIONAL_PATHS)
+        .chain(&[CACHE_METADATA_PATH])
+        .map(|fname| fname.to_string())
+        .chain(localized);
+    for fname in fnames {
+        let path = append_path(cache_path, fname);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fills the cache from the configured source or one of the default ones, asking the user for
+/// another source if none of them work.
+fn download_from_any_source(cache_path: &Path, data_source_url: &str) {
+    let configured = DataSource::parse(data_source_url);
+    let mut downloaded = configured
+        .into_iter()
+        .chain(DataSource::defaults())
+        .any(|source| try_download(&source, cache_path));
+
+    // Only bother the user if none of the known sources worked
+    while !downloaded {
+        let input = Text::new(
+            "Please enter a github repository (owner/name), URL, or local directory to load data from:",
+        )
+        .prompt()
+        .unwrap();
+        match DataSource::parse(&input) {
+            Some(source) => downloaded = try_download(&source, cache_path),
+            None => println!("That isn't a repository name, URL, or directory."),
+        }
+    }
+}
+
+/// Loads the sheets from a directory of CSVs, like the cache, timing each one with `times`.
+fn load_all_data(base_path: &Path, times: Option<&SheetTimes>) -> Result<Data, LoadDataError> {
+    load_sheets(
+        |name| match File::open(append_path(base_path, format!("{}.csv", name))) {
+            Ok(file) => Sheet::from_reader(
+                name.to_string(),
+                BufReader::with_capacity(READ_BUFFER_SIZE, file),
+            )
+            .map(Some),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        },
+        times,
+    )
+}
+
+impl Sheet<'static> {
+    fn open<P: AsRef<Path>>(path: P) -> Result<Self, LoadDataError> {
+        let name = path
+            .as_ref()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Sheet::from_reader(
+            name,
+            BufReader::with_capacity(READ_BUFFER_SIZE, File::open(path)?),
+        )
+    }
+}
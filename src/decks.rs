@@ -1,9 +1,13 @@
 use chrono::{DateTime, Utc};
-use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
+use crate::{stats::MatchResult, store};
+
 #[derive(Debug, Error)]
 pub enum SavedDeckError {
     #[error("Could not read/write config file")]
@@ -13,26 +17,40 @@ pub enum SavedDeckError {
     SerdeError(#[from] serde_json::Error),
 }
 
+/// How many of the decks played most recently are remembered.
+const RECENT_DECKS: usize = 3;
+
 #[derive(Serialize, Deserialize)]
 pub struct SavedDecks {
     decks: HashMap<String, Deck>,
 
+    /// The deck last played against each NPC, by English name.
+    #[serde(default)]
+    last_used: HashMap<String, LastUsed>,
+
+    /// The decks played most recently against any NPC, the latest first.
+    #[serde(default)]
+    recent: Vec<String>,
+
     #[serde(skip)]
     config_path: PathBuf,
 }
 impl SavedDecks {
-    pub fn new(project_dirs: &ProjectDirs) -> Result<Self, SavedDeckError> {
-        let mut config_path = project_dirs.config_dir().to_path_buf();
-        config_path.push("decks.json");
+    pub fn new(profile_dir: &Path) -> Result<Self, SavedDeckError> {
+        let config_path = profile_dir.join("decks.json");
 
-        if config_path.exists() {
-            let mut result: SavedDecks = serde_json::from_reader(File::open(&config_path)?)?;
+        let saved = store::load(&config_path, |text| {
+            serde_json::from_str::<SavedDecks>(text).map_err(SavedDeckError::from)
+        })?;
+        if let Some(mut result) = saved {
             result.config_path = config_path;
             Ok(result)
         } else {
             std::fs::create_dir_all(config_path.parent().unwrap())?;
             let result = SavedDecks {
                 decks: HashMap::new(),
+                last_used: HashMap::new(),
+                recent: Vec::new(),
                 config_path,
             };
             result.save()?;
@@ -41,11 +59,23 @@ impl SavedDecks {
     }
 
     pub fn add_deck(&mut self, name: String, cards: [i32; 5]) -> Result<(), SavedDeckError> {
+        self.add_tagged_deck(name, cards, None)
+    }
+
+    /// Adds a deck with a tag saying what it's for, like the tournament it was built for.
+    pub fn add_tagged_deck(
+        &mut self,
+        name: String,
+        cards: [i32; 5],
+        tag: Option<String>,
+    ) -> Result<(), SavedDeckError> {
         self.decks.insert(
             name,
             Deck {
                 created: Utc::now(),
                 cards,
+                tag,
+                favorite: false,
             },
         );
         self.save()?;
@@ -54,13 +84,19 @@ impl SavedDecks {
 
     pub fn remove_deck(&mut self, name: &str) -> Result<(), SavedDeckError> {
         self.decks.remove(name);
+        self.last_used.retain(|_, last_used| last_used.deck != name);
+        self.recent.retain(|recent| recent != name);
         self.save()?;
         Ok(())
     }
 
     pub fn get_deck(&self, name: &str) -> Result<[i32; 5], SavedDeckError> {
         let deck = self.decks.get(name).unwrap();
-        Ok(deck.cards.clone())
+        Ok(deck.cards)
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.decks.get(name).and_then(|deck| deck.tag.as_deref())
     }
 
     pub fn get_deck_names(&self) -> Vec<String> {
@@ -71,14 +107,141 @@ impl SavedDecks {
         self.decks.len()
     }
 
+    pub fn is_favorite(&self, name: &str) -> bool {
+        self.decks.get(name).is_some_and(|deck| deck.favorite)
+    }
+
+    /// Stars or unstars a deck, to list it near the top when picking a deck.
+    pub fn set_favorite(&mut self, name: &str, favorite: bool) -> Result<(), SavedDeckError> {
+        if let Some(deck) = self.decks.get_mut(name) {
+            deck.favorite = favorite;
+        }
+        self.save()
+    }
+
+    /// Whether `name` is one of the decks played most recently.
+    pub fn is_recent(&self, name: &str) -> bool {
+        self.recent.iter().any(|recent| recent == name)
+    }
+
+    /// Every deck name in the order to offer them in: the decks played most recently, latest
+    /// first, then the favorites, then the rest, each of those two by name.
+    pub fn get_deck_names_by_use(&self) -> Vec<String> {
+        let mut rest = self
+            .get_deck_names()
+            .into_iter()
+            .filter(|name| !self.is_recent(name))
+            .collect::<Vec<_>>();
+        rest.sort_by(|a, b| (!self.is_favorite(a), a).cmp(&(!self.is_favorite(b), b)));
+        self.recent.iter().cloned().chain(rest).collect()
+    }
+
+    /// The deck last played against `npc_name`, and how that match went.
+    pub fn last_used(&self, npc_name: &str) -> Option<&LastUsed> {
+        self.last_used.get(npc_name)
+    }
+
+    /// Remembers that `deck_name` was played against `npc_name`, and that it's the deck played most
+    /// recently, unless it isn't a registered deck.
+    pub fn record_last_used(
+        &mut self,
+        npc_name: &str,
+        deck_name: &str,
+        result: MatchResult,
+    ) -> Result<(), SavedDeckError> {
+        if !self.decks.contains_key(deck_name) {
+            return Ok(());
+        }
+        self.last_used.insert(
+            npc_name.to_string(),
+            LastUsed {
+                deck: deck_name.to_string(),
+                result,
+            },
+        );
+        self.recent.retain(|recent| recent != deck_name);
+        self.recent.insert(0, deck_name.to_string());
+        self.recent.truncate(RECENT_DECKS);
+        self.save()
+    }
+
+    /// Adds the decks from `imported` with cards that no deck here has, skipping any that play a card
+    /// `known_card` doesn't know. A deck named like a different one here gets "(imported)" after its
+    /// name.
+    pub fn merge(
+        &mut self,
+        imported: SavedDecks,
+        known_card: impl Fn(i32) -> bool,
+    ) -> Result<DeckMerge, SavedDeckError> {
+        let mut result = DeckMerge::default();
+        for (name, deck) in imported.decks {
+            if !deck.cards.iter().all(|&id| known_card(id)) {
+                result.unknown_cards.push(name);
+                continue;
+            }
+            if self
+                .decks
+                .values()
+                .any(|existing| existing.cards == deck.cards)
+            {
+                continue;
+            }
+
+            let mut new_name = name.clone();
+            let mut copies = 1;
+            while self.decks.contains_key(&new_name) {
+                new_name = match copies {
+                    1 => format!("{} (imported)", name),
+                    _ => format!("{} (imported {})", name, copies),
+                };
+                copies += 1;
+            }
+            if new_name != name {
+                result.renamed += 1;
+            }
+            self.decks.insert(new_name, deck);
+            result.added += 1;
+        }
+        for (npc_name, last_used) in imported.last_used {
+            if self.decks.contains_key(&last_used.deck) {
+                self.last_used.entry(npc_name).or_insert(last_used);
+            }
+        }
+        self.save()?;
+        Ok(result)
+    }
+
     fn save(&self) -> Result<(), SavedDeckError> {
-        serde_json::to_writer_pretty(File::create(&self.config_path)?, self)?;
+        store::save(
+            &self.config_path,
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
         Ok(())
     }
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct LastUsed {
+    pub deck: String,
+    pub result: MatchResult,
+}
+
+/// What [`SavedDecks::merge`] did.
+#[derive(Default)]
+pub struct DeckMerge {
+    pub added: usize,
+    /// How many of the added decks were renamed to keep them apart from decks already here.
+    pub renamed: usize,
+    /// Names of the decks that weren't added because they play cards that weren't recognized.
+    pub unknown_cards: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 struct Deck {
     created: DateTime<Utc>,
     cards: [i32; 5],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    favorite: bool,
 }
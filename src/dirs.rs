@@ -0,0 +1,77 @@
+//! Where the settings, profiles, saved decks, and card data cache are stored: the platform's usual
+//! config and cache directories, or a single directory picked with `--data-dir` or the
+//! `TRIPLE_TRIAD_SOLVER_DATA_DIR` environment variable (for portable installs, shared drives, and
+//! testing).
+
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// The command line option that overrides the data directory.
+pub const DATA_DIR_ARG: &str = "--data-dir";
+/// The environment variable that overrides the data directory, when `--data-dir` isn't given.
+pub const DATA_DIR_VAR: &str = "TRIPLE_TRIAD_SOLVER_DATA_DIR";
+
+pub struct DataDirs {
+    config_dir: PathBuf,
+    cache_dir: PathBuf,
+}
+impl DataDirs {
+    /// Settings, profiles, and decks go straight into `dir`, and the card data cache into a
+    /// `cache` directory inside it.
+    pub fn in_dir(dir: &Path) -> DataDirs {
+        DataDirs {
+            config_dir: dir.to_path_buf(),
+            cache_dir: dir.join("cache"),
+        }
+    }
+
+    /// Takes `--data-dir DIR` (or `--data-dir=DIR`) out of `args`, falling back on the environment
+    /// variable and then the platform's usual directories.
+    pub fn from_args(args: &mut Vec<String>) -> Result<DataDirs, String> {
+        let mut dir = None;
+        let mut i = 0;
+        while i < args.len() {
+            if args[i] == DATA_DIR_ARG {
+                if i + 1 == args.len() {
+                    return Err(format!("{} needs a directory", DATA_DIR_ARG));
+                }
+                dir = Some(PathBuf::from(args.remove(i + 1)));
+                args.remove(i);
+            } else if let Some(value) = args[i].strip_prefix(&format!("{}=", DATA_DIR_ARG)) {
+                dir = Some(PathBuf::from(value));
+                args.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+
+        let dir = dir.or_else(|| {
+            std::env::var_os(DATA_DIR_VAR)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        });
+        match dir {
+            Some(dir) => Ok(DataDirs::in_dir(&dir)),
+            None => ProjectDirs::from("com", "ununoctium", "TripleTriadSolver")
+                .map(|project_dirs| DataDirs {
+                    config_dir: project_dirs.config_dir().to_path_buf(),
+                    cache_dir: project_dirs.cache_dir().to_path_buf(),
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Could not find a home directory; use {} or set {}",
+                        DATA_DIR_ARG, DATA_DIR_VAR
+                    )
+                }),
+        }
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+}
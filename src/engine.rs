@@ -0,0 +1,313 @@
+// The `engine` subcommand: a line protocol on stdin/stdout, modeled on chess's UCI, so other
+// programs can run the solver as a subprocess. Commands, one per line:
+//
+//   tti                      identifies the engine and its options, ending with `ttiok`
+//   isready                  answered with `readyok` once earlier commands are done
+//   setoption name <name> value <value>
+//                            sets MonteCarloIterations, PlayoutBudget (the Monte Carlo playouts
+//                            for a whole tiebreak, shared out between the tied moves; 0 allows
+//                            MonteCarloIterations for each), TiebreakConfidence (how sure the
+//                            tiebreak has to be that a move is worse before dropping it, from 0.5
+//                            to 1), TieValue, SymmetryPruning (`true` or `false`), Opponent (how
+//                            the opponent is expected to play: Perfect, GreedyFlips, or Random),
+//                            Seed (a number that makes the Monte Carlo playouts come out the same
+//                            every time, or `none`), or MultiPV (how many of the best moves `go`
+//                            reports) for the following searches
+//   position <notation> [moves <card>@<cell> ...]
+//                            sets the position, in the one-line notation (see the `solve` module),
+//                            then plays the given moves from it, alternating sides, with flips
+//                            worked out here. Cells are written like `B2`.
+//   go [movetime <ms>] [iterations <n>]
+//                            searches the position for the player whose hand it gave, printing
+//                            `info score <score> [wdl <win> <tie> <loss>]` and then
+//                            `bestmove <card>@<cell>`, or `bestmove none` when there's nothing to
+//                            play. `wdl` is only given when the Monte Carlo tiebreak ran, as how
+//                            many of its playouts per thousand were won, tied, and lost. With
+//                            MultiPV above 1, each of the best moves gets its own
+//                            `info multipv <rank> score <score> [wdl ...] pv <card>@<cell> ...`
+//                            line instead, with the line the search expects after it
+//   quit
+//
+// Problems are reported as `info string <message>` lines (followed by `bestmove none` for `go`, so
+// it always gets an answer), and unknown commands are ignored. Data loading can print a few lines
+// before the first command is read, so clients should skip anything before `ttiok`, like UCI
+// clients do before `uciok`.
+
+use std::{
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+use triple_triad_solver::{
+    data::Data,
+    game::{self, CardId, Game, GameMove, Player},
+    search::{self, GamePlayer, Opponent, OutcomeRates, SearchConfig, SearchableGame, WinState},
+    solve::{self, Position},
+};
+
+use crate::parse_cell;
+
+struct EnginePosition {
+    game: Game,
+    player: Player,
+    to_move: Player,
+}
+
+pub fn run(data: &Data, search_config: &SearchConfig) {
+    let mut search_config = search_config.clone();
+    let mut multi_pv = 1;
+    let mut position = None;
+
+    for line in io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let line = line.trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("tti") => {
+                println!("id name triple_triad_solver {}", env!("CARGO_PKG_VERSION"));
+                println!(
+                    "option name MonteCarloIterations type spin default {}",
+                    search_config.monte_carlo_iterations
+                );
+                println!(
+                    "option name PlayoutBudget type spin default {} min 0",
+                    search_config.playout_budget.unwrap_or(0)
+                );
+                println!(
+                    "option name TiebreakConfidence type string default {}",
+                    search_config.tiebreak_confidence
+                );
+                println!(
+                    "option name TieValue type string default {}",
+                    search_config.tie_value
+                );
+                println!(
+                    "option name SymmetryPruning type check default {}",
+                    search_config.symmetry_pruning
+                );
+                println!(
+                    "option name Opponent type combo default {:?}{}",
+                    search_config.opponent,
+                    Opponent::ALL
+                        .iter()
+                        .map(|opponent| format!(" var {:?}", opponent))
+                        .collect::<String>()
+                );
+                println!(
+                    "option name Seed type string default {}",
+                    search_config
+                        .seed
+                        .map_or_else(|| "none".to_string(), |seed| seed.to_string())
+                );
+                println!("option name MultiPV type spin default {} min 1", multi_pv);
+                println!("ttiok");
+            }
+            Some("isready") => println!("readyok"),
+            Some("setoption") => {
+                if let Err(message) = set_option(line, &mut search_config, &mut multi_pv) {
+                    println!("info string {}", message);
+                }
+            }
+            Some("position") => match set_position(&line["position".len()..], data) {
+                Ok(new_position) => position = Some(new_position),
+                Err(message) => {
+                    position = None;
+                    println!("info string {}", message);
+                }
+            },
+            Some("go") => match &position {
+                Some(position) => go(position, words, &search_config, multi_pv),
+                None => {
+                    println!("info string no position has been set");
+                    println!("bestmove none");
+                }
+            },
+            Some("quit") => break,
+            _ => {}
+        }
+        // Whoever's driving the engine is waiting on this output, which stdout would otherwise
+        // hold onto when it's a pipe
+        let _ = io::stdout().flush();
+    }
+}
+
+fn set_option(
+    line: &str,
+    search_config: &mut SearchConfig,
+    multi_pv: &mut usize,
+) -> Result<(), String> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let (name, value) = match words.as_slice() {
+        ["setoption", "name", name, "value", value] => (*name, *value),
+        _ => return Err("expected setoption name <name> value <value>".to_string()),
+    };
+    let invalid = || format!("invalid value {} for {}", value, name);
+    match name {
+        "MonteCarloIterations" => {
+            search_config.monte_carlo_iterations = value.parse().map_err(|_| invalid())?
+        }
+        "PlayoutBudget" => {
+            let budget: usize = value.parse().map_err(|_| invalid())?;
+            search_config.playout_budget = (budget != 0).then_some(budget);
+        }
+        "TiebreakConfidence" => {
+            search_config.tiebreak_confidence = value
+                .parse()
+                .ok()
+                .filter(|confidence| (0.5..=1.0).contains(confidence))
+                .ok_or_else(invalid)?
+        }
+        "Seed" => {
+            search_config.seed = match value {
+                "none" => None,
+                seed => Some(seed.parse().map_err(|_| invalid())?),
+            }
+        }
+        "TieValue" => search_config.tie_value = value.parse().map_err(|_| invalid())?,
+        "SymmetryPruning" => {
+            search_config.symmetry_pruning = value.parse().map_err(|_| invalid())?
+        }
+        "Opponent" => {
+            search_config.opponent = *Opponent::ALL
+                .iter()
+                .find(|opponent| format!("{:?}", opponent) == value)
+                .ok_or_else(invalid)?
+        }
+        "MultiPV" => match value.parse() {
+            Ok(count) if count >= 1 => *multi_pv = count,
+            _ => return Err(invalid()),
+        },
+        _ => return Err(format!("unknown option {}", name)),
+    }
+    Ok(())
+}
+
+fn set_position(args: &str, data: &Data) -> Result<EnginePosition, String> {
+    let (notation, moves) = match args.split_once(" moves ") {
+        Some((notation, moves)) => (notation, moves),
+        None => (args, ""),
+    };
+    let start = notation
+        .trim()
+        .parse::<Position>()
+        .map_err(|e| e.to_string())?;
+    let mut position = EnginePosition {
+        game: solve::game_for_position(data, &start).map_err(|e| e.to_string())?,
+        player: start.player(),
+        to_move: start.to_move,
+    };
+
+    for mv in moves.split_whitespace() {
+        let (card, cell) = mv
+            .split_once('@')
+            .and_then(|(card, cell)| Some((card.parse::<i32>().ok()?, parse_cell(cell)?)))
+            .ok_or_else(|| format!("invalid move {}", mv))?;
+        if position.game.board_cell(cell).is_some() {
+            return Err(format!(
+                "{} is already taken in move {}",
+                game::cell_code(cell),
+                mv
+            ));
+        }
+        let card_idx = CardId::new(card)
+            .and_then(|id| position.game.find_in_hand(position.to_move, id))
+            .ok_or_else(|| {
+                format!(
+                    "card {} isn't in {}'s hand in move {}",
+                    card, position.to_move, mv
+                )
+            })?;
+
+        position.game.apply_move(&GameMove {
+            player: position.to_move,
+            card_idx,
+            placement: cell,
+        });
+        position.to_move = position.to_move.other();
+    }
+    Ok(position)
+}
+
+fn go<'a>(
+    position: &EnginePosition,
+    mut args: impl Iterator<Item = &'a str>,
+    search_config: &SearchConfig,
+    multi_pv: usize,
+) {
+    let mut search_config = search_config.clone();
+    while let Some(arg) = args.next() {
+        let value = args.next().and_then(|value| value.parse::<u64>().ok());
+        match (arg, value) {
+            ("movetime", Some(ms)) => search_config.time_budget = Some(Duration::from_millis(ms)),
+            ("iterations", Some(n)) => search_config.monte_carlo_iterations = n as usize,
+            _ => {
+                println!("info string invalid go argument {}", arg);
+                println!("bestmove none");
+                return;
+            }
+        }
+    }
+
+    if position.to_move != position.player {
+        println!("info string it's the opponent's turn");
+        println!("bestmove none");
+        return;
+    }
+    if !matches!(position.game.win_state(), WinState::NotFinished) {
+        println!("bestmove none");
+        return;
+    }
+
+    let best_move = if multi_pv > 1 {
+        let lines = search::top_lines(&position.game, position.player, &search_config, multi_pv);
+        for (rank, line) in lines.iter().enumerate() {
+            println!(
+                "info multipv {} score {}{} pv {}",
+                rank + 1,
+                line.score,
+                wdl(line.outcomes),
+                std::iter::once(&line.mv)
+                    .chain(&line.line)
+                    .map(|mv| move_notation(&position.game, mv))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            );
+        }
+        lines.into_iter().next().map(|line| line.mv)
+    } else {
+        let (best_move, (score, rates)) =
+            search::get_best_move_for_player(&position.game, position.player, &search_config);
+        if best_move.is_some() {
+            println!("info score {}{}", score, wdl(rates));
+        }
+        best_move
+    };
+    match best_move {
+        Some(best_move) => println!("bestmove {}", move_notation(&position.game, &best_move)),
+        None => println!("bestmove none"),
+    }
+}
+
+/// ` wdl <win> <tie> <loss>` in per mille, like UCI's, when the Monte Carlo tiebreak ran.
+fn wdl(rates: Option<OutcomeRates>) -> String {
+    rates
+        .map(|rates| {
+            format!(
+                " wdl {:.0} {:.0} {:.0}",
+                rates.win * 1000.0,
+                rates.tie * 1000.0,
+                rates.loss * 1000.0
+            )
+        })
+        .unwrap_or_default()
+}
+
+fn move_notation(game: &Game, mv: &GameMove) -> String {
+    format!(
+        "{}@{}",
+        game.player_hand_card_id(mv.player, mv.card_idx),
+        game::cell_code(mv.placement)
+    )
+}
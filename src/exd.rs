@@ -0,0 +1,325 @@
+//! Minimal reader for the game's raw Excel sheets (EXH headers and EXD pages), as extracted from the
+//! game files by tools like SaintCoinach or ffxiv-explorer. Sheets are converted into the same CSV
+//! layout the datamining repositories use, so the regular loaders can read them.
+
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ExdError {
+    #[error("could not read sheet file")]
+    IoError(#[from] std::io::Error),
+
+    #[error("{0} is not a valid EXH/EXD file")]
+    BadMagic(PathBuf),
+
+    #[error("{0} is truncated")]
+    Truncated(PathBuf),
+
+    #[error("sheet {0} uses sub-rows, which aren't supported")]
+    UnsupportedVariant(String),
+
+    #[error("unknown column type {0:#x}")]
+    UnknownColumnType(u16),
+}
+
+#[derive(Clone, Copy)]
+enum ColumnType {
+    String,
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Int64,
+    UInt64,
+    PackedBool(u8),
+}
+impl ColumnType {
+    fn from_raw(raw: u16) -> Result<Self, ExdError> {
+        Ok(match raw {
+            0x0 => ColumnType::String,
+            0x1 => ColumnType::Bool,
+            0x2 => ColumnType::Int8,
+            0x3 => ColumnType::UInt8,
+            0x4 => ColumnType::Int16,
+            0x5 => ColumnType::UInt16,
+            0x6 => ColumnType::Int32,
+            0x7 => ColumnType::UInt32,
+            0x9 => ColumnType::Float32,
+            0xA => ColumnType::Int64,
+            0xB => ColumnType::UInt64,
+            0x19..=0x20 => ColumnType::PackedBool((raw - 0x19) as u8),
+            _ => return Err(ExdError::UnknownColumnType(raw)),
+        })
+    }
+
+    fn csv_type_name(&self) -> &'static str {
+        match *self {
+            ColumnType::String => "str",
+            ColumnType::Bool | ColumnType::PackedBool(_) => "bool",
+            ColumnType::Int8 => "sbyte",
+            ColumnType::UInt8 => "byte",
+            ColumnType::Int16 => "int16",
+            ColumnType::UInt16 => "uint16",
+            ColumnType::Int32 => "int32",
+            ColumnType::UInt32 => "uint32",
+            ColumnType::Float32 => "single",
+            ColumnType::Int64 => "int64",
+            ColumnType::UInt64 => "uint64",
+        }
+    }
+}
+
+struct Column {
+    kind: ColumnType,
+    offset: usize,
+}
+
+struct SheetHeader {
+    row_size: usize,
+    columns: Vec<Column>,
+    page_starts: Vec<u32>,
+    languages: Vec<u8>,
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_header(path: &Path, sheet: &str) -> Result<SheetHeader, ExdError> {
+    let bytes = std::fs::read(path)?;
+    if bytes.len() < 0x20 {
+        return Err(ExdError::Truncated(path.to_path_buf()));
+    }
+    if &bytes[0..4] != b"EXHF" {
+        return Err(ExdError::BadMagic(path.to_path_buf()));
+    }
+
+    let row_size = read_u16(&bytes, 0x06) as usize;
+    let column_count = read_u16(&bytes, 0x08) as usize;
+    let page_count = read_u16(&bytes, 0x0A) as usize;
+    let language_count = read_u16(&bytes, 0x0C) as usize;
+    if bytes[0x11] != 1 {
+        return Err(ExdError::UnsupportedVariant(sheet.to_string()));
+    }
+
+    let columns_start = 0x20;
+    let pages_start = columns_start + column_count * 4;
+    let languages_start = pages_start + page_count * 8;
+    if bytes.len() < languages_start + language_count * 2 {
+        return Err(ExdError::Truncated(path.to_path_buf()));
+    }
+
+    let columns = (0..column_count)
+        .map(|i| {
+            let at = columns_start + i * 4;
+            Ok(Column {
+                kind: ColumnType::from_raw(read_u16(&bytes, at))?,
+                offset: read_u16(&bytes, at + 2) as usize,
+            })
+        })
+        .collect::<Result<_, ExdError>>()?;
+    let page_starts = (0..page_count)
+        .map(|i| read_u32(&bytes, pages_start + i * 8))
+        .collect();
+    // Language codes are a single byte padded to two; different tools disagree on which byte is
+    // the padding, so take whichever one is set.
+    let languages = (0..language_count)
+        .map(|i| {
+            let at = languages_start + i * 2;
+            bytes[at] | bytes[at + 1]
+        })
+        .collect();
+
+    Ok(SheetHeader {
+        row_size,
+        columns,
+        page_starts,
+        languages,
+    })
+}
+
+fn language_suffix(code: u8) -> &'static str {
+    match code {
+        1 => "_ja",
+        2 => "_en",
+        3 => "_de",
+        4 => "_fr",
+        5 => "_chs",
+        6 => "_cht",
+        7 => "_ko",
+        _ => "",
+    }
+}
+
+/// Finds the directory holding the sheet's `.exh`, which is either `dir` itself or its `exd` subdirectory.
+pub fn find_sheet_dir(dir: &Path, sheet: &str) -> Option<PathBuf> {
+    vec![dir.to_path_buf(), dir.join("exd")]
+        .into_iter()
+        .find(|candidate| candidate.join(format!("{}.exh", sheet)).exists())
+}
+
+/// Strips the macro payloads (icons, colors, auto-translate entries...) out of an SeString, leaving
+/// only the plain text.
+fn decode_string(bytes: &[u8]) -> String {
+    let mut text = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == 0x02 && i + 2 < bytes.len() {
+            // 0x02, macro type, length, payload..., 0x03
+            let (length, skip) = match bytes[i + 2] {
+                n @ 0x01..=0xEF => (n as usize - 1, 1),
+                0xF0 => (bytes.get(i + 3).copied().unwrap_or(0) as usize, 2),
+                0xF1 => ((bytes.get(i + 3).copied().unwrap_or(0) as usize) << 8, 2),
+                0xF2 => (
+                    u16::from_be_bytes([
+                        bytes.get(i + 3).copied().unwrap_or(0),
+                        bytes.get(i + 4).copied().unwrap_or(0),
+                    ]) as usize,
+                    3,
+                ),
+                _ => (0, 1),
+            };
+            i += 2 + skip + length + 1;
+        } else {
+            text.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+fn format_field(
+    row: &[u8],
+    row_size: usize,
+    column: &Column,
+    path: &Path,
+) -> Result<String, ExdError> {
+    let truncated = || ExdError::Truncated(path.to_path_buf());
+    let field = |len: usize| {
+        row.get(column.offset..column.offset + len)
+            .ok_or_else(truncated)
+    };
+
+    Ok(match column.kind {
+        ColumnType::String => {
+            let start = row_size + read_u32(field(4)?, 0) as usize;
+            let bytes = row.get(start..).ok_or_else(truncated)?;
+            let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            decode_string(&bytes[..end])
+        }
+        ColumnType::Bool => (field(1)?[0] != 0).to_string(),
+        ColumnType::PackedBool(bit) => ((field(1)?[0] >> bit) & 1 != 0).to_string(),
+        ColumnType::Int8 => (field(1)?[0] as i8).to_string(),
+        ColumnType::UInt8 => field(1)?[0].to_string(),
+        ColumnType::Int16 => i16::from_be_bytes(field(2)?.try_into().unwrap()).to_string(),
+        ColumnType::UInt16 => u16::from_be_bytes(field(2)?.try_into().unwrap()).to_string(),
+        ColumnType::Int32 => i32::from_be_bytes(field(4)?.try_into().unwrap()).to_string(),
+        ColumnType::UInt32 => u32::from_be_bytes(field(4)?.try_into().unwrap()).to_string(),
+        ColumnType::Float32 => f32::from_be_bytes(field(4)?.try_into().unwrap()).to_string(),
+        ColumnType::Int64 => i64::from_be_bytes(field(8)?.try_into().unwrap()).to_string(),
+        ColumnType::UInt64 => u64::from_be_bytes(field(8)?.try_into().unwrap()).to_string(),
+    })
+}
+
+/// Converts a raw sheet into a CSV at `destination`, with the same three header lines (column
+/// indices, column names, and types) as the datamining repositories. `column_names` names the
+/// sheet's columns in order; unnamed columns are left blank. Text comes from the `language` pages
+/// (using the game's language codes) if the sheet has them, or its first language otherwise.
+pub fn export_sheet_csv(
+    sheet_dir: &Path,
+    sheet: &str,
+    language: u8,
+    column_names: &[String],
+    destination: &Path,
+) -> Result<usize, ExdError> {
+    let header = read_header(&sheet_dir.join(format!("{}.exh", sheet)), sheet)?;
+    let language = header
+        .languages
+        .iter()
+        .copied()
+        .find(|code| *code == language)
+        .or_else(|| header.languages.first().copied())
+        .unwrap_or(0);
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(BufWriter::new(File::create(destination)?));
+    let write_error = |e: csv::Error| ExdError::IoError(e.into());
+
+    writer
+        .write_record(
+            std::iter::once("key".to_string())
+                .chain((0..header.columns.len()).map(|i| i.to_string())),
+        )
+        .map_err(write_error)?;
+    writer
+        .write_record(std::iter::once("#".to_string()).chain(
+            (0..header.columns.len()).map(|i| column_names.get(i).cloned().unwrap_or_default()),
+        ))
+        .map_err(write_error)?;
+    writer
+        .write_record(
+            std::iter::once("int32").chain(header.columns.iter().map(|c| c.kind.csv_type_name())),
+        )
+        .map_err(write_error)?;
+
+    let mut row_count = 0;
+    for page_start in &header.page_starts {
+        let path = sheet_dir.join(format!(
+            "{}_{}{}.exd",
+            sheet,
+            page_start,
+            language_suffix(language)
+        ));
+        let bytes = std::fs::read(&path)?;
+        if bytes.len() < 0x20 {
+            return Err(ExdError::Truncated(path));
+        }
+        if &bytes[0..4] != b"EXDF" {
+            return Err(ExdError::BadMagic(path));
+        }
+
+        let index_size = read_u32(&bytes, 0x08) as usize;
+        if bytes.len() < 0x20 + index_size {
+            return Err(ExdError::Truncated(path));
+        }
+        for entry in (0x20..0x20 + index_size).step_by(8) {
+            let row_id = read_u32(&bytes, entry);
+            let offset = read_u32(&bytes, entry + 4) as usize;
+            if bytes.len() < offset + 6 {
+                return Err(ExdError::Truncated(path));
+            }
+            let data_size = read_u32(&bytes, offset) as usize;
+            let row = bytes
+                .get(offset + 6..offset + 6 + data_size)
+                .ok_or_else(|| ExdError::Truncated(path.clone()))?;
+
+            let fields = header
+                .columns
+                .iter()
+                .map(|column| format_field(row, header.row_size, column, &path))
+                .collect::<Result<Vec<_>, ExdError>>()?;
+            writer
+                .write_record(std::iter::once(row_id.to_string()).chain(fields))
+                .map_err(write_error)?;
+            row_count += 1;
+        }
+    }
+
+    writer.flush()?;
+    Ok(row_count)
+}
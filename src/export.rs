@@ -0,0 +1,301 @@
+//! Writes a position, or a match move by move, out for sharing: as a standalone HTML page, or as a
+//! PNG image when built with the `png` feature (or `vision`, which brings in the same image
+//! library). Both draw the board themselves, so they read the same wherever they're posted. A
+//! match can also be written as a Markdown transcript, with what the solver advised before each of
+//! the player's moves.
+
+#[cfg(feature = "image")]
+use image::{ImageError, ImageFormat, Rgb, RgbImage};
+
+use std::collections::BTreeMap;
+
+use crate::{
+    data::Data,
+    game::{Game, GameMove, RenderStyle},
+    search::SearchableGame,
+};
+
+/// How much of the match to export.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportScope {
+    /// Just the board as it is now.
+    Position,
+    /// The board after each move played so far.
+    Replay,
+}
+
+/// The boards to export, each with a caption: the board now, or the board after each move with
+/// the move described as in [`Game::move_list`].
+fn boards(game: &Game, scope: ExportScope, data: &Data) -> Vec<(String, Game)> {
+    let moves = game.played_moves();
+    if scope == ExportScope::Position || moves.is_empty() {
+        return vec![(String::from("The board"), game.clone())];
+    }
+
+    let descriptions = game.move_list(data);
+    let mut replay = game.clone();
+    replay.undo_last_moves(moves.len());
+    moves
+        .iter()
+        .enumerate()
+        .map(|(idx, mv)| {
+            replay.apply_move(mv);
+            let caption = descriptions
+                .get(idx)
+                .cloned()
+                .unwrap_or_else(|| format!("After move {}", idx + 1));
+            (caption, replay.clone())
+        })
+        .collect()
+}
+
+const PAGE_STYLE: &str = "
+body { font-family: sans-serif; background: #1e1f22; color: #ddd; }
+section { display: inline-block; margin: 0 1.5em 1.5em 0; vertical-align: top; }
+h2 { font-size: 1em; font-weight: normal; }
+.tt-board { border-collapse: collapse; }
+.tt-board caption { caption-side: bottom; padding-top: 0.5em; font-size: 0.9em; }
+.tt-board td { width: 4.5em; height: 4.5em; border: 2px solid #444; text-align: center;
+  font-weight: bold; font-size: 1.1em; color: #fff; }
+.tt-empty { background: #2b2d31; color: #666 !important; font-weight: normal !important; }
+.tt-blue { background: #2f5fb3; }
+.tt-red { background: #b33a3a; }
+.tt-placed { outline: 3px solid #f5c542; outline-offset: -3px; }
+.tt-flipped { outline: 3px dashed #f5c542; outline-offset: -3px; }
+.tt-north, .tt-south { display: block; }
+.tt-suit { display: inline-block; min-width: 1em; font-size: 0.8em; opacity: 0.8; }
+";
+
+/// A standalone HTML page with the boards, titled `title`, that needs nothing else to show.
+pub fn html_page(game: &Game, scope: ExportScope, title: &str, data: &Data) -> String {
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>{title}</h1>\n",
+        PAGE_STYLE,
+        title = escape_html(title)
+    );
+    for (caption, board) in boards(game, scope, data) {
+        let mut table = Vec::new();
+        // Writing to a Vec can't fail
+        board.render(RenderStyle::Html, &mut table).unwrap();
+        page.push_str(&format!(
+            "<section>\n<h2>{}</h2>\n{}</section>\n",
+            escape_html(&caption),
+            String::from_utf8_lossy(&table)
+        ));
+    }
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+/// What the solver advised before one of the player's moves, for [`transcript`].
+pub struct MoveAdvice {
+    pub recommended: GameMove,
+    /// As it was shown: the recommended move, why, and the next best moves.
+    pub lines: Vec<String>,
+}
+
+/// A match as Markdown, for studying it later or reporting a bad recommendation: `details` (like
+/// the deck and the rules) as a list under `title`, then each move with the board after it, and
+/// for the player's moves, the advice in `advice` (by the move's index) and whether it was
+/// followed.
+pub fn transcript(
+    game: &Game,
+    title: &str,
+    details: &[String],
+    advice: &BTreeMap<usize, MoveAdvice>,
+    data: &Data,
+) -> String {
+    let mut text = format!("# {}\n\n", title);
+    for detail in details {
+        text.push_str(&format!("- {}\n", detail));
+    }
+
+    let moves = game.played_moves();
+    for (idx, (caption, board)) in boards(game, ExportScope::Replay, data)
+        .into_iter()
+        .enumerate()
+    {
+        text.push_str(&format!("\n## {}\n\n", caption));
+        if let (Some(advice), Some(played)) = (advice.get(&idx), moves.get(idx)) {
+            text.push_str("The solver's advice:\n\n");
+            for line in &advice.lines {
+                text.push_str(&format!("> {}\n", line));
+            }
+            text.push_str(if advice.recommended == *played {
+                "\nThe recommended move was played.\n\n"
+            } else {
+                "\nA different move was played.\n\n"
+            });
+        }
+        let mut grid = Vec::new();
+        // Writing to a Vec can't fail
+        board.render(RenderStyle::Plain, &mut grid).unwrap();
+        text.push_str(&format!("```\n{}```\n", String::from_utf8_lossy(&grid)));
+    }
+    text
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Pixels on a side of a board cell in a PNG.
+#[cfg(feature = "image")]
+const CELL_SIZE: u32 = 72;
+/// Pixels between cells, and twice this between boards.
+#[cfg(feature = "image")]
+const GAP: u32 = 4;
+/// How many boards go in a row of a PNG before starting the next row.
+#[cfg(feature = "image")]
+const BOARDS_PER_ROW: u32 = 5;
+/// How many pixels wide and tall each pixel of the font is drawn.
+#[cfg(feature = "image")]
+const FONT_SCALE: u32 = 3;
+
+/// The boards in a PNG image, left to right and then in rows, in the order they were played.
+/// Each card is filled in its owner's color with its values and suit on it, and the cards the
+/// last move put down or flipped are outlined.
+#[cfg(feature = "image")]
+pub fn png(game: &Game, scope: ExportScope, data: &Data) -> Result<Vec<u8>, ImageError> {
+    let boards = boards(game, scope, data);
+    let board_size = CELL_SIZE * 3 + GAP * 4;
+    let columns = (boards.len() as u32).min(BOARDS_PER_ROW);
+    let rows = (boards.len() as u32).div_ceil(BOARDS_PER_ROW);
+    let mut image = RgbImage::from_pixel(
+        columns * board_size + (columns - 1) * GAP * 2,
+        rows * board_size + (rows - 1) * GAP * 2,
+        Rgb([30, 31, 34]),
+    );
+    for (idx, (_, board)) in boards.iter().enumerate() {
+        let idx = idx as u32;
+        let x = idx % BOARDS_PER_ROW * (board_size + GAP * 2);
+        let y = idx / BOARDS_PER_ROW * (board_size + GAP * 2);
+        draw_png_board(&mut image, board, x, y);
+    }
+
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(feature = "image")]
+fn draw_png_board(image: &mut RgbImage, game: &Game, x: u32, y: u32) {
+    use crate::game::{CellChange, Player};
+
+    let changes = game.last_move_changes();
+    for (pos, change) in changes.iter().enumerate() {
+        let left = x + GAP + (pos as u32 % 3) * (CELL_SIZE + GAP);
+        let top = y + GAP + (pos as u32 / 3) * (CELL_SIZE + GAP);
+        let (values, suit, owner) = match game.board_cell(pos) {
+            Some(cell) => cell,
+            None => {
+                fill(image, left, top, CELL_SIZE, CELL_SIZE, Rgb([43, 45, 49]));
+                continue;
+            }
+        };
+        let color = match owner {
+            Player::Blue => Rgb([47, 95, 179]),
+            Player::Red => Rgb([179, 58, 58]),
+        };
+        if *change == CellChange::Unchanged {
+            fill(image, left, top, CELL_SIZE, CELL_SIZE, color);
+        } else {
+            // An outline in the same yellow as the HTML page
+            fill(image, left, top, CELL_SIZE, CELL_SIZE, Rgb([245, 197, 66]));
+            fill(
+                image,
+                left + 3,
+                top + 3,
+                CELL_SIZE - 6,
+                CELL_SIZE - 6,
+                color,
+            );
+        }
+
+        // North, east, south, west around the middle, with the suit in it
+        let [north, east, south, west] = values;
+        let glyph_width = 3 * FONT_SCALE;
+        let glyph_height = 5 * FONT_SCALE;
+        let center_x = left + (CELL_SIZE - glyph_width) / 2;
+        let center_y = top + (CELL_SIZE - glyph_height) / 2;
+        let margin = 8;
+        draw_text(image, &north, center_x, top + margin);
+        draw_text(
+            image,
+            &south,
+            center_x,
+            top + CELL_SIZE - margin - glyph_height,
+        );
+        draw_text(image, &west, left + margin, center_y);
+        draw_text(
+            image,
+            &east,
+            left + CELL_SIZE - margin - glyph_width,
+            center_y,
+        );
+        draw_text(image, suit.to_string().trim(), center_x, center_y);
+    }
+}
+
+#[cfg(feature = "image")]
+fn fill(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Draws `text` in white from its top left corner, skipping characters the font doesn't have.
+#[cfg(feature = "image")]
+fn draw_text(image: &mut RgbImage, text: &str, x: u32, y: u32) {
+    for (idx, c) in text.chars().enumerate() {
+        let rows = match glyph(c) {
+            Some(rows) => rows,
+            None => continue,
+        };
+        let glyph_x = x + idx as u32 * 4 * FONT_SCALE;
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..3 {
+                if bits & (0b100 >> column) != 0 {
+                    fill(
+                        image,
+                        glyph_x + column * FONT_SCALE,
+                        y + row as u32 * FONT_SCALE,
+                        FONT_SCALE,
+                        FONT_SCALE,
+                        Rgb([255, 255, 255]),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A 3×5 pixel glyph for the characters that go on a card: the values and the suit letters. Each
+/// row's bits are its pixels from left to right.
+#[cfg(feature = "image")]
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        _ => return None,
+    })
+}
@@ -0,0 +1,364 @@
+//! Finding out where cards come from, and estimating how long it takes to farm them from NPCs (or
+//! how much MGP farming them earns), and how hard each NPC is to beat in the first place.
+
+use inquire::CustomType;
+use std::sync::Arc;
+
+use triple_triad_solver::{
+    data::Data,
+    decks::SavedDecks,
+    game::{CardId, Game, Player, Rules},
+    optimize,
+    search::{self, FirstMove, OutcomeRates},
+    settings::{MgpPayouts, Settings},
+    stats::MatchStats,
+};
+
+// Random matches simulated per NPC when estimating win rates, split between either player going
+// first
+const WIN_RATE_SIMULATIONS: usize = 4_000;
+// Fewer for MGP estimates, which simulate every registered deck against every NPC
+const MGP_SIMULATIONS: usize = 1_000;
+const MGP_REPORT_LENGTH: usize = 15;
+// Per NPC when ranking them by difficulty, against the same reference deck
+const DIFFICULTY_SIMULATIONS: usize = 2_000;
+/// The difficulty tiers, hardest first, each with the share of matches (ties counting half) the
+/// reference deck gets below which an NPC is in it.
+const DIFFICULTY_TIERS: [(&str, f64); 4] = [
+    ("Very hard", 0.5),
+    ("Hard", 0.7),
+    ("Moderate", 0.85),
+    ("Easy", f64::INFINITY),
+];
+const DEFAULT_MATCH_SECONDS: u32 = 120;
+
+pub fn where_to_get(data: &Data) {
+    let card_id = crate::select_card("Which card?", data);
+    let card_name = data.card_display_name(card_id);
+    let info = data.card_info.get(&card_id);
+
+    if let Some(acquisition) = info.and_then(|info| info.acquisition.as_ref()) {
+        println!("{}", acquisition);
+    }
+    if let Some(quest_id) = info.and_then(|info| info.quest_id) {
+        println!("{} is rewarded by quest #{}.", card_name, quest_id);
+    }
+
+    let npcs = data.npcs_rewarding(card_id);
+    if npcs.is_empty() {
+        println!("No NPCs are known to give out {}.", card_name);
+        return;
+    }
+
+    println!("{} can be won from:", card_name);
+    for (name, npc) in npcs {
+        match &npc.location {
+            Some(location) => println!("  {} — {}", data.npc_display_name(name), location),
+            None => println!("  {}", data.npc_display_name(name)),
+        }
+    }
+    if let Some(info) = info {
+        println!(
+            "Approximate drop rate after a win: {:.0}%",
+            info.approximate_drop_rate() * 100.0
+        );
+    }
+}
+
+pub fn plan_farm(data: &Data, saved_decks: &SavedDecks) {
+    if saved_decks.get_deck_count() == 0 {
+        println!("You must have at least 1 registered deck to plan a farm!");
+        return;
+    }
+
+    let card_id = crate::select_card("Which card do you want?", data);
+    let card_name = data.card_display_name(card_id);
+    let npcs = data.npcs_rewarding(card_id);
+    if npcs.is_empty() {
+        println!("No NPCs are known to give out {}.", card_name);
+        return;
+    }
+
+    let deck_name = crate::select_deck("Which deck would you use?", saved_decks);
+    let deck = saved_decks.get_deck(&deck_name).unwrap();
+    let drop_rate = data
+        .card_info
+        .get(&card_id)
+        .map(|info| info.approximate_drop_rate())
+        .unwrap_or_default();
+
+    println!("Simulating matches...");
+    let mut plans: Vec<(&str, f64)> = npcs
+        .into_iter()
+        .map(|(name, _)| {
+            let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+            game.set_cards_in_hand(Player::Blue, &deck.map(|id| CardId::new(id).unwrap()), 5);
+            game.set_cards_for_npc(Player::Red, data, name);
+
+            let win_rate = search::estimate_win_rate(
+                &game,
+                FirstMove::Random,
+                Player::Blue,
+                WIN_RATE_SIMULATIONS,
+                None,
+            );
+            (name, win_rate)
+        })
+        .collect();
+    plans.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+
+    println!(
+        "\nEstimates for {} with {} (approximate drop rate {:.0}%):",
+        card_name,
+        deck_name,
+        drop_rate * 100.0
+    );
+    for (name, win_rate) in plans {
+        let chance_per_match = win_rate * drop_rate;
+        if chance_per_match > 0.0 {
+            println!(
+                "  {}: wins {:.0}% of matches, about {:.0} matches to get the card",
+                data.npc_display_name(name),
+                win_rate * 100.0,
+                (1.0 / chance_per_match).ceil()
+            );
+        } else {
+            println!(
+                "  {}: this deck can't beat them",
+                data.npc_display_name(name)
+            );
+        }
+    }
+}
+
+/// Asks for a number, offering `last` (the one entered last time) unless it's 0.
+fn prompt_number(message: &str, last: u32) -> u32 {
+    let prompt = CustomType::new(message);
+    match last {
+        0 => prompt.prompt().unwrap(),
+        last => prompt
+            .with_default((last, &|value: u32| value.to_string()))
+            .prompt()
+            .unwrap(),
+    }
+}
+
+/// Estimates how much MGP an hour of playing each NPC earns, with whichever registered deck does
+/// best against them, and lists the best NPCs to farm.
+pub fn estimate_mgp(data: &Data, saved_decks: &SavedDecks, settings: &mut Settings) {
+    if saved_decks.get_deck_count() == 0 {
+        println!("You must have at least 1 registered deck to estimate MGP earnings!");
+        return;
+    }
+
+    println!("Enter what a typical NPC pays out, as shown when you challenge them.");
+    let last = settings.mgp_payouts;
+    settings.mgp_payouts = MgpPayouts {
+        win: prompt_number("MGP for a win:", last.win),
+        tie: prompt_number("MGP for a draw:", last.tie),
+        loss: prompt_number("MGP for a loss:", last.loss),
+        match_seconds: prompt_number(
+            "Seconds per match, including challenging them again:",
+            match last.match_seconds {
+                0 => DEFAULT_MATCH_SECONDS,
+                last => last,
+            },
+        ),
+    };
+    settings.save().unwrap();
+    let payouts = settings.mgp_payouts;
+    let matches_per_hour = 3600.0 / payouts.match_seconds.max(1) as f64;
+
+    println!("Simulating matches...");
+    let decks: Vec<(String, [i32; 5])> = saved_decks
+        .get_deck_names()
+        .into_iter()
+        .map(|name| {
+            let deck = saved_decks.get_deck(&name).unwrap();
+            (name, deck)
+        })
+        .collect();
+    let mut estimates: Vec<(&str, &str, OutcomeRates, f64)> = data
+        .npcs_by_name
+        .iter()
+        .map(|(npc_name, npc)| {
+            decks
+                .iter()
+                .map(|(deck_name, deck)| {
+                    let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+                    game.set_cards_in_hand(
+                        Player::Blue,
+                        &deck.map(|id| CardId::new(id).unwrap()),
+                        5,
+                    );
+                    game.set_cards_for_npc(Player::Red, data, npc_name);
+                    let rates = search::estimate_outcomes(
+                        &game,
+                        FirstMove::Random,
+                        Player::Blue,
+                        MGP_SIMULATIONS,
+                        None,
+                    );
+                    let per_match = rates.win * payouts.win as f64
+                        + rates.tie * payouts.tie as f64
+                        + rates.loss * payouts.loss as f64
+                        - npc.fee as f64;
+                    (
+                        npc_name.as_str(),
+                        deck_name.as_str(),
+                        rates,
+                        per_match * matches_per_hour,
+                    )
+                })
+                .max_by(|(_, _, _, a), (_, _, _, b)| a.partial_cmp(b).unwrap())
+                .unwrap()
+        })
+        .collect();
+    estimates.sort_by(|(_, _, _, a), (_, _, _, b)| b.partial_cmp(a).unwrap());
+
+    println!(
+        "\nBest NPCs to farm, at {:.0} matches an hour:",
+        matches_per_hour
+    );
+    for (npc_name, deck_name, rates, per_hour) in estimates.into_iter().take(MGP_REPORT_LENGTH) {
+        println!(
+            "  {}: about {:.0} MGP an hour with {} (wins {:.0}%, draws {:.0}%, fee {} MGP)",
+            data.npc_display_name(npc_name),
+            per_hour,
+            deck_name,
+            rates.win * 100.0,
+            rates.tie * 100.0,
+            data.npcs_by_name[npc_name].fee
+        );
+    }
+    println!("Payouts differ between NPCs, so check the top few before settling on one.");
+}
+
+/// Ranks every NPC by how well a strong deck does against them in simulated matches, under their
+/// own rules and with their fixed and variable cards, and lists them in difficulty tiers with the
+/// cards they drop, to pick which to take on first when collecting.
+pub fn rank_npcs(data: &Data, saved_decks: &SavedDecks, match_stats: &MatchStats) {
+    let owned = crate::NpcPicker::new(saved_decks, match_stats).owned;
+    let card_names = |deck: [i32; 5]| {
+        deck.iter()
+            .map(|&id| data.card_display_name(id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    // Under Reverse, the yardstick is a deck of low cards
+    let reverse = Rules {
+        reverse: true,
+        ..Rules::default()
+    };
+    let (deck, reverse_deck) = match (
+        optimize::reference_deck(data, &Rules::default()),
+        optimize::reference_deck(data, &reverse),
+    ) {
+        (Some(deck), Some(reverse_deck)) => (deck, reverse_deck),
+        _ => {
+            println!("There aren't enough cards in the card data to build a deck.");
+            return;
+        }
+    };
+
+    println!("Simulating matches...");
+    let mut ranked: Vec<(&str, OutcomeRates)> = Vec::new();
+    for (npc_name, npc) in &data.npcs_by_name {
+        let deck = if npc.rules.reverse {
+            reverse_deck
+        } else {
+            deck
+        };
+        let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+        game.set_cards_in_hand(Player::Blue, &deck.map(|id| CardId::new(id).unwrap()), 5);
+        game.set_cards_for_npc(Player::Red, data, npc_name);
+        let rates = search::estimate_outcomes(
+            &game,
+            FirstMove::Random,
+            Player::Blue,
+            DIFFICULTY_SIMULATIONS,
+            None,
+        );
+        ranked.push((npc_name, rates));
+    }
+    let score = |rates: &OutcomeRates| rates.win + rates.tie / 2.0;
+    ranked.sort_by(|(a_name, a), (b_name, b)| {
+        score(a)
+            .partial_cmp(&score(b))
+            .unwrap()
+            .then_with(|| a_name.cmp(b_name))
+    });
+
+    println!(
+        "\nHow often a strong deck ({}) wins against each NPC, playing at random:",
+        card_names(deck)
+    );
+    if data.npcs_by_name.values().any(|npc| npc.rules.reverse) {
+        println!(
+            "Against NPCs playing Reverse, it's a deck of low cards instead ({}).",
+            card_names(reverse_deck)
+        );
+    }
+    let mut ranked = ranked.into_iter().peekable();
+    let mut lower_bound = 0.0;
+    for (tier, upper_bound) in DIFFICULTY_TIERS {
+        let mut heading = false;
+        while let Some((npc_name, rates)) = ranked.next_if(|(_, rates)| score(rates) < upper_bound)
+        {
+            if !heading {
+                if upper_bound.is_finite() {
+                    println!(
+                        "{} (the deck scores {:.0}% to {:.0}%):",
+                        tier,
+                        lower_bound * 100.0,
+                        upper_bound * 100.0
+                    );
+                } else {
+                    println!(
+                        "{} (the deck scores {:.0}% or more):",
+                        tier,
+                        lower_bound * 100.0
+                    );
+                }
+                heading = true;
+            }
+            let npc = &data.npcs_by_name[npc_name];
+            let mut line = format!(
+                "  {}: wins {:.0}%, draws {:.0}%",
+                data.npc_display_name(npc_name),
+                rates.win * 100.0,
+                rates.tie * 100.0
+            );
+            let mut rules = npc.rules.active_rule_names().join(", ");
+            if npc.uses_regional_rules {
+                rules = if rules.is_empty() {
+                    String::from("regional rules")
+                } else {
+                    format!("{} and regional rules", rules)
+                };
+            }
+            if !rules.is_empty() {
+                line.push_str(&format!("; {}", rules));
+            }
+            match npc
+                .reward_cards
+                .iter()
+                .filter(|card| !owned.contains(card))
+                .count()
+            {
+                0 => {}
+                1 => line.push_str("; 1 new card"),
+                new_cards => line.push_str(&format!("; {} new cards", new_cards)),
+            }
+            if let Some(location) = &npc.location {
+                line.push_str(&format!(" — {}", location));
+            }
+            println!("{}", line);
+        }
+        lower_bound = upper_bound;
+    }
+    println!(
+        "Score counts a draw as half a win. Regional rules aren't simulated, since they change."
+    );
+}
@@ -1,20 +1,302 @@
-use colorful::{core::color_string::CString, Color, Colorful};
+use colorful::{Color, Colorful};
+use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
     collections::VecDeque,
     fmt::{Display, Formatter},
     ops::{Index, IndexMut},
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
 };
+use thiserror::Error;
 
 use crate::{
     data::Data,
-    search::{GamePlayer, SearchableGame, WinState},
+    search::{EvalWeights, GamePlayer, SearchableGame, WinState},
 };
 
+mod builder;
+mod card_db;
+mod glossary;
+mod render;
+mod rule_set;
+mod symmetry;
+
+pub use builder::{GameBuildError, GameBuilder, HandCard};
+pub use card_db::{CardDb, CardId};
+pub use glossary::{RuleExplanation, GLOSSARY};
+pub use render::{RenderStyle, UnknownRenderStyle};
+pub use rule_set::{Comparison, Resolver, RuleSet};
+
 const MAX_VALUE: i32 = 10;
 
+/// The rules that change which cards a move flips, so the board shows whether they're in play.
+const FLIP_RULES: [&str; 6] = [
+    "Same",
+    "Plus",
+    "Reverse",
+    "Fallen Ace",
+    "Ascension",
+    "Descension",
+];
+
+/// Slots in a hand, for the cards it's known to hold followed by the ones it might: enough for an
+/// NPC's fixed cards and a pool of variable cards bigger than the five it could draw. Every slot
+/// is checked for moves, so more would slow down the search and the Monte Carlo playouts.
+pub const HAND_SLOTS: usize = 10;
+
+static COLOR_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns ANSI colors in the board display on or off.
+pub fn set_color_enabled(enabled: bool) {
+    COLOR_ENABLED.store(enabled, atomic::Ordering::Relaxed);
+}
+
+pub fn color_enabled() -> bool {
+    COLOR_ENABLED.load(atomic::Ordering::Relaxed)
+}
+
+static BOARD_LABELS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Turns the row/column labels around the board display on or off.
+pub fn set_board_labels_enabled(enabled: bool) {
+    BOARD_LABELS_ENABLED.store(enabled, atomic::Ordering::Relaxed);
+}
+
+static PLAIN_TEXT_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns plain ASCII output on or off: boxes drawn with `-`, `|`, and `+`, and stars as `*`, for
+/// limited terminals, screen readers, and output piped to files.
+pub fn set_plain_text_enabled(enabled: bool) {
+    PLAIN_TEXT_ENABLED.store(enabled, atomic::Ordering::Relaxed);
+}
+
+pub fn plain_text_enabled() -> bool {
+    PLAIN_TEXT_ENABLED.load(atomic::Ordering::Relaxed)
+}
+
+static BOARD_GRID_ENABLED: AtomicBool = AtomicBool::new(true);
+static BOARD_DESCRIPTION_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Chooses whether [`Game::display_with_hand_panel`] draws the board grid, describes the board in
+/// words (see [`Game::describe_board`]), or both.
+pub fn set_board_view(grid: bool, description: bool) {
+    BOARD_GRID_ENABLED.store(grid, atomic::Ordering::Relaxed);
+    BOARD_DESCRIPTION_ENABLED.store(description, atomic::Ordering::Relaxed);
+}
+
+/// The characters boxes are drawn with.
+pub struct BoxChars {
+    pub horizontal: &'static str,
+    pub vertical: &'static str,
+    /// The corners and joins, laid out like a keypad: top left, top, top right, left, middle,
+    /// right, bottom left, bottom, bottom right.
+    pub joins: [&'static str; 9],
+}
+
+const UNICODE_BOX_CHARS: BoxChars = BoxChars {
+    horizontal: "─",
+    vertical: "│",
+    joins: ["┌", "┬", "┐", "├", "┼", "┤", "└", "┴", "┘"],
+};
+
+const ASCII_BOX_CHARS: BoxChars = BoxChars {
+    horizontal: "-",
+    vertical: "|",
+    joins: ["+"; 9],
+};
+
+/// How the board grid is drawn, for drawing it other than the settings say (see
+/// [`Game::render`]).
+#[derive(Clone, Copy)]
+struct GridStyle {
+    color: bool,
+    labels: bool,
+    chars: &'static BoxChars,
+}
+impl GridStyle {
+    /// The style the display settings ask for.
+    fn current() -> Self {
+        GridStyle {
+            color: COLOR_ENABLED.load(atomic::Ordering::Relaxed),
+            labels: BOARD_LABELS_ENABLED.load(atomic::Ordering::Relaxed),
+            chars: box_chars(),
+        }
+    }
+}
+
+/// The characters to draw boxes with, plain ASCII if that's turned on.
+pub fn box_chars() -> &'static BoxChars {
+    if plain_text_enabled() {
+        &ASCII_BOX_CHARS
+    } else {
+        &UNICODE_BOX_CHARS
+    }
+}
+
+/// A card's rarity as a row of stars, `*` in plain ASCII.
+pub fn stars(count: u8) -> String {
+    let star = if plain_text_enabled() { "*" } else { "★" };
+    star.repeat(count as usize)
+}
+
+const ROW_LABELS: [char; 3] = ['A', 'B', 'C'];
+
+/// Board positions in words, in the same order as the board.
+const CELL_NAMES: [&str; 9] = [
+    "Top left",
+    "Top",
+    "Top right",
+    "Left",
+    "Center",
+    "Right",
+    "Bottom left",
+    "Bottom",
+    "Bottom right",
+];
+
+/// The code for a board position, from `A1` (top left) to `C3` (bottom right).
+pub fn cell_code(pos: usize) -> String {
+    format!("{}{}", ROW_LABELS[pos / 3], pos % 3 + 1)
+}
+
+/// `text` with its first letter in upper case.
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// A number of cards in words, like "1 card" or "3 cards".
+fn cards_count(count: usize) -> String {
+    match count {
+        1 => String::from("1 card"),
+        _ => format!("{} cards", count),
+    }
+}
+
+fn paint(text: &str, color: Color) -> String {
+    paint_if(COLOR_ENABLED.load(atomic::Ordering::Relaxed), text, color)
+}
+
+/// Like `paint`, but only in color when `enabled`, whatever the setting is.
+fn paint_if(enabled: bool, text: &str, color: Color) -> String {
+    if enabled {
+        text.color(color).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Like `paint_if`, but also bold and underlined to stand out.
+fn paint_emphasized(enabled: bool, text: &str, color: Color) -> String {
+    if enabled {
+        text.color(color).bold().underlined().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// The number of characters `text` takes up on screen, skipping ANSI color codes.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// What's known about a card in a hand, for the hand panel next to the board.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HandCardStatus {
+    /// Always in the hand, and not played yet.
+    InHand,
+    /// One of the variable cards, which may or may not be in the hand.
+    Possible,
+    /// Played already.
+    Played,
+    /// One of the variable cards, but enough of them have been played that it can't be in the hand.
+    RuledOut,
+}
+impl HandCardStatus {
+    pub fn label(&self) -> &'static str {
+        match *self {
+            HandCardStatus::InHand => "in hand",
+            HandCardStatus::Possible => "maybe",
+            HandCardStatus::Played => "played",
+            HandCardStatus::RuledOut => "not in hand",
+        }
+    }
+}
+
+/// Something about the moves entered so far that can't have happened in the game, usually because
+/// one of them was entered wrong.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Inconsistency {
+    #[error("{card} was already played on move {first}, and there's only one of it in {hand}")]
+    CardPlayedTwice {
+        card: String,
+        first: usize,
+        hand: String,
+    },
+
+    #[error("{hand} still has {known} cards it's known to hold, but only {left} cards left in it")]
+    HandSizeMismatch {
+        hand: String,
+        known: usize,
+        left: usize,
+    },
+
+    #[error("The board in the game doesn't match the moves entered, which lead to {expected}")]
+    OwnershipMismatch { expected: String },
+}
+
+/// How a board cell changed in the last move.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellChange {
+    Unchanged,
+    Placed,
+    Flipped,
+}
+impl CellChange {
+    pub fn marker(&self) -> char {
+        match *self {
+            CellChange::Unchanged => ' ',
+            CellChange::Placed => '+',
+            CellChange::Flipped => '*',
+        }
+    }
+}
+
+/// How many cards the last move flipped, by the way each was taken.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Captures {
+    /// Beaten by the side of the card played next to them.
+    pub basic: u8,
+    pub same: u8,
+    pub plus: u8,
+    /// Beaten by a card that was itself taken by Same, Plus, or another combo.
+    pub combo: u8,
+}
+impl Captures {
+    pub fn total(&self) -> u8 {
+        self.basic + self.same + self.plus + self.combo
+    }
+}
+
 // Optional rules
-#[derive(Default, Clone, Debug)]
+#[derive(Copy, Default, Clone, Debug)]
 pub struct Rules {
     /// When a card is played, if two or more of the sides are touching other cards,
     /// and the ranks of those sides are identical to those on the sides of the card played,
@@ -29,8 +311,8 @@ pub struct Rules {
     /// Cards must be played in the order they appear in the deck.
     pub order: bool,
 
-    // Cards must be played in a random order determined before the match.
-    pub chaos: bool, // TODO: ????? how to handle this
+    /// The card to play each turn is picked at random from the hand.
+    pub chaos: bool,
 
     /// Lower numbers flip higher numbers.
     pub reverse: bool,
@@ -44,8 +326,10 @@ pub struct Rules {
     /// A card's ranks decrease in proportion to the number of cards of the same type already played.
     pub decension: bool,
 
-    /// One random card from each player's deck will be swapped with the other.
-    pub swap: bool, // TODO: ????? how to handle this
+    /// One random card from each player's deck will be swapped with the other. Both traded cards
+    /// are shown before the first move, so unlike Chaos, it's never left to chance in a search:
+    /// the hands are entered as they are after the trade.
+    pub swap: bool,
 }
 impl Rules {
     pub fn add_rule_from_csv(&mut self, rule: i32) {
@@ -72,9 +356,82 @@ impl Rules {
             }
         }
     }
+
+    /// Names of every rule the solver knows about, in the order they're displayed.
+    pub const NAMES: [&'static str; 9] = [
+        "Same",
+        "Plus",
+        "Order",
+        "Chaos",
+        "Reverse",
+        "Fallen Ace",
+        "Ascension",
+        "Descension",
+        "Swap",
+    ];
+
+    fn flag_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "Same" => Some(&mut self.same),
+            "Plus" => Some(&mut self.plus),
+            "Order" => Some(&mut self.order),
+            "Chaos" => Some(&mut self.chaos),
+            "Reverse" => Some(&mut self.reverse),
+            "Fallen Ace" => Some(&mut self.fallen_ace),
+            "Ascension" => Some(&mut self.ascension),
+            "Descension" => Some(&mut self.decension),
+            "Swap" => Some(&mut self.swap),
+            _ => None,
+        }
+    }
+
+    /// Turns on the rule with the given name (one of [`Rules::NAMES`]).
+    pub fn add_rule_by_name(&mut self, name: &str) {
+        if let Some(flag) = self.flag_mut(name) {
+            *flag = true;
+        }
+    }
+
+    /// Turns the rule with the given name (one of [`Rules::NAMES`]) on or off.
+    pub fn set_rule_by_name(&mut self, name: &str, on: bool) {
+        if let Some(flag) = self.flag_mut(name) {
+            *flag = on;
+        }
+    }
+
+    /// Turns on every rule that's on in `other`.
+    pub fn merge(&mut self, other: &Rules) {
+        for name in other.active_rule_names() {
+            self.add_rule_by_name(name);
+        }
+    }
+
+    /// Whether each rule is on, in the same order as [`Rules::NAMES`].
+    pub fn flags(&self) -> [bool; 9] {
+        [
+            self.same,
+            self.plus,
+            self.order,
+            self.chaos,
+            self.reverse,
+            self.fallen_ace,
+            self.ascension,
+            self.decension,
+            self.swap,
+        ]
+    }
+
+    pub fn active_rule_names(&self) -> Vec<&'static str> {
+        Rules::NAMES
+            .iter()
+            .zip(self.flags())
+            .filter(|(_, active)| *active)
+            .map(|(name, _)| *name)
+            .collect()
+    }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(usize)]
 pub enum Player {
     Red,
@@ -87,6 +444,14 @@ impl Player {
             Player::Red => Color::LightRed,
         }
     }
+    /// The letter marking this player's cards on the board when there are no colors to tell them
+    /// apart.
+    pub fn letter(&self) -> char {
+        match *self {
+            Player::Blue => 'B',
+            Player::Red => 'R',
+        }
+    }
 }
 impl Display for Player {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -123,7 +488,7 @@ pub enum Direction {
     East,
 }
 impl Direction {
-    fn opposite(&self) -> Direction {
+    pub fn opposite(&self) -> Direction {
         match *self {
             Direction::East => Direction::West,
             Direction::North => Direction::South,
@@ -131,22 +496,57 @@ impl Direction {
             Direction::West => Direction::East,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::West => "west",
+            Direction::East => "east",
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
-#[repr(usize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
 pub enum Suit {
+    None,
     Primal,
     Beastman,
     Scion,
     Garlean,
 }
+impl Suit {
+    /// The suit its letter on the board stands for, like `P` for Primal.
+    pub fn from_letter(letter: char) -> Option<Suit> {
+        match letter.to_ascii_uppercase() {
+            'P' => Some(Suit::Primal),
+            'B' => Some(Suit::Beastman),
+            'S' => Some(Suit::Scion),
+            'G' => Some(Suit::Garlean),
+            _ => None,
+        }
+    }
+
+    /// The suit's name, like `Primal`, or `None` for no suit.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            Suit::None => None,
+            Suit::Primal => Some("Primal"),
+            Suit::Beastman => Some("Beastman"),
+            Suit::Scion => Some("Scion"),
+            Suit::Garlean => Some("Garlean"),
+        }
+    }
+}
 impl Display for Suit {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
             "{}",
             match *self {
+                // A space rather than nothing, so boards line up whether cards have suits or not
+                Suit::None => " ",
                 Suit::Primal => "P",
                 Suit::Beastman => "B",
                 Suit::Scion => "S",
@@ -155,71 +555,82 @@ impl Display for Suit {
         )
     }
 }
-#[derive(Clone, Default)]
-pub struct Modifiers([i32; 4]);
-impl Index<Suit> for Modifiers {
-    type Output = i32;
+/// For Ascension and Descension: how many cards of each suit have been put on the board, and how
+/// much each of them changes the values of every card of its suit.
+#[derive(Copy, Clone, Default)]
+pub struct Modifiers {
+    counts: [i32; 5],
+    /// 1 under Ascension, -1 under Descension, and 0 without either.
+    step: i32,
+}
+impl Modifiers {
+    /// How many cards of `suit` have been put on the board.
+    pub fn count(&self, suit: Suit) -> i32 {
+        self.counts[suit as usize]
+    }
 
-    fn index(&self, index: Suit) -> &Self::Output {
-        &self.0[index as usize]
+    /// What's added to every value of a card of `suit`, before the result is clamped.
+    pub fn modifier(&self, suit: Suit) -> i32 {
+        match suit {
+            Suit::None => 0,
+            _ => self.counts[suit as usize] * self.step,
+        }
     }
-}
-impl IndexMut<Suit> for Modifiers {
-    fn index_mut(&mut self, index: Suit) -> &mut Self::Output {
-        &mut self.0[index as usize]
+
+    fn add_card(&mut self, suit: Suit) {
+        self.counts[suit as usize] += 1;
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct Card {
-    values: [i32; 4],
-    pub suit: Option<Suit>, // TODO: have "None" suit instead of Option for better packing
+    values: [u8; 4],
+    pub suit: Suit,
+}
+impl Display for Card {
+    /// Shows the values clockwise from the top, like `4-2-3-2`, followed by the suit if it has one.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let modifiers = Modifiers::default();
+        let values = [
+            Direction::North,
+            Direction::East,
+            Direction::South,
+            Direction::West,
+        ]
+        .map(|direction| self.get_modified_value_display(&modifiers, direction));
+        write!(f, "{}", values.join("-"))?;
+        if self.suit != Suit::None {
+            write!(f, " {}", self.suit)?;
+        }
+        Ok(())
+    }
 }
 impl Card {
-    pub fn new(n: i32, s: i32, w: i32, e: i32, suit: Option<Suit>) -> Self {
+    pub fn new(n: u8, s: u8, w: u8, e: u8, suit: Suit) -> Self {
         Card {
             values: [n, s, w, e],
             suit,
         }
     }
 
-    /// Returns true if this card is beaten a card just played in the given position relative to this card.
-    /// Example:
-    ///     [this][other]
-    /// would be represented by Position::East and
-    ///     [other]
-    ///     [this]
-    /// would be represented by Position::North.
-    fn is_flipped_by(
-        &self,
-        other: &Card,
-        direction: Direction,
-        modifiers: &Modifiers,
-        rules: &Rules,
-    ) -> bool {
-        let my_value = self.get_modified_value(modifiers, direction);
-        let other_value = other.get_modified_value(modifiers, direction.opposite());
+    /// The card's printed value on the given side, before any modifiers.
+    pub fn value(&self, direction: Direction) -> i32 {
+        self.values[direction as usize] as i32
+    }
 
-        if !rules.reverse {
-            if rules.fallen_ace && (my_value == MAX_VALUE) && (other_value == 1) {
-                true
-            } else {
-                other_value > my_value
-            }
-        } else {
-            if rules.fallen_ace && (my_value == 1) && (other_value == MAX_VALUE) {
-                true
-            } else {
-                other_value < my_value
-            }
-        }
+    /// Whether this card is at least as high as `other` on every side, so under the basic rules
+    /// it takes everything `other` takes. Suits and the rules that look past single comparisons
+    /// (Same, Plus, Reverse) aren't considered.
+    pub fn dominates(&self, other: &Card) -> bool {
+        self.values
+            .iter()
+            .zip(&other.values)
+            .all(|(mine, theirs)| mine >= theirs)
     }
 
+    /// The card's value on the given side with its suit's modifier added, kept between 1 and A.
     pub fn get_modified_value(&self, modifiers: &Modifiers, direction: Direction) -> i32 {
-        self.values[direction as usize]
-            + (self.suit.map(|s| modifiers[s]).unwrap_or(0))
-                .min(MAX_VALUE)
-                .max(0)
+        (self.value(direction) + modifiers.modifier(self.suit)).clamp(1, MAX_VALUE)
     }
 
     pub fn get_modified_value_display(
@@ -236,53 +647,134 @@ impl Card {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
 pub struct GameMove {
     pub player: Player,
     pub card_idx: usize,
     pub placement: usize,
 }
 
-#[derive(Clone, Default)]
+#[derive(Copy, Clone, Default)]
 struct GameState {
     // 0, 1, 2
     // 3, 4, 5
     // 6, 7, 8
-    board: [Option<(Card, Player)>; 9],
-    hands: [[Option<(i32, Card)>; 10]; 2], // (id, card)
+    board: [Option<(CardId, Player)>; 9],
+    hands: [[Option<CardId>; HAND_SLOTS]; 2],
     modifiers: Modifiers,
     actual_hand_sizes: [usize; 2],
+    /// What the move that led to this state flipped.
+    captures: Captures,
+    /// Whose turn it is, once a move has been played or the game was built knowing it.
+    to_move: Option<Player>,
 }
+/// What a move changed, for undoing it in place.
+#[derive(Copy, Clone)]
+struct Undo {
+    mv: GameMove,
+    card: CardId,
+    /// The cells whose cards the move took, a bit per cell.
+    flipped: u16,
+    modifiers: Modifiers,
+    captures: Captures,
+    to_move: Option<Player>,
+}
+
 impl GameState {
+    /// Plays `mv` on this state, returning what's needed to undo it.
+    fn apply(&mut self, mv: &GameMove, rule_set: &RuleSet, cards: &CardDb) -> Undo {
+        let card = self.hands[mv.player][mv.card_idx].take().unwrap();
+        let mut undo = Undo {
+            mv: *mv,
+            card,
+            flipped: 0,
+            modifiers: self.modifiers,
+            captures: self.captures,
+            to_move: self.to_move,
+        };
+        self.actual_hand_sizes[mv.player] -= 1;
+        self.to_move = Some(mv.player.other());
+        self.board[mv.placement] = Some((card, mv.player));
+        undo.flipped = rule_set.resolve(self, mv.placement, mv.player, cards);
+        undo
+    }
+
+    fn undo(&mut self, undo: &Undo) {
+        let mv = &undo.mv;
+        self.board[mv.placement] = None;
+        for pos in (0..9).filter(|pos| undo.flipped & 1 << pos != 0) {
+            if let Some((_, owner)) = &mut self.board[pos] {
+                *owner = mv.player.other();
+            }
+        }
+        self.hands[mv.player][mv.card_idx] = Some(undo.card);
+        self.actual_hand_sizes[mv.player] += 1;
+        self.modifiers = undo.modifiers;
+        self.captures = undo.captures;
+        self.to_move = undo.to_move;
+    }
+
     fn is_game_over(&self) -> bool {
         self.board.iter().all(|x| x.is_some())
     }
 
     fn scores(&self) -> [usize; 2] {
-        let mut scores = self.actual_hand_sizes.clone();
+        let mut scores = self.actual_hand_sizes;
 
-        for space in &self.board {
-            if let Some((_, player)) = space {
-                scores[*player] += 1;
-            }
+        for (_, player) in self.board.iter().flatten() {
+            scores[*player] += 1;
         }
 
         scores
     }
 
-    fn eval_position(&self, player: Player) -> f64 {
+    fn eval_position(
+        &self,
+        player: Player,
+        weights: &EvalWeights,
+        cards: &CardDb,
+        reverse: bool,
+    ) -> f64 {
         let scores = self.scores();
+        let lead = (scores[player] as f64) - (scores[player.other()] as f64);
 
-        // If the game is over, then the score is either +/- infinity, or zero if there was a tie
+        // If the game is over, then the score is either +/- 100, or the tie's if there was a tie
         if self.is_game_over() {
             match scores[player].cmp(&scores[player.other()]) {
-                Ordering::Greater => 100f64,
-                Ordering::Equal => -30f64,
-                Ordering::Less => -100f64,
+                Ordering::Greater => 100f64 + weights.margin * lead,
+                Ordering::Equal => weights.tie,
+                Ordering::Less => -100f64 + weights.margin * lead,
             }
+        } else if weights.sides != 0.0 {
+            lead + weights.sides * self.exposed_sides(player, cards, reverse) as f64
         } else {
-            (scores[player] as f64) - (scores[player.other()] as f64)
+            lead
+        }
+    }
+
+    /// The side values `player`'s cards on the board turn toward empty cells, less the opponent's.
+    /// Under Reverse a low side is the hard one to take, so it counts for more.
+    fn exposed_sides(&self, player: Player, cards: &CardDb, reverse: bool) -> i32 {
+        let mut total = 0;
+        for (pos, (id, owner)) in self
+            .board
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, cell)| Some((pos, (*cell)?)))
+        {
+            for other in (0..9).filter(|&other| self.board[other].is_none()) {
+                if let Some(direction) = Game::adjacency(pos, other) {
+                    let value = cards[id].get_modified_value(&self.modifiers, direction);
+                    let strength = if reverse {
+                        MAX_VALUE + 1 - value
+                    } else {
+                        value
+                    };
+                    total += if owner == player { strength } else { -strength };
+                }
+            }
         }
+        total
     }
 
     fn get_possible_moves(
@@ -312,30 +804,65 @@ impl GameState {
     }
 }
 
+/// The moves played before the current state, so they can be undone.
+#[derive(Clone)]
+enum History {
+    /// Every earlier state, oldest first, with the move played from it. The interactive game keeps
+    /// these so earlier positions and the moves between them can be looked at.
+    Snapshots(VecDeque<(GameState, GameMove)>),
+    /// What each move since `start` changed, so it can be undone in place. The search's copies
+    /// keep these, since they play and undo millions of moves and only look at the current state.
+    Moves {
+        start: Box<GameState>,
+        undo: Vec<Undo>,
+    },
+}
+
+#[derive(Clone)]
 pub struct Game {
-    // last entry is current state
-    state_and_history: VecDeque<GameState>,
+    state: GameState,
+    history: History,
     rules: Rules,
+    /// Built from `rules`, and rebuilt whenever they change.
+    rule_set: RuleSet,
     humans: [bool; 2],
+    /// Which hand slots hold cards the hand is known to have, rather than ones it might. NPC hands
+    /// are laid out with the fixed cards first and the variable ones after them.
+    known: [[bool; HAND_SLOTS]; 2],
+    /// The values of the cards the states hold the IDs of, shared with the search's copies.
+    cards: Arc<CardDb>,
+    /// Under Chaos, the hand slot picked for the player about to move, which is the only card
+    /// they can play this turn. Only holds for the current state: the next move clears it, and
+    /// the search's copies only use it before their first move.
+    chaos_pick: Option<usize>,
+    /// What positions are worth to the search, which sets them on its copies.
+    eval_weights: EvalWeights,
 }
 impl Game {
     // Because of the order rule, it matters which player is human
-    pub fn new(human_color: Player) -> Self {
+    pub fn new(human_color: Player, cards: Arc<CardDb>) -> Self {
         Game {
-            state_and_history: {
-                let mut history = VecDeque::with_capacity(100);
-                history.push_back(Default::default());
-                history
-            },
+            state: Default::default(),
+            history: History::Snapshots(VecDeque::with_capacity(100)),
             rules: Default::default(),
+            rule_set: Default::default(),
             humans: {
                 let mut humans = [false; 2];
                 humans[human_color] = true;
                 humans
             },
+            known: [std::array::from_fn(|idx| idx < 5); 2],
+            cards,
+            chaos_pick: None,
+            eval_weights: EvalWeights::default(),
         }
     }
 
+    /// The card values the game looks its cards up in.
+    pub fn cards(&self) -> &CardDb {
+        &self.cards
+    }
+
     fn adjacency(from: usize, to: usize) -> Option<Direction> {
         Some(match (from, to) {
             (0, 1) | (1, 2) | (3, 4) | (4, 5) | (6, 7) | (7, 8) => Direction::East,
@@ -347,97 +874,794 @@ impl Game {
     }
 
     fn current_state(&self) -> &GameState {
-        self.state_and_history.back().unwrap()
+        &self.state
     }
 
-    // Note: directly modifies the current game state, doesn't affect history
-    pub fn set_cards_in_hand(
-        &mut self,
-        player: Player,
-        cards: &[(i32, Card); 5],
-        actual_size: usize,
-    ) {
-        let state = self.state_and_history.back_mut().unwrap();
+    fn current_state_mut(&mut self) -> &mut GameState {
+        &mut self.state
+    }
+
+    /// The state the history starts from.
+    fn initial_state(&self) -> &GameState {
+        match &self.history {
+            History::Snapshots(states) => states.front().map_or(&self.state, |(state, _)| state),
+            History::Moves { start, .. } => start,
+        }
+    }
+
+    /// The moves played to reach the current state, oldest first.
+    pub fn played_moves(&self) -> Vec<GameMove> {
+        match &self.history {
+            History::Snapshots(states) => states.iter().map(|(_, mv)| *mv).collect(),
+            History::Moves { undo, .. } => undo.iter().map(|undo| undo.mv).collect(),
+        }
+    }
+
+    /// The moves played so far, numbered, each with the card, the cell, how many cards it flipped,
+    /// and the score after it, like "3. You: Dodo in B2, flipped 1 (you 6, opponent 4)". For
+    /// checking the match in the tool against the one in the game.
+    pub fn move_list(&self, data: &Data) -> Vec<String> {
+        let states = match &self.history {
+            History::Snapshots(states) => states,
+            History::Moves { .. } => return Vec::new(),
+        };
+        let (mine, theirs) = if self.humans[Player::Red] && !self.humans[Player::Blue] {
+            (Player::Red, Player::Blue)
+        } else {
+            (Player::Blue, Player::Red)
+        };
+        let name = |player: Player| match (player == mine, self.humans[mine]) {
+            (true, true) => "you",
+            (false, true) => "opponent",
+            (true, false) => "blue",
+            (false, false) => "red",
+        };
+        states
+            .iter()
+            .enumerate()
+            .map(|(idx, (before, mv))| {
+                let after = states.get(idx + 1).map_or(&self.state, |(state, _)| state);
+                let card = before.hands[mv.player][mv.card_idx].unwrap();
+                let flipped = (0..9)
+                    .filter(|&pos| match (&before.board[pos], &after.board[pos]) {
+                        (Some((_, owner)), Some((_, new_owner))) => owner != new_owner,
+                        _ => false,
+                    })
+                    .count();
+                let scores = after.scores();
+                let mut description = format!(
+                    "{}. {}: {} in {}",
+                    idx + 1,
+                    capitalized(name(mv.player)),
+                    data.card_display_name(card.get()),
+                    cell_code(mv.placement)
+                );
+                if flipped > 0 {
+                    description.push_str(&format!(", flipped {}", flipped));
+                }
+                description.push_str(&format!(
+                    " ({} {}, {} {})",
+                    name(mine),
+                    scores[mine],
+                    name(theirs),
+                    scores[theirs]
+                ));
+                description
+            })
+            .collect()
+    }
+
+    /// `player`'s hand in words, like "your hand".
+    fn hand_name(&self, player: Player) -> String {
+        if self.humans[player] {
+            String::from("your hand")
+        } else if self.humans[player.other()] {
+            String::from("the opponent's hand")
+        } else {
+            format!("{}'s hand", player)
+        }
+    }
+
+    /// The move that played the card in `player`'s hand `slot`, counting from 1, if it's been
+    /// played.
+    pub fn move_playing_slot(&self, player: Player, slot: usize) -> Option<usize> {
+        self.played_moves()
+            .iter()
+            .position(|mv| mv.player == player && mv.card_idx == slot)
+            .map(|idx| idx + 1)
+    }
+
+    /// The error for entering a move with the card in `player`'s hand `slot` after it was played
+    /// already, or `None` if it hasn't been.
+    pub fn replayed_slot(&self, player: Player, slot: usize, data: &Data) -> Option<Inconsistency> {
+        let first = self.move_playing_slot(player, slot)?;
+        let card = self.initial_state().hands[player][slot]?;
+        Some(Inconsistency::CardPlayedTwice {
+            card: data.card_display_name(card.get()).to_string(),
+            first,
+            hand: self.hand_name(player),
+        })
+    }
+
+    /// Checks that the moves entered so far could have happened: no card was played twice, and
+    /// each hand has room left for the cards it's known to hold.
+    pub fn check_consistency(&self, data: &Data) -> Result<(), Inconsistency> {
+        let initial = self.initial_state();
+        let mut played: Vec<(Player, CardId, usize)> = Vec::with_capacity(9);
+        for (idx, mv) in self.played_moves().iter().enumerate() {
+            let card = match initial.hands[mv.player][mv.card_idx] {
+                Some(card) => card,
+                None => continue,
+            };
+            if let Some(&(_, _, first)) = played
+                .iter()
+                .find(|&&(player, id, _)| player == mv.player && id == card)
+            {
+                return Err(Inconsistency::CardPlayedTwice {
+                    card: data.card_display_name(card.get()).to_string(),
+                    first,
+                    hand: self.hand_name(mv.player),
+                });
+            }
+            played.push((mv.player, card, idx + 1));
+        }
+
+        let state = self.current_state();
+        for player in [Player::Blue, Player::Red] {
+            let known = state.hands[player]
+                .iter()
+                .zip(&self.known[player])
+                .filter(|(slot, &known)| slot.is_some() && known)
+                .count();
+            let left = state.actual_hand_sizes[player];
+            if known > left {
+                return Err(Inconsistency::HandSizeMismatch {
+                    hand: capitalized(&self.hand_name(player)),
+                    known,
+                    left,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the board against the owners seen in the game, `None` for an empty cell.
+    pub fn check_owners(&self, seen: &[Option<Player>; 9]) -> Result<(), Inconsistency> {
+        let board = self.current_state().board;
+        let wrong = (0..9)
+            .filter(|&pos| board[pos].map(|(_, owner)| owner) != seen[pos])
+            .collect::<Vec<_>>();
+        if wrong.is_empty() {
+            return Ok(());
+        }
+        let describe = |pos: usize| match board[pos] {
+            Some((_, owner)) => format!("a {} card in {}", owner, cell_code(pos)),
+            None => format!("nothing in {}", cell_code(pos)),
+        };
+        Err(Inconsistency::OwnershipMismatch {
+            expected: wrong
+                .iter()
+                .map(|&pos| describe(pos))
+                .collect::<Vec<_>>()
+                .join(", "),
+        })
+    }
+
+    /// The rules the moves entered lead to the owners seen in the game under, with one of the rules
+    /// that change what's flipped turned on or off, as `(rule name, whether it's on, rules)`. For
+    /// working out which rule the game is playing by when its board doesn't match, like a regional
+    /// rule that wasn't known about.
+    pub fn rules_matching_owners(
+        &self,
+        seen: &[Option<Player>; 9],
+    ) -> Vec<(&'static str, bool, Rules)> {
+        FLIP_RULES
+            .iter()
+            .filter_map(|&name| {
+                let on = !self.rules.flags()[Rules::NAMES.iter().position(|&n| n == name)?];
+                let mut rules = self.rules;
+                rules.set_rule_by_name(name, on);
+                let mut game = self.clone();
+                game.replay_with_rules(rules);
+                game.check_owners(seen).is_ok().then_some((name, on, rules))
+            })
+            .collect()
+    }
+
+    /// Plays the moves entered so far again under `rules`, which hold for the rest of the match.
+    pub fn replay_with_rules(&mut self, rules: Rules) {
+        let moves = self.played_moves();
+        self.undo_last_moves(moves.len());
+        self.set_rules(rules);
+        for mv in &moves {
+            self.apply_move(mv);
+        }
+    }
+
+    /// Finds the card that was just placed and the cards it flipped.
+    pub fn last_move_changes(&self) -> [CellChange; 9] {
+        let mut changes = [CellChange::Unchanged; 9];
+        match &self.history {
+            History::Snapshots(states) => {
+                // Compares the board against the state before the last move
+                let previous = match states.back() {
+                    Some((previous, _)) => previous,
+                    None => return changes,
+                };
+                for (pos, change) in changes.iter_mut().enumerate() {
+                    *change = match (&previous.board[pos], &self.state.board[pos]) {
+                        (None, Some(_)) => CellChange::Placed,
+                        (Some((_, before)), Some((_, after))) if before != after => {
+                            CellChange::Flipped
+                        }
+                        _ => CellChange::Unchanged,
+                    };
+                }
+            }
+            History::Moves { undo, .. } => {
+                if let Some(undo) = undo.last() {
+                    for (pos, change) in changes.iter_mut().enumerate() {
+                        if undo.flipped & 1 << pos != 0 {
+                            *change = CellChange::Flipped;
+                        }
+                    }
+                    changes[undo.mv.placement] = CellChange::Placed;
+                }
+            }
+        }
+        changes
+    }
+
+    /// How the cards flipped by the last move were taken.
+    pub fn last_move_captures(&self) -> Captures {
+        self.current_state().captures
+    }
+
+    /// Computes the state that playing `mv` would lead to, without adding it to the history.
+    fn state_after(&self, mv: &GameMove) -> GameState {
+        let mut new_state = *self.current_state();
+        new_state.apply(mv, &self.rule_set, &self.cards);
+        new_state
+    }
+
+    /// Returns the cells whose cards would be flipped by playing `mv`, without playing it.
+    pub fn preview_move(&self, mv: &GameMove) -> Vec<usize> {
+        let current = self.current_state();
+        let next = self.state_after(mv);
+        (0..9)
+            .filter(|&pos| match (&current.board[pos], &next.board[pos]) {
+                (Some((_, before)), Some((_, after))) => before != after,
+                _ => false,
+            })
+            .collect()
+    }
+
+    // Note: directly modifies the current game state, doesn't affect history. Up to `HAND_SLOTS`
+    // cards can be given, for hands that are only partly known, like an NPC's.
+    pub fn set_cards_in_hand(&mut self, player: Player, cards: &[CardId], actual_size: usize) {
+        let state = self.current_state_mut();
         let hand = &mut state.hands[player];
 
-        for i in 0..5 {
-            hand[i] = Some(cards[i].clone());
+        for (slot, card) in hand.iter_mut().zip(cards.iter()) {
+            *slot = Some(*card);
         }
-        for i in 5..hand.len() {
-            hand[i] = None;
+        for slot in hand.iter_mut().skip(cards.len()) {
+            *slot = None;
         }
 
         state.actual_hand_sizes[player] = actual_size;
     }
 
+    // Note: directly modifies the current game state, doesn't affect history. Puts a card on the
+    // board as if it had been played there, without flipping anything, so set the rules first.
+    pub fn place_card(&mut self, pos: usize, card: CardId, owner: Player) {
+        let mut modifiers = self.current_state().modifiers;
+        self.rule_set
+            .adjust_modifiers(&mut modifiers, &self.cards[card]);
+        let state = self.current_state_mut();
+        state.modifiers = modifiers;
+        state.board[pos] = Some((card, owner));
+    }
+
+    // Note: directly modifies the current game state, doesn't affect history. For correcting the
+    // board when it doesn't match the game's, like after a move was entered wrong.
+    pub fn set_owner(&mut self, pos: usize, owner: Player) -> bool {
+        let state = self.current_state_mut();
+        match &mut state.board[pos] {
+            Some((_, cell_owner)) => {
+                *cell_owner = owner;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The slot holding the card with the given ID in `player`'s hand, if it hasn't been played.
+    pub fn find_in_hand(&self, player: Player, card_id: CardId) -> Option<usize> {
+        self.current_state().hands[player]
+            .iter()
+            .position(|&slot| slot == Some(card_id))
+    }
+
+    /// Gives `player` an NPC's cards and rules. Its fixed cards are known to be in its hand, and
+    /// its variable cards might be, with as many of them held as it draws. Variable cards that
+    /// don't fit in the hand's slots are left out.
     pub fn set_cards_for_npc(&mut self, player: Player, data: &Data, npc_name: &str) {
         let npc = data.npcs_by_name.get(npc_name).unwrap();
-        let state = self.state_and_history.back_mut().unwrap();
-        let hand = &mut state.hands[player];
-        for i in 0..5 {
-            if npc.fixed_cards[i] != 0 {
-                hand[i] = Some((
-                    npc.fixed_cards[i],
-                    data.get_card(npc.fixed_cards[i]).unwrap().clone(),
+        let cards = npc
+            .fixed
+            .iter()
+            .map(|&id| (id, true))
+            .chain(npc.variable.iter().map(|&id| (id, false)))
+            .take(HAND_SLOTS)
+            .collect::<Vec<_>>();
+
+        let state = self.current_state_mut();
+        for (slot, &(id, _)) in state.hands[player].iter_mut().zip(&cards) {
+            *slot = Some(id);
+        }
+        for slot in state.hands[player].iter_mut().skip(cards.len()) {
+            *slot = None;
+        }
+        state.actual_hand_sizes[player] = (npc.fixed.len() + npc.draws).min(cards.len());
+        for (idx, known) in self.known[player].iter_mut().enumerate() {
+            *known = cards.get(idx).is_some_and(|&(_, known)| known);
+        }
+
+        self.rules = npc.rules;
+        self.rules_changed();
+    }
+
+    /// Sets the hand slot Chaos picked for the player about to move, or `None` if it isn't known,
+    /// in which case the search treats the pick as still to come.
+    pub fn set_chaos_pick(&mut self, slot: Option<usize>) {
+        self.chaos_pick = slot;
+    }
+
+    /// The slot Chaos picked for the current turn, if it's known.
+    fn current_chaos_pick(&self) -> Option<usize> {
+        match &self.history {
+            History::Moves { undo, .. } if !undo.is_empty() => None,
+            _ => self.chaos_pick,
+        }
+    }
+
+    /// Whose turn it is, if the game knows: after a move, or when it was built with
+    /// [`GameBuilder::to_move`].
+    pub fn to_move(&self) -> Option<Player> {
+        self.current_state().to_move
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn rule_set(&self) -> &RuleSet {
+        &self.rule_set
+    }
+
+    /// Adds rules on top of the NPC's own, like the Gold Saucer's current regional rules.
+    pub fn add_rules(&mut self, rules: &Rules) {
+        self.rules.merge(rules);
+        self.rules_changed();
+    }
+
+    /// Replaces the rules entirely, for when the ones in play differ from the NPC's, like after the
+    /// roulette or when the data is wrong.
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+        self.rules_changed();
+    }
+
+    /// Rebuilds what's derived from the rules. They're set up before the match starts, so only
+    /// the current state needs updating.
+    fn rules_changed(&mut self) {
+        self.rule_set = RuleSet::new(&self.rules);
+        let step = self.rule_set.suit_step();
+        self.current_state_mut().modifiers.step = step;
+    }
+
+    /// The card in the given slot of `player`'s hand, if it hasn't been played.
+    pub fn hand_card(&self, player: Player, idx: usize) -> Option<&Card> {
+        self.current_state().hands[player][idx].map(|id| &self.cards[id])
+    }
+
+    pub fn player_hand_card_id(&self, player: Player, idx: usize) -> CardId {
+        self.current_state().hands[player][idx].unwrap()
+    }
+
+    pub fn player_hand_card_name<'b>(&self, player: Player, idx: usize, data: &'b Data) -> &'b str {
+        data.card_display_name(self.player_hand_card_id(player, idx).get())
+    }
+
+    /// Describes the line [`search::expected_line`] expects after the player's move, like "Ifrit
+    /// Trainer will likely play Bomb in B2, then play your Moogle card in C1.", or `None` if there's
+    /// nothing left to play after it. The cards are looked up in the current hands, so this is for
+    /// before the player's move is played.
+    ///
+    /// [`search::expected_line`]: crate::search::expected_line
+    pub fn describe_expected_line(
+        &self,
+        line: &[GameMove],
+        opponent_name: &str,
+        data: &Data,
+    ) -> Option<String> {
+        let (reply, answer) = match line {
+            [] => return None,
+            [reply, rest @ ..] => (reply, rest.first()),
+        };
+        let mut description = format!(
+            "{} will likely play {} in {}",
+            opponent_name,
+            self.player_hand_card_name(reply.player, reply.card_idx, data),
+            cell_code(reply.placement)
+        );
+        if let Some(answer) = answer {
+            description.push_str(&format!(
+                ", then play your {} card in {}",
+                self.player_hand_card_name(answer.player, answer.card_idx, data),
+                cell_code(answer.placement)
+            ));
+        }
+        description.push('.');
+        Some(description)
+    }
+
+    /// Explains why `mv` is worth playing, in a few sentences: how many cards it flips now, which
+    /// sides of the player's cards are left facing empty cells, how it changes the most cards the
+    /// opponent could flip next, and what the player's next move in `line` (the line expected
+    /// after it, from [`search::expected_line`]) takes. Like [`Game::describe_expected_line`], for
+    /// before the move is played.
+    ///
+    /// [`search::expected_line`]: crate::search::expected_line
+    pub fn explain_move(
+        &self,
+        mv: &GameMove,
+        line: &[GameMove],
+        opponent_name: &str,
+        data: &Data,
+    ) -> String {
+        let player = mv.player;
+        let opponent = player.other();
+        let mut sentences = Vec::new();
+
+        let flips = self.preview_move(mv);
+        sentences.push(match flips.len() {
+            0 => String::from("It doesn't flip anything now."),
+            count => format!(
+                "It flips {} now ({}).",
+                cards_count(count),
+                flips
+                    .iter()
+                    .map(|&pos| cell_code(pos))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+
+        let mut after = self.truncate_history_and_clone();
+        after.apply_move(mv);
+        let state = after.current_state();
+        let mut open = Vec::new();
+        for (pos, (id, _)) in state
+            .board
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, cell)| Some((pos, (*cell)?)))
+            .filter(|(_, (_, owner))| *owner == player)
+        {
+            let sides = (0..9)
+                .filter(|&other| state.board[other].is_none())
+                .filter_map(|other| Game::adjacency(pos, other))
+                .map(|direction| {
+                    format!(
+                        "{} {}",
+                        direction.name(),
+                        self.cards[id].get_modified_value_display(&state.modifiers, direction)
+                    )
+                })
+                .collect::<Vec<_>>();
+            if !sides.is_empty() {
+                open.push(format!(
+                    "{} in {} ({})",
+                    data.card_display_name(id.get()),
+                    cell_code(pos),
+                    sides.join(", ")
                 ));
-            } else {
-                hand[i] = None;
             }
         }
+        sentences.push(if open.is_empty() {
+            String::from("None of your cards are left open.")
+        } else {
+            format!("Left open: {}.", open.join("; "))
+        });
+
+        let (threat_before, _) = self.most_flips(opponent);
+        let (threat_after, threat) = after.most_flips(opponent);
+        let example = |threat: GameMove| {
+            format!(
+                "like with {} in {}",
+                after.player_hand_card_name(opponent, threat.card_idx, data),
+                cell_code(threat.placement)
+            )
+        };
+        match (threat_after.cmp(&threat_before), threat) {
+            (Ordering::Less, _) => sentences.push(format!(
+                "It cuts the most cards {} can flip next from {} to {}.",
+                opponent_name, threat_before, threat_after
+            )),
+            (Ordering::Equal, Some(threat)) => sentences.push(format!(
+                "{} can still flip {} next, {}.",
+                opponent_name,
+                cards_count(threat_after),
+                example(threat)
+            )),
+            (Ordering::Greater, Some(threat)) => sentences.push(format!(
+                "It lets {} flip {} next, {}.",
+                opponent_name,
+                cards_count(threat_after),
+                example(threat)
+            )),
+            _ => {}
+        }
 
-        for i in 0..5 {
-            if npc.variable_cards[i] != 0 {
-                hand[i + 5] = Some((
-                    npc.variable_cards[i],
-                    data.get_card(npc.variable_cards[i]).unwrap().clone(),
+        if let [reply, answer, ..] = line {
+            after.apply_move(reply);
+            let taken = after.preview_move(answer).len();
+            if taken > 0 {
+                sentences.push(format!(
+                    "It sets up flipping {} with your {} card in {} after {}'s likely reply.",
+                    cards_count(taken),
+                    self.player_hand_card_name(player, answer.card_idx, data),
+                    cell_code(answer.placement),
+                    opponent_name
                 ));
+            }
+        }
+        sentences.join(" ")
+    }
+
+    /// The most cards `player` could flip with one move from here, with a move that does, or
+    /// `None` if none flips any.
+    fn most_flips(&self, player: Player) -> (usize, Option<GameMove>) {
+        let mut moves = Vec::with_capacity(100);
+        self.current_state()
+            .get_possible_moves(player, false, &mut moves);
+        moves
+            .iter()
+            .map(|mv| (self.preview_move(mv).len(), *mv))
+            .filter(|(flips, _)| *flips > 0)
+            .max_by_key(|(flips, _)| *flips)
+            .map_or((0, None), |(flips, mv)| (flips, Some(mv)))
+    }
+
+    /// Lists the cards `player` started with as `(slot, id, card, status)`.
+    pub fn hand_overview(&self, player: Player) -> Vec<(usize, CardId, &Card, HandCardStatus)> {
+        let initial_state = self.initial_state();
+        let initial = &initial_state.hands[player];
+        let current = &self.current_state().hands[player];
+        let known = &self.known[player];
+
+        let known_count = (0..initial.len())
+            .filter(|&idx| known[idx] && initial[idx].is_some())
+            .count();
+        let possible_held = initial_state.actual_hand_sizes[player].saturating_sub(known_count);
+        let possible_played = (0..initial.len())
+            .filter(|&idx| !known[idx] && initial[idx].is_some() && current[idx].is_none())
+            .count();
+
+        initial
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, slot)| {
+                let id = (*slot)?;
+                let status = if current[idx].is_none() {
+                    HandCardStatus::Played
+                } else if known[idx] {
+                    HandCardStatus::InHand
+                } else if possible_played >= possible_held {
+                    HandCardStatus::RuledOut
+                } else {
+                    HandCardStatus::Possible
+                };
+                Some((idx, id, &self.cards[id], status))
+            })
+            .collect()
+    }
+
+    fn hand_panel(&self, player: Player, data: &Data) -> Vec<String> {
+        let mut lines = vec![String::from(if self.humans[player] {
+            "Your hand:"
+        } else {
+            "Opponent's hand:"
+        })];
+        for (_, id, card, status) in self.hand_overview(player) {
+            let color = match status {
+                HandCardStatus::InHand => player.display_color(),
+                HandCardStatus::Possible => Color::Yellow,
+                HandCardStatus::Played | HandCardStatus::RuledOut => Color::DarkGray,
+            };
+            lines.push(paint(
+                &format!(
+                    "  {} ({}) — {}",
+                    data.card_display_name(id.get()),
+                    card,
+                    status.label()
+                ),
+                color,
+            ));
+        }
+        lines
+    }
+
+    /// Describes the board in words, one cell per line, like "Top left (A1): your Dodo, 4 north, 2
+    /// east, 3 south, 2 west, just placed", followed by the score. Built from the same state as the
+    /// grid, for screen readers and anyone who'd rather read than scan a drawing.
+    pub fn describe_board(&self, data: &Data) -> String {
+        let state = self.current_state();
+        let changes = self.last_move_changes();
+        let owner_name = |player: Player| {
+            if self.humans[player] {
+                "your"
             } else {
-                hand[i + 5] = None;
+                "the opponent's"
             }
+        };
+
+        let mut description = String::new();
+        for (pos, name) in CELL_NAMES.iter().enumerate() {
+            description.push_str(&format!("{} ({}): ", name, cell_code(pos)));
+            let (id, owner) = match state.board[pos] {
+                Some(cell) => cell,
+                None => {
+                    description.push_str("empty\n");
+                    continue;
+                }
+            };
+            let (values, suit, _) = self.board_cell(pos).unwrap();
+            description.push_str(&format!(
+                "{} {}, {} north, {} east, {} south, {} west",
+                owner_name(owner),
+                data.card_display_name(id.get()),
+                values[0],
+                values[1],
+                values[2],
+                values[3]
+            ));
+            if let Some(suit) = suit.name() {
+                description.push_str(&format!(", {}", suit));
+            }
+            match changes[pos] {
+                CellChange::Unchanged => {}
+                CellChange::Placed => description.push_str(", just placed"),
+                CellChange::Flipped => description.push_str(", just flipped"),
+            }
+            description.push('\n');
         }
 
-        state.actual_hand_sizes[player] = 5;
-        self.rules = npc.rules.clone();
+        let scores = self.scores();
+        let (mine, theirs) = if self.humans[Player::Red] && !self.humans[Player::Blue] {
+            (Player::Red, Player::Blue)
+        } else {
+            (Player::Blue, Player::Red)
+        };
+        description.push_str(&format!(
+            "Score: {} {}, {} {}\n",
+            if self.humans[mine] { "you" } else { "blue" },
+            scores[mine],
+            if self.humans[mine] { "opponent" } else { "red" },
+            scores[theirs]
+        ));
+        description
     }
 
-    pub fn player_hand_card_name<'a, 'b>(
-        &'a self,
-        player: Player,
-        idx: usize,
-        data: &'b Data,
-    ) -> &'b String {
-        let id = self.current_state().hands[player][idx].as_ref().unwrap().0;
-        data.card_names.get(&id).unwrap()
+    /// The board display with `player`'s hand panel to the right of it. Depending on
+    /// [`set_board_view`], the grid may be left out, and the board may be described in words below.
+    pub fn display_with_hand_panel(&self, player: Player, data: &Data) -> String {
+        let board = if BOARD_GRID_ENABLED.load(atomic::Ordering::Relaxed) {
+            self.to_string()
+        } else {
+            String::new()
+        };
+        let board_lines = board.lines().collect::<Vec<_>>();
+        let width = board_lines
+            .iter()
+            .map(|line| visible_width(line))
+            .max()
+            .unwrap_or(0);
+        let panel = self.hand_panel(player, data);
+
+        let mut result = String::new();
+        for row in 0..board_lines.len().max(panel.len()) {
+            let board_line = board_lines.get(row).copied().unwrap_or("");
+            match panel.get(row) {
+                Some(panel_line) if board_lines.is_empty() => {
+                    result.push_str(&format!("{}\n", panel_line))
+                }
+                Some(panel_line) => result.push_str(&format!(
+                    "{}{}   {}\n",
+                    board_line,
+                    " ".repeat(width - visible_width(board_line)),
+                    panel_line
+                )),
+                None => result.push_str(&format!("{}\n", board_line)),
+            }
+        }
+        if BOARD_DESCRIPTION_ENABLED.load(atomic::Ordering::Relaxed) {
+            result.push_str(&self.describe_board(data));
+        }
+        result
+    }
+
+    /// The card in a board cell as its values (north, east, south, west, with modifiers applied),
+    /// suit, and owner.
+    pub fn board_cell(&self, pos: usize) -> Option<([String; 4], Suit, Player)> {
+        let state = self.current_state();
+        state.board[pos].map(|(id, player)| {
+            let card = &self.cards[id];
+            let values = [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ]
+            .map(|direction| card.get_modified_value_display(&state.modifiers, direction));
+            (values, card.suit, player)
+        })
+    }
+
+    pub fn hand_size(&self, player: Player) -> usize {
+        self.current_state().actual_hand_sizes[player]
     }
 
-    fn get_display(&self, pos: usize, dir: Direction) -> CString {
+    /// Each player's current score: their cards on the board plus the ones still in their hand.
+    pub fn scores(&self) -> [usize; 2] {
+        self.current_state().scores()
+    }
+
+    fn get_display(&self, pos: usize, dir: Direction, change: CellChange, color: bool) -> String {
         let state = self.current_state();
         state.board[pos]
-            .as_ref()
-            .map(|(card, player)| {
-                card.get_modified_value_display(&state.modifiers, dir)
-                    .color(player.display_color())
+            .map(|(id, player)| {
+                let value = self.cards[id].get_modified_value_display(&state.modifiers, dir);
+                if change == CellChange::Unchanged {
+                    paint_if(color, &value, player.display_color())
+                } else {
+                    paint_emphasized(color, &value, player.display_color())
+                }
             })
-            .unwrap_or_else(|| " ".to_string().color(Color::Black))
+            .unwrap_or_else(|| paint_if(color, " ", Color::Black))
     }
 
-    fn get_hand_display(&self, player: Player) -> CString {
-        self.current_state().actual_hand_sizes[player]
-            .to_string()
-            .color(player.display_color())
+    fn get_hand_display(&self, player: Player, color: bool) -> String {
+        paint_if(
+            color,
+            &self.current_state().actual_hand_sizes[player].to_string(),
+            player.display_color(),
+        )
     }
 
-    fn get_suit_display(&self, pos: usize) -> CString {
+    fn get_suit_display(&self, pos: usize, color: bool) -> String {
         self.current_state().board[pos]
-            .as_ref()
-            .map(|(card, player)| {
-                card.suit
-                    .map(|suit| suit.to_string().color(player.display_color()))
-                    .unwrap_or_else(|| " ".color(player.display_color()))
+            .map(|(id, player)| {
+                paint_if(
+                    color,
+                    &self.cards[id].suit.to_string(),
+                    player.display_color(),
+                )
             })
-            .unwrap_or_else(|| " ".color(Color::Black))
+            .unwrap_or_else(|| paint_if(color, " ", Color::Black))
     }
 }
+/// Folds `value` into the transposition table key `key` (splitmix64's finalizer), so that every
+/// bit of the key depends on every value mixed in.
+fn mix_key(key: u64, value: u64) -> u64 {
+    let mut x = key ^ value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
 impl SearchableGame for Game {
     type Move = GameMove;
     type Player = Player;
@@ -447,53 +1671,92 @@ impl SearchableGame for Game {
             player,
             self.humans[player] && self.rules.order,
             buffer,
-        )
+        );
+        if let Some(slot) = self.current_chaos_pick() {
+            if buffer.iter().any(|mv| mv.card_idx == slot) {
+                buffer.retain(|mv| mv.card_idx == slot);
+            }
+        }
+    }
+
+    /// Under Chaos, each card in the hand is picked with the same chance. For an NPC whose hand is
+    /// only partly known, each of its fixed cards is one of the cards it holds, and the chance left
+    /// over for the cards it drew is split evenly between the variable cards it might hold.
+    fn chance_outcomes(&self, moves: &[GameMove], outcomes: &mut Vec<(Vec<GameMove>, f64)>) {
+        outcomes.clear();
+        if !self.rules.chaos || moves.is_empty() {
+            return;
+        }
+        let player = moves[0].player;
+        let mut slots = moves.iter().map(|mv| mv.card_idx).collect::<Vec<_>>();
+        slots.sort_unstable();
+        slots.dedup();
+        if slots.len() < 2 {
+            return;
+        }
+
+        let known = &self.known[player];
+        let fixed = slots.iter().filter(|&&slot| known[slot]).count();
+        let variable = slots.len() - fixed;
+        // Once as many variable cards have been played as it drew, the rest can't be picked
+        let held = if variable == 0 {
+            fixed
+        } else {
+            self.current_state().actual_hand_sizes[player]
+                .max(fixed)
+                .max(1)
+        };
+        let fixed_chance = 1.0 / held as f64;
+        let variable_chance = (held - fixed) as f64 / held as f64 / variable.max(1) as f64;
+        outcomes.extend(slots.into_iter().filter_map(|slot| {
+            let chance = if known[slot] {
+                fixed_chance
+            } else {
+                variable_chance
+            };
+            if chance == 0.0 {
+                return None;
+            }
+            let slot_moves = moves
+                .iter()
+                .filter(|mv| mv.card_idx == slot)
+                .copied()
+                .collect();
+            Some((slot_moves, chance))
+        }));
     }
 
     fn evaluate_current_position_for(&self, player: Self::Player) -> f64 {
-        self.current_state().eval_position(player)
+        self.current_state().eval_position(
+            player,
+            &self.eval_weights,
+            &self.cards,
+            self.rules.reverse,
+        )
     }
 
     fn apply_move(&mut self, mv: &Self::Move) {
-        let mut new_state = self.current_state().clone();
-        let (_, played_card) = new_state.hands[mv.player][mv.card_idx].take().unwrap();
-        new_state.actual_hand_sizes[mv.player] -= 1;
-
-        for possibly_adjacent in 0..9 {
-            if let Some(direction) = Game::adjacency(possibly_adjacent, mv.placement) {
-                if let Some((ref card, ref mut owner)) = new_state.board[possibly_adjacent] {
-                    if card.is_flipped_by(
-                        &played_card,
-                        direction,
-                        &new_state.modifiers,
-                        &self.rules,
-                    ) {
-                        *owner = mv.player;
-                    }
-                }
+        match &mut self.history {
+            History::Snapshots(states) => {
+                states.push_back((self.state, *mv));
+                self.state.apply(mv, &self.rule_set, &self.cards);
+                self.chaos_pick = None;
             }
-        }
-
-        // TODO: handle SAME and PLUS rules
-
-        if self.rules.ascension {
-            if let Some(suit) = played_card.suit {
-                new_state.modifiers[suit] += 1;
+            History::Moves { undo, .. } => {
+                undo.push(self.state.apply(mv, &self.rule_set, &self.cards))
             }
         }
-        if self.rules.decension {
-            if let Some(suit) = played_card.suit {
-                new_state.modifiers[suit] -= 1;
-            }
-        }
-
-        new_state.board[mv.placement] = Some((played_card, mv.player));
-        self.state_and_history.push_back(new_state);
     }
 
     fn undo_last_moves(&mut self, n: usize) {
         for _ in 0..n {
-            self.state_and_history.pop_back();
+            match &mut self.history {
+                History::Snapshots(states) => {
+                    self.state = states.pop_back().unwrap().0;
+                    self.chaos_pick = None;
+                }
+                History::Moves { undo, .. } => self.state.undo(&undo.pop().unwrap()),
+            }
         }
     }
 
@@ -513,120 +1776,317 @@ impl SearchableGame for Game {
 
     fn truncate_history_and_clone(&self) -> Self {
         Game {
-            state_and_history: {
-                let mut state = VecDeque::with_capacity(10);
-                state.push_back(self.current_state().clone());
-                state
+            state: self.state,
+            history: History::Moves {
+                start: Box::new(self.state),
+                undo: Vec::with_capacity(9),
             },
-            rules: self.rules.clone(),
-            humans: self.humans.clone(),
+            rules: self.rules,
+            rule_set: self.rule_set.clone(),
+            humans: self.humans,
+            known: self.known,
+            cards: Arc::clone(&self.cards),
+            chaos_pick: self.chaos_pick,
+            eval_weights: self.eval_weights,
+        }
+    }
+
+    fn is_perfect_information(&self) -> bool {
+        let state = self.current_state();
+        !self.rules.chaos
+            && [Player::Red, Player::Blue].iter().all(|&player| {
+                state.hands[player]
+                    .iter()
+                    .zip(&self.known[player])
+                    .all(|(slot, &known)| slot.is_none() || known)
+            })
+    }
+
+    fn set_eval_weights(&mut self, weights: &EvalWeights) {
+        self.eval_weights = *weights;
+    }
+
+    fn material_lead(&self, player: Player) -> f64 {
+        let scores = self.scores();
+        (scores[player] as f64) - (scores[player.other()] as f64)
+    }
+
+    /// A card stays in the same hand slot until it's played, so the slot and the cell make a move.
+    fn history_key(&self, mv: &GameMove) -> Option<usize> {
+        Some((mv.card_idx * 9 + mv.placement) * 2 + mv.player as usize)
+    }
+
+    /// Mixes in the board, the cards left in each hand and which of them are known, how many
+    /// cards each hand really holds, the rules, and who's human (for Order). Under Ascension or
+    /// Descension it mixes in each suit's count too: a position can set those apart from the board
+    /// (see `GameBuilder::suit_count`), so two positions with the same board can still have
+    /// different modifiers. A Chaos pick is only ever for the position a search starts from, which
+    /// isn't looked up.
+    fn position_key(&self, to_move: Player) -> Option<u64> {
+        let state = self.current_state();
+        let rules = &self.rules;
+        let flags = [
+            rules.same,
+            rules.plus,
+            rules.order,
+            rules.chaos,
+            rules.reverse,
+            rules.fallen_ace,
+            rules.ascension,
+            rules.decension,
+            self.humans[Player::Red],
+            self.humans[Player::Blue],
+            to_move == Player::Red,
+        ]
+        .iter()
+        .fold(0u64, |flags, &flag| flags << 1 | flag as u64);
+        let mut key = mix_key(
+            0,
+            flags
+                | (state.actual_hand_sizes[Player::Red] as u64) << 16
+                | (state.actual_hand_sizes[Player::Blue] as u64) << 24,
+        );
+        for cell in &state.board {
+            key = mix_key(
+                key,
+                cell.map_or(0, |(id, owner)| (id.get() as u64) << 1 | owner as u64),
+            );
+        }
+        for player in [Player::Red, Player::Blue] {
+            for (slot, &known) in state.hands[player].iter().zip(&self.known[player]) {
+                key = mix_key(
+                    key,
+                    slot.map_or(0, |id| (id.get() as u64) << 1 | known as u64),
+                );
+            }
+        }
+        if rules.ascension || rules.decension {
+            // 12 bits for each of the five counts, which is plenty for any override
+            key = mix_key(
+                key,
+                state
+                    .modifiers
+                    .counts
+                    .iter()
+                    .fold(0u64, |counts, &count| counts << 12 | (count as u64 & 0xfff)),
+            );
+        }
+        Some(key)
+    }
+
+    fn canonical_move(&self, mv: &Self::Move) -> Self::Move {
+        GameMove {
+            placement: symmetry::canonical_cell(&self.state, &self.cards, mv.placement),
+            ..*mv
         }
     }
 }
 impl Display for Game {
+    //        1     2     3
     //   ┌─────┬─────┬─────┐
-    //   │  0S │  0S │  0S │
+    // A │  0S │  0S │  0S │
     //   │ 0 0 │ 0 0 │ 0 0 │
     //   │  0  │  0  │  0  │
     //   ├─────┼─────┼─────┤
-    //   │  0  │  0  │  0  │
-    // 4 │ 0 0 │ 0 0 │ 0 0 │  5
-    //   │  0  │  0  │  0  │
+    // B │+ 0  │* 0  │     │
+    // 4 │ 0 0 │ 0 0 │ B3  │ 5
+    //   │  0  │  0  │     │
     //   ├─────┼─────┼─────┤
-    //   │  0  │  0  │  0  │
+    // C │  0  │  0  │  0  │
     //   │ 0 0 │ 0 0 │ 0 0 │
     //   │  0  │  0  │  0  │
     //   └─────┴─────┴─────┘
+    // The column numbers, row letters, and empty cell codes are only shown with board labels on.
+    // The card played last is marked with a +, and the cards it flipped with a *. Without colors,
+    // the middle of each card shows its owner's letter instead, like `4 B 3`, and in plain ASCII
+    // the lines are drawn with -, |, and +.
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.draw_board(f, &[None; 9], GridStyle::current())
+    }
+}
+
+/// The board with each empty cell shaded by a value, from [`Game::heatmap`].
+pub struct Heatmap<'a> {
+    game: &'a Game,
+    values: [Option<f64>; 9],
+}
+impl Display for Heatmap<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.game.draw_board(f, &self.values, GridStyle::current())
+    }
+}
+
+impl Game {
+    /// The board with each empty cell that has a value showing it, shaded from red for the lowest
+    /// to green for the highest, like the score of the best move into each cell.
+    pub fn heatmap(&self, values: [Option<f64>; 9]) -> Heatmap<'_> {
+        Heatmap { game: self, values }
+    }
+
+    /// The value of each empty cell in a heatmap in words, one cell per line, like "Top left (A1):
+    /// 12".
+    pub fn describe_heatmap(&self, values: &[Option<f64>; 9]) -> String {
+        let mut description = String::new();
+        for (pos, name) in CELL_NAMES.iter().enumerate() {
+            if let Some(value) = values[pos] {
+                description.push_str(&format!("{} ({}): {:.0}\n", name, cell_code(pos), value));
+            }
+        }
+        description
+    }
+
+    /// Like [`Game::display_with_hand_panel`], the heatmap as a grid, in words, or both, depending
+    /// on [`set_board_view`].
+    pub fn display_heatmap(&self, values: [Option<f64>; 9]) -> String {
+        let mut result = String::new();
+        if BOARD_GRID_ENABLED.load(atomic::Ordering::Relaxed) {
+            result.push_str(&self.heatmap(values).to_string());
+        }
+        if BOARD_DESCRIPTION_ENABLED.load(atomic::Ordering::Relaxed) {
+            result.push_str(&self.describe_heatmap(&values));
+        }
+        result
+    }
+
+    /// One line of an empty cell in a heatmap: the value on the middle line, and blank otherwise,
+    /// with the whole cell shaded by how `value` compares to the lowest and highest in `heat`.
+    fn heat_cell_line(value: f64, heat: &[Option<f64>; 9], middle: bool, color: bool) -> String {
+        let text = if middle {
+            format!("{:^5}", format!("{:.0}", value))
+        } else {
+            " ".repeat(5)
+        };
+        if !color {
+            return text;
+        }
+        let values = heat.iter().flatten();
+        let lowest = values.clone().copied().fold(f64::INFINITY, f64::min);
+        let highest = values.copied().fold(f64::NEG_INFINITY, f64::max);
+        let shade = if value >= highest {
+            Color::Green
+        } else if (value - lowest) * 2.0 >= highest - lowest {
+            Color::Yellow
+        } else {
+            Color::Red
+        };
+        text.color(Color::Black).bg_color(shade).to_string()
+    }
+
+    fn draw_board(
+        &self,
+        f: &mut impl std::fmt::Write,
+        heat: &[Option<f64>; 9],
+        style: GridStyle,
+    ) -> std::fmt::Result {
         use Direction::*;
 
-        writeln!(f, "  ┌─────┬─────┬─────┐")?;
-        writeln!(
-            f,
-            "  │  {}{} │  {}{} │  {}{} │",
-            self.get_display(0, North),
-            self.get_suit_display(0),
-            self.get_display(1, North),
-            self.get_suit_display(1),
-            self.get_display(2, North),
-            self.get_suit_display(2),
-        )?;
-        writeln!(
-            f,
-            "  │ {} {} │ {} {} │ {} {} │",
-            self.get_display(0, West),
-            self.get_display(0, East),
-            self.get_display(1, West),
-            self.get_display(1, East),
-            self.get_display(2, West),
-            self.get_display(2, East)
-        )?;
-        writeln!(
-            f,
-            "  │  {}  │  {}  │  {}  │",
-            self.get_display(0, South),
-            self.get_display(1, South),
-            self.get_display(2, South)
-        )?;
-        writeln!(f, "  ├─────┼─────┼─────┤")?;
-        writeln!(
-            f,
-            "  │  {}{} │  {}{} │  {}{} │",
-            self.get_display(3, North),
-            self.get_suit_display(3),
-            self.get_display(4, North),
-            self.get_suit_display(4),
-            self.get_display(5, North),
-            self.get_suit_display(5),
-        )?;
-        writeln!(
-            f,
-            "{} │ {} {} │ {} {} │ {} {} │ {}",
-            self.get_hand_display(Player::Blue),
-            self.get_display(3, West),
-            self.get_display(3, East),
-            self.get_display(4, West),
-            self.get_display(4, East),
-            self.get_display(5, West),
-            self.get_display(5, East),
-            self.get_hand_display(Player::Red),
-        )?;
-        writeln!(
-            f,
-            "  │  {}  │  {}  │  {}  │",
-            self.get_display(3, South),
-            self.get_display(4, South),
-            self.get_display(5, South)
-        )?;
-        writeln!(f, "  ├─────┼─────┼─────┤")?;
-        writeln!(
-            f,
-            "  │  {}{} │  {}{} │  {}{} │",
-            self.get_display(6, North),
-            self.get_suit_display(6),
-            self.get_display(7, North),
-            self.get_suit_display(7),
-            self.get_display(8, North),
-            self.get_suit_display(8),
-        )?;
-        writeln!(
-            f,
-            "  │ {} {} │ {} {} │ {} {} │",
-            self.get_display(6, West),
-            self.get_display(6, East),
-            self.get_display(7, West),
-            self.get_display(7, East),
-            self.get_display(8, West),
-            self.get_display(8, East)
-        )?;
-        writeln!(
-            f,
-            "  │  {}  │  {}  │  {}  │",
-            self.get_display(6, South),
-            self.get_display(7, South),
-            self.get_display(8, South)
-        )?;
-        writeln!(f, "  └─────┴─────┴─────┘")?;
+        let GridStyle {
+            color,
+            labels,
+            chars,
+        } = style;
+        let owners = !color;
+        let changes = self.last_move_changes();
+        let line = chars.horizontal.repeat(5);
+        // A horizontal line across the board, using the given row of `joins`
+        let rule = |row: usize| {
+            format!(
+                "  {}{}{}{}{}{}{}",
+                chars.joins[row * 3],
+                line,
+                chars.joins[row * 3 + 1],
+                line,
+                chars.joins[row * 3 + 1],
+                line,
+                chars.joins[row * 3 + 2]
+            )
+        };
+        let bar = chars.vertical;
+        if labels {
+            writeln!(f, "     1     2     3")?;
+        }
+        writeln!(f, "{}", rule(0))?;
+        for (row, row_label) in ROW_LABELS.iter().enumerate() {
+            let cells = [row * 3, row * 3 + 1, row * 3 + 2];
+
+            let row_label = if labels { *row_label } else { ' ' };
+            writeln!(
+                f,
+                "{} {}{}{}",
+                row_label,
+                bar,
+                cells
+                    .map(|pos| match heat[pos] {
+                        Some(value) => Game::heat_cell_line(value, heat, false, color),
+                        None => format!(
+                            "{} {}{} ",
+                            changes[pos].marker(),
+                            self.get_display(pos, North, changes[pos], color),
+                            self.get_suit_display(pos, color)
+                        ),
+                    })
+                    .join(bar),
+                bar
+            )?;
+
+            // The hand sizes go on either side of the middle of the board
+            let middle = cells
+                .map(|pos| match self.current_state().board[pos] {
+                    None if heat[pos].is_some() => {
+                        Game::heat_cell_line(heat[pos].unwrap(), heat, true, color)
+                    }
+                    None if labels => {
+                        format!(" {}  ", paint_if(color, &cell_code(pos), Color::DarkGray))
+                    }
+                    Some((_, owner)) if owners => format!(
+                        "{} {} {}",
+                        self.get_display(pos, West, changes[pos], color),
+                        owner.letter(),
+                        self.get_display(pos, East, changes[pos], color)
+                    ),
+                    _ => format!(
+                        " {} {} ",
+                        self.get_display(pos, West, changes[pos], color),
+                        self.get_display(pos, East, changes[pos], color)
+                    ),
+                })
+                .join(bar);
+            if row == 1 {
+                writeln!(
+                    f,
+                    "{} {}{}{} {}",
+                    self.get_hand_display(Player::Blue, color),
+                    bar,
+                    middle,
+                    bar,
+                    self.get_hand_display(Player::Red, color),
+                )?;
+            } else {
+                writeln!(f, "  {}{}{}", bar, middle, bar)?;
+            }
+
+            writeln!(
+                f,
+                "  {}{}{}",
+                bar,
+                cells
+                    .map(|pos| match heat[pos] {
+                        Some(value) => Game::heat_cell_line(value, heat, false, color),
+                        None =>
+                            format!("  {}  ", self.get_display(pos, South, changes[pos], color)),
+                    })
+                    .join(bar),
+                bar
+            )?;
+
+            if row < 2 {
+                writeln!(f, "{}", rule(1))?;
+            }
+        }
+        writeln!(f, "{}", rule(2))?;
+        if changes.contains(&CellChange::Placed) {
+            writeln!(f, "  (+ just played, * flipped)")?;
+        }
 
         Ok(())
     }
@@ -0,0 +1,204 @@
+//! Setting up a [`Game`] in any position, rather than at the start of a match against an NPC.
+
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use super::{CardDb, CardId, Game, Player, Rules, Suit, HAND_SLOTS};
+
+const KNOWN_SLOTS: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum GameBuildError {
+    #[error(
+        "{player} can be known to hold at most {} cards, but {count} were given",
+        KNOWN_SLOTS
+    )]
+    TooManyKnownCards { player: Player, count: usize },
+
+    #[error(
+        "{player} can have at most {} cards given, but {count} were",
+        HAND_SLOTS
+    )]
+    TooManyCards { player: Player, count: usize },
+
+    #[error("{player} holds {size} cards, but only {count} were given")]
+    HandSizeTooLarge {
+        player: Player,
+        size: usize,
+        count: usize,
+    },
+
+    #[error("{player} is known to hold {known} cards, more than the {size} they hold")]
+    HandSizeTooSmall {
+        player: Player,
+        size: usize,
+        known: usize,
+    },
+
+    #[error("There's no card with ID {0}")]
+    UnknownCard(CardId),
+}
+
+/// A card given for a hand: one the hand is known to hold, or one it only might.
+#[derive(Clone, Copy, Debug)]
+pub struct HandCard {
+    pub id: CardId,
+    pub known: bool,
+}
+
+/// Builds a [`Game`] from the rules, both hands, the cards already on the board, and whose turn it
+/// is. Cards on the board count towards Ascension and Descension as if they'd been played, unless
+/// the counts are given with [`GameBuilder::suit_count`].
+pub struct GameBuilder {
+    human: Player,
+    cards: Arc<CardDb>,
+    rules: Rules,
+    hands: [Vec<HandCard>; 2],
+    hand_sizes: [Option<usize>; 2],
+    board: [Option<(CardId, Player)>; 9],
+    suit_counts: Vec<(Suit, i32)>,
+    to_move: Option<Player>,
+}
+impl GameBuilder {
+    /// Starts an empty game that looks its cards up in `cards`. Like [`Game::new`], the human
+    /// matters because the Order rule only applies to them.
+    pub fn new(human: Player, cards: Arc<CardDb>) -> Self {
+        GameBuilder {
+            human,
+            cards,
+            rules: Rules::default(),
+            hands: [vec![], vec![]],
+            hand_sizes: [None; 2],
+            board: Default::default(),
+            suit_counts: vec![],
+            to_move: None,
+        }
+    }
+
+    pub fn rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Adds a card to `player`'s hand. The hand's slots are numbered with the known cards first,
+    /// then the possible ones, each in the order they were added.
+    pub fn hand_card(mut self, player: Player, card: HandCard) -> Self {
+        self.hands[player].push(card);
+        self
+    }
+
+    /// Adds a card `player`'s hand is known to hold.
+    pub fn known_card(self, player: Player, id: CardId) -> Self {
+        self.hand_card(player, HandCard { id, known: true })
+    }
+
+    /// Adds a card `player`'s hand might hold, like an NPC's variable cards.
+    pub fn possible_card(self, player: Player, id: CardId) -> Self {
+        self.hand_card(player, HandCard { id, known: false })
+    }
+
+    /// How many cards `player` actually holds. Defaults to every card given, up to five.
+    pub fn hand_size(mut self, player: Player, size: usize) -> Self {
+        self.hand_sizes[player] = Some(size);
+        self
+    }
+
+    /// Puts a card on the board without flipping anything.
+    pub fn place(mut self, pos: usize, card: CardId, owner: Player) -> Self {
+        self.board[pos] = Some((card, owner));
+        self
+    }
+
+    /// How many cards of `suit` have been played, for Ascension and Descension, instead of
+    /// counting the cards of the suit on the board.
+    pub fn suit_count(mut self, suit: Suit, count: i32) -> Self {
+        self.suit_counts.push((suit, count));
+        self
+    }
+
+    pub fn to_move(mut self, player: Player) -> Self {
+        self.to_move = Some(player);
+        self
+    }
+
+    pub fn build(self) -> Result<Game, GameBuildError> {
+        let unknown = self
+            .hands
+            .iter()
+            .flatten()
+            .map(|card| card.id)
+            .chain(self.board.iter().flatten().map(|&(id, _)| id));
+        for id in unknown {
+            if !self.cards.contains(id) {
+                return Err(GameBuildError::UnknownCard(id));
+            }
+        }
+
+        let mut game = Game::new(self.human, self.cards);
+        game.rules = self.rules;
+        game.rules_changed();
+
+        let mut state = *game.current_state();
+        for player in [Player::Red, Player::Blue] {
+            let cards = &self.hands[player];
+            let known = cards.iter().filter(|card| card.known).count();
+            if known > KNOWN_SLOTS {
+                return Err(GameBuildError::TooManyKnownCards {
+                    player,
+                    count: known,
+                });
+            }
+            if cards.len() > HAND_SLOTS {
+                return Err(GameBuildError::TooManyCards {
+                    player,
+                    count: cards.len(),
+                });
+            }
+            let size = self.hand_sizes[player].unwrap_or(cards.len().min(KNOWN_SLOTS));
+            if size > cards.len() {
+                return Err(GameBuildError::HandSizeTooLarge {
+                    player,
+                    size,
+                    count: cards.len(),
+                });
+            }
+            if size < known {
+                return Err(GameBuildError::HandSizeTooSmall {
+                    player,
+                    size,
+                    known,
+                });
+            }
+
+            // Known cards first, keeping the order they were given in otherwise
+            let ordered = cards
+                .iter()
+                .filter(|card| card.known)
+                .chain(cards.iter().filter(|card| !card.known));
+            for (idx, card) in ordered.enumerate() {
+                state.hands[player][idx] = Some(card.id);
+                game.known[player][idx] = card.known;
+            }
+            for idx in cards.len()..HAND_SLOTS {
+                game.known[player][idx] = false;
+            }
+            state.actual_hand_sizes[player] = size;
+        }
+
+        for (pos, cell) in self.board.iter().enumerate() {
+            if let Some((id, owner)) = *cell {
+                game.rule_set
+                    .adjust_modifiers(&mut state.modifiers, &game.cards[id]);
+                state.board[pos] = Some((id, owner));
+            }
+        }
+        for &(suit, count) in &self.suit_counts {
+            state.modifiers.counts[suit as usize] = count;
+        }
+        state.to_move = self.to_move;
+        *game.current_state_mut() = state;
+
+        Ok(game)
+    }
+}
@@ -0,0 +1,70 @@
+//! Card values by ID, so games can hold small IDs and look the values up when they need them.
+
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+    num::NonZeroU16,
+    ops::Index,
+};
+
+use super::Card;
+
+/// A card's ID in the game data. The data uses 0 for "no card", so it isn't an ID, which lets an
+/// `Option<CardId>` take no more room than the ID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CardId(NonZeroU16);
+impl CardId {
+    /// The ID for the number the data uses, or `None` if no card could have it.
+    pub fn new(id: i32) -> Option<Self> {
+        u16::try_from(id).ok().and_then(NonZeroU16::new).map(CardId)
+    }
+
+    /// The number the data uses for the card.
+    pub fn get(self) -> i32 {
+        self.0.get() as i32
+    }
+}
+impl Display for CardId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Every card's values, in a table indexed by ID.
+#[derive(Default, Debug)]
+pub struct CardDb {
+    cards: Vec<Option<Card>>,
+}
+impl CardDb {
+    pub fn insert(&mut self, id: CardId, card: Card) {
+        let idx = id.0.get() as usize;
+        if self.cards.len() <= idx {
+            self.cards.resize(idx + 1, None);
+        }
+        self.cards[idx] = Some(card);
+    }
+
+    pub fn get(&self, id: CardId) -> Option<&Card> {
+        self.cards.get(id.0.get() as usize)?.as_ref()
+    }
+
+    pub fn contains(&self, id: CardId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Every card, by ID.
+    pub fn iter(&self) -> impl Iterator<Item = (CardId, &Card)> {
+        self.cards
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, card)| Some((CardId::new(idx as i32)?, card.as_ref()?)))
+    }
+}
+impl Index<CardId> for CardDb {
+    type Output = Card;
+
+    /// Panics if there's no card with the ID, which games can't hold.
+    fn index(&self, id: CardId) -> &Card {
+        self.get(id).unwrap()
+    }
+}
@@ -0,0 +1,91 @@
+//! What each rule does, in words, for showing players alongside the match.
+
+use super::Rules;
+
+/// One rule's explanation: a one-line reminder for while it's in play, and the longer version.
+pub struct RuleExplanation {
+    /// One of [`Rules::NAMES`].
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub details: &'static str,
+}
+
+/// Every rule the solver knows about, in the same order as [`Rules::NAMES`].
+pub const GLOSSARY: [RuleExplanation; 9] = [
+    RuleExplanation {
+        name: "Same",
+        summary: "Matching two or more touching sides flips those cards.",
+        details: "When a card is placed so that two or more of its sides match the values of the \
+                  sides of the cards they touch, every one of those cards the opponent owns is \
+                  flipped, whatever their other values. Cards flipped this way go on to flip \
+                  their neighbours as if they'd just been placed (a combo).",
+    },
+    RuleExplanation {
+        name: "Plus",
+        summary: "Equal sums on two or more touching sides flip those cards.",
+        details: "When a card is placed so that the values it touches add up to the same sum with \
+                  its own sides on two or more sides, every one of those cards the opponent owns \
+                  is flipped. Like Same, the flipped cards then combo into their neighbours.",
+    },
+    RuleExplanation {
+        name: "Order",
+        summary: "Cards must be played in the order they sit in the hand.",
+        details: "Each player has to play the leftmost card left in their hand, so only where it \
+                  goes can be chosen.",
+    },
+    RuleExplanation {
+        name: "Chaos",
+        summary: "The card to play each turn is picked at random.",
+        details: "Each turn, a random card from the hand is picked for the player, who only \
+                  chooses where it goes. The solver weighs every card the opponent might be \
+                  given, and asks which card you were given before recommending where it goes.",
+    },
+    RuleExplanation {
+        name: "Reverse",
+        summary: "Lower values beat higher ones.",
+        details: "Captures are reversed: a side flips the card it touches if its value is lower \
+                  than the touching side instead of higher.",
+    },
+    RuleExplanation {
+        name: "Fallen Ace",
+        summary: "A 1 beats an A (or an A beats a 1, under Reverse).",
+        details: "A side with a value of 1 can flip a card whose touching side is an A, which \
+                  otherwise can't be beaten. Under Reverse it works the other way round: an A \
+                  flips a 1.",
+    },
+    RuleExplanation {
+        name: "Ascension",
+        summary: "Each card of a type on the board raises that type's values by 1.",
+        details: "Every Primal, Scion, Beastman, or Garlean card placed on the board raises \
+                  the values of all cards of its type by 1, both on the board and in hand, \
+                  up to A.",
+    },
+    RuleExplanation {
+        name: "Descension",
+        summary: "Each card of a type on the board lowers that type's values by 1.",
+        details: "Every Primal, Scion, Beastman, or Garlean card placed on the board lowers \
+                  the values of all cards of its type by 1, both on the board and in hand, \
+                  down to 1.",
+    },
+    RuleExplanation {
+        name: "Swap",
+        summary: "One random card from each deck is traded before the match.",
+        details: "Before the match starts, one card picked at random from each player's deck is \
+                  swapped with the other's. Both cards are shown before the first move, so the \
+                  solver doesn't guess at the trade: enter the hand you actually end up with. \
+                  Against an NPC, it still expects the NPC to hold its usual cards.",
+    },
+];
+
+impl Rules {
+    /// The explanations of the rules that are on, in the same order as
+    /// [`Rules::active_rule_names`].
+    pub fn explanations(&self) -> Vec<&'static RuleExplanation> {
+        GLOSSARY
+            .iter()
+            .zip(self.flags())
+            .filter(|(_, active)| *active)
+            .map(|(explanation, _)| explanation)
+            .collect()
+    }
+}
@@ -0,0 +1,198 @@
+//! Drawing the board for other places than the terminal the settings are for, like a web page, a
+//! chat message, or a file, so each of them doesn't have to lay out the grid itself.
+
+use serde::Serialize;
+use std::{
+    fmt::{Display, Formatter},
+    io::{self, Write},
+    str::FromStr,
+};
+use thiserror::Error;
+
+use super::{
+    cell_code, CellChange, Game, GridStyle, Player, Suit, ASCII_BOX_CHARS, UNICODE_BOX_CHARS,
+};
+
+/// The ways [`Game::render`] can draw the board.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// The terminal grid, in ANSI colors and box drawing characters.
+    Ansi,
+    /// The terminal grid in plain ASCII without colors, with each card's owner as a letter.
+    Plain,
+    /// The cells, hand sizes, and scores as JSON, for clients that lay out the board themselves.
+    Json,
+    /// An HTML table, with classes for each card's owner and what the last move did to it, for
+    /// styling with CSS.
+    Html,
+}
+impl RenderStyle {
+    pub const ALL: [RenderStyle; 4] = [
+        RenderStyle::Ansi,
+        RenderStyle::Plain,
+        RenderStyle::Json,
+        RenderStyle::Html,
+    ];
+
+    /// The MIME type of what the style renders, for sending it over HTTP.
+    pub fn content_type(&self) -> &'static str {
+        match *self {
+            RenderStyle::Ansi | RenderStyle::Plain => "text/plain; charset=utf-8",
+            RenderStyle::Json => "application/json",
+            RenderStyle::Html => "text/html; charset=utf-8",
+        }
+    }
+}
+impl Display for RenderStyle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                RenderStyle::Ansi => "ansi",
+                RenderStyle::Plain => "plain",
+                RenderStyle::Json => "json",
+                RenderStyle::Html => "html",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown render style {0:?} (expected ansi, plain, json, or html)")]
+pub struct UnknownRenderStyle(String);
+
+impl FromStr for RenderStyle {
+    type Err = UnknownRenderStyle;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        RenderStyle::ALL
+            .iter()
+            .copied()
+            .find(|style| style.to_string().eq_ignore_ascii_case(text))
+            .ok_or_else(|| UnknownRenderStyle(text.to_string()))
+    }
+}
+
+/// A card on the board, as [`RenderStyle::Json`] writes it.
+#[derive(Serialize)]
+struct RenderedCard {
+    /// North, east, south, west, with modifiers applied, and `A` for 10.
+    values: [String; 4],
+    suit: Option<Suit>,
+    owner: Player,
+    change: CellChange,
+}
+
+/// The board as [`RenderStyle::Json`] writes it.
+#[derive(Serialize)]
+struct RenderedBoard {
+    /// Row by row from the top left, with `null` for empty cells.
+    cells: Vec<Option<RenderedCard>>,
+    /// Red, then blue.
+    hand_sizes: [usize; 2],
+    /// Red, then blue.
+    scores: [usize; 2],
+}
+
+impl Game {
+    /// Draws the board into `w` in `style`. Unlike the `Display` impl, this ignores the display
+    /// settings, so every caller gets the same output for the same style.
+    pub fn render(&self, style: RenderStyle, w: &mut impl Write) -> io::Result<()> {
+        let grid = |color, chars| {
+            let mut text = String::new();
+            let style = GridStyle {
+                color,
+                labels: true,
+                chars,
+            };
+            self.draw_board(&mut text, &[None; 9], style)
+                .map(|_| text)
+                .map_err(|_| io::Error::other("couldn't draw the board"))
+        };
+        let text = match style {
+            RenderStyle::Ansi => grid(true, &UNICODE_BOX_CHARS)?,
+            RenderStyle::Plain => grid(false, &ASCII_BOX_CHARS)?,
+            RenderStyle::Json => serde_json::to_string(&self.rendered_board())?,
+            RenderStyle::Html => self.html_board(),
+        };
+        w.write_all(text.as_bytes())
+    }
+
+    fn rendered_board(&self) -> RenderedBoard {
+        let changes = self.last_move_changes();
+        let cells = (0..9)
+            .map(|pos| {
+                self.board_cell(pos)
+                    .map(|(values, suit, owner)| RenderedCard {
+                        values,
+                        suit: (suit != Suit::None).then_some(suit),
+                        owner,
+                        change: changes[pos],
+                    })
+            })
+            .collect();
+        RenderedBoard {
+            cells,
+            hand_sizes: [self.hand_size(Player::Red), self.hand_size(Player::Blue)],
+            scores: self.scores(),
+        }
+    }
+
+    // <table class="tt-board">
+    //   <caption>Blue holds 4, red holds 4</caption>
+    //   <tr>
+    //     <td class="tt-card tt-blue tt-placed"><span class="tt-north">4</span> ...</td>
+    //     <td class="tt-empty">A2</td>
+    //     ...
+    // Each card's values are spans in the order north, west, suit, east, south, with classes
+    // `tt-north` and so on, and cards the last move put down or flipped get `tt-placed` or
+    // `tt-flipped`.
+    fn html_board(&self) -> String {
+        let changes = self.last_move_changes();
+        let mut html = String::from("<table class=\"tt-board\">\n");
+        html.push_str(&format!(
+            "  <caption>Blue holds {}, red holds {}</caption>\n",
+            self.hand_size(Player::Blue),
+            self.hand_size(Player::Red)
+        ));
+        for (pos, change) in changes.iter().enumerate() {
+            if pos % 3 == 0 {
+                html.push_str("  <tr>\n");
+            }
+            match self.board_cell(pos) {
+                Some(([north, east, south, west], suit, owner)) => {
+                    let change = match change {
+                        CellChange::Unchanged => "",
+                        CellChange::Placed => " tt-placed",
+                        CellChange::Flipped => " tt-flipped",
+                    };
+                    html.push_str(&format!(
+                        "    <td class=\"tt-card tt-{}{}\">\
+                             <span class=\"tt-north\">{}</span> \
+                             <span class=\"tt-west\">{}</span> \
+                             <span class=\"tt-suit\">{}</span> \
+                             <span class=\"tt-east\">{}</span> \
+                             <span class=\"tt-south\">{}</span></td>\n",
+                        owner.to_string().to_lowercase(),
+                        change,
+                        north,
+                        west,
+                        suit.to_string().trim(),
+                        east,
+                        south
+                    ));
+                }
+                None => html.push_str(&format!(
+                    "    <td class=\"tt-empty\">{}</td>\n",
+                    cell_code(pos)
+                )),
+            }
+            if pos % 3 == 2 {
+                html.push_str("  </tr>\n");
+            }
+        }
+        html.push_str("</table>\n");
+        html
+    }
+}
@@ -0,0 +1,246 @@
+//! Working out what a move does. Each rule that takes cards or changes values is a [`Resolver`],
+//! and a [`RuleSet`] runs the ones a match's rules turn on in a fixed order. Reverse and Fallen Ace
+//! don't take cards themselves, they change how two sides are compared, so they're a
+//! [`Comparison`] shared by every resolver that compares sides.
+
+use super::{Captures, Card, CardDb, Game, GameState, Modifiers, Player, Rules, MAX_VALUE};
+
+/// How one side decides whether it beats the side it touches.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Comparison {
+    /// Lower values beat higher ones.
+    pub reverse: bool,
+    /// A 1 beats an A, or an A beats a 1 when Reverse is in play.
+    pub fallen_ace: bool,
+}
+impl Comparison {
+    /// Whether the `attacker` side of a card takes the card with the touching `defender` side.
+    pub fn beats(&self, attacker: i32, defender: i32) -> bool {
+        if self.reverse {
+            attacker < defender || (self.fallen_ace && attacker == MAX_VALUE && defender == 1)
+        } else {
+            attacker > defender || (self.fallen_ace && attacker == 1 && defender == MAX_VALUE)
+        }
+    }
+}
+
+/// One step of working out a move. [`RuleSet`] runs them in the order they're listed here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolver {
+    /// Takes the cards whose touching sides equal the played card's, if two or more cards touch
+    /// it that way. The cards taken then combo.
+    Same,
+    /// Takes the cards whose touching sides add up with the played card's to a sum shared by two
+    /// or more cards touching it. The cards taken then combo.
+    Plus,
+    /// Takes the cards whose touching sides the played card's sides beat.
+    Basic,
+    /// Counts the played card towards its suit, raising the values of every card of the suit by
+    /// one more for the rest of the match. This comes after the cards are taken: like in the game,
+    /// the played card is raised by the cards of its suit already on the board, not by itself.
+    Ascension,
+    /// Like [`Resolver::Ascension`], but lowering the values.
+    Descension,
+}
+impl Resolver {
+    /// Applies the resolver's change to the suit counts for `card` going on the board, which
+    /// happens even for cards put there without being played.
+    fn adjust_modifiers(&self, modifiers: &mut Modifiers, card: &Card) {
+        if let Resolver::Ascension | Resolver::Descension = *self {
+            modifiers.add_card(card.suit);
+        }
+    }
+}
+
+/// The resolvers a match's rules turn on, and the comparison they use.
+#[derive(Clone, Debug)]
+pub struct RuleSet {
+    comparison: Comparison,
+    resolvers: Vec<Resolver>,
+}
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::new(&Rules::default())
+    }
+}
+impl RuleSet {
+    pub fn new(rules: &Rules) -> Self {
+        let resolvers = [
+            (Resolver::Same, rules.same),
+            (Resolver::Plus, rules.plus),
+            (Resolver::Basic, true),
+            (Resolver::Ascension, rules.ascension),
+            (Resolver::Descension, rules.decension),
+        ]
+        .iter()
+        .filter(|(_, active)| *active)
+        .map(|(resolver, _)| *resolver)
+        .collect();
+
+        RuleSet {
+            comparison: Comparison {
+                reverse: rules.reverse,
+                fallen_ace: rules.fallen_ace,
+            },
+            resolvers,
+        }
+    }
+
+    pub fn comparison(&self) -> Comparison {
+        self.comparison
+    }
+
+    /// How much each card of a suit on the board changes the values of the suit's cards.
+    pub fn suit_step(&self) -> i32 {
+        self.resolvers
+            .iter()
+            .map(|resolver| match resolver {
+                Resolver::Ascension => 1,
+                Resolver::Descension => -1,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// The resolvers that run, in order.
+    pub fn resolvers(&self) -> &[Resolver] {
+        &self.resolvers
+    }
+
+    /// Works out the move that put `player`'s card in `pos`, which must already be on the board,
+    /// recording what it took in `state.captures`. Returns the cells of the cards taken, a bit per
+    /// cell.
+    pub(super) fn resolve(
+        &self,
+        state: &mut GameState,
+        pos: usize,
+        player: Player,
+        cards: &CardDb,
+    ) -> u16 {
+        state.captures = Captures::default();
+        let mut all_taken = 0;
+        for resolver in &self.resolvers {
+            match *resolver {
+                Resolver::Same => {
+                    let taken = take(state, same_cells(state, pos, cards), player);
+                    state.captures.same += taken.count_ones() as u8;
+                    all_taken |= taken | self.combo(state, taken, player, cards);
+                }
+                Resolver::Plus => {
+                    let taken = take(state, plus_cells(state, pos, cards), player);
+                    state.captures.plus += taken.count_ones() as u8;
+                    all_taken |= taken | self.combo(state, taken, player, cards);
+                }
+                Resolver::Basic => {
+                    let taken = take(state, self.beaten_cells(state, pos, cards), player);
+                    state.captures.basic += taken.count_ones() as u8;
+                    all_taken |= taken;
+                }
+                Resolver::Ascension | Resolver::Descension => {
+                    let (id, _) = state.board[pos].unwrap();
+                    resolver.adjust_modifiers(&mut state.modifiers, &cards[id]);
+                }
+            }
+        }
+        all_taken
+    }
+
+    /// Applies the rules' changes to the suits' values for `card` being put on the board without
+    /// being played, like when setting up a position.
+    pub(super) fn adjust_modifiers(&self, modifiers: &mut Modifiers, card: &Card) {
+        for resolver in &self.resolvers {
+            resolver.adjust_modifiers(modifiers, card);
+        }
+    }
+
+    /// Lets the cards in `queue` (a bit per cell), just taken by Same or Plus, take the cards they
+    /// beat, and those the cards they beat, until nothing else changes hands. Returns the cells of
+    /// the cards taken.
+    fn combo(&self, state: &mut GameState, mut queue: u16, player: Player, cards: &CardDb) -> u16 {
+        let mut all_taken = 0;
+        while queue != 0 {
+            let pos = queue.trailing_zeros() as usize;
+            queue &= queue - 1;
+            let taken = take(state, self.beaten_cells(state, pos, cards), player);
+            state.captures.combo += taken.count_ones() as u8;
+            all_taken |= taken;
+            queue |= taken;
+        }
+        all_taken
+    }
+
+    /// The cells of the cards the card in `pos` beats, as a bit per cell.
+    fn beaten_cells(&self, state: &GameState, pos: usize, cards: &CardDb) -> u16 {
+        touching(state, pos, cards)
+            .filter(|&(_, side, other_side)| self.comparison.beats(side, other_side))
+            .fold(0, |cells, (other, _, _)| cells | 1 << other)
+    }
+}
+
+/// The cards touching the one in `pos`, as their cell, the value of the side of the card in `pos`
+/// that touches them, and the value of their side that touches it.
+fn touching<'a>(
+    state: &'a GameState,
+    pos: usize,
+    cards: &'a CardDb,
+) -> impl Iterator<Item = (usize, i32, i32)> + 'a {
+    let card = &cards[state.board[pos].unwrap().0];
+    (0..9).filter_map(move |other| {
+        let direction = Game::adjacency(pos, other)?;
+        let (other_id, _) = state.board[other]?;
+        Some((
+            other,
+            card.get_modified_value(&state.modifiers, direction),
+            cards[other_id].get_modified_value(&state.modifiers, direction.opposite()),
+        ))
+    })
+}
+
+/// The cells Same matches for the card in `pos`, including the mover's own cards, which count
+/// towards the two but aren't taken.
+fn same_cells(state: &GameState, pos: usize, cards: &CardDb) -> u16 {
+    let cells = touching(state, pos, cards)
+        .filter(|&(_, side, other_side)| side == other_side)
+        .fold(0u16, |cells, (other, _, _)| cells | 1 << other);
+    if cells.count_ones() >= 2 {
+        cells
+    } else {
+        0
+    }
+}
+
+/// The cells Plus matches for the card in `pos`, including the mover's own cards, like
+/// [`same_cells`].
+fn plus_cells(state: &GameState, pos: usize, cards: &CardDb) -> u16 {
+    // At most four cards touch it, so this doesn't need an allocation
+    let mut sums = [(0, 0); 4];
+    let mut count = 0;
+    for (other, side, other_side) in touching(state, pos, cards) {
+        sums[count] = (other, side + other_side);
+        count += 1;
+    }
+    let sums = &sums[..count];
+    sums.iter()
+        .filter(|(_, sum)| {
+            sums.iter()
+                .filter(|(_, other_sum)| other_sum == sum)
+                .count()
+                >= 2
+        })
+        .fold(0, |cells, (other, _)| cells | 1 << other)
+}
+
+/// Gives `player` the cards in `cells` (a bit per cell) that aren't theirs already, returning the
+/// cells that changed hands.
+fn take(state: &mut GameState, cells: u16, player: Player) -> u16 {
+    let mut taken = 0;
+    for pos in (0..9).filter(|pos| cells & 1 << pos != 0) {
+        if let Some((_, owner)) = &mut state.board[pos] {
+            if *owner != player {
+                *owner = player;
+                taken |= 1 << pos;
+            }
+        }
+    }
+    taken
+}
@@ -0,0 +1,139 @@
+//! The ways the board can be turned or flipped onto itself, for skipping moves that play out the
+//! same as others. Cards turn with the board, so a position only has a symmetry if every card in
+//! play reads the same after it, which mostly happens early on with evenly sided cards.
+
+use super::{Card, CardDb, Direction, GameState};
+
+/// The sides of a card, in the order [`Card`] stores their values.
+const SIDES: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+/// A rotation or reflection of the board: where each cell goes, and which way each side of a card
+/// faces afterwards.
+struct Symmetry {
+    cells: [usize; 9],
+    /// Indexed by the side's direction before.
+    sides: [Direction; 4],
+}
+impl Symmetry {
+    /// The symmetry moving the cell in `(row, column)` to `cell(row, column)`, with `side` giving
+    /// where each side of a card faces once it's moved.
+    fn new(cell: fn(usize, usize) -> (usize, usize), side: fn(Direction) -> Direction) -> Self {
+        Symmetry {
+            cells: std::array::from_fn(|pos| {
+                let (row, column) = cell(pos / 3, pos % 3);
+                row * 3 + column
+            }),
+            sides: SIDES.map(side),
+        }
+    }
+
+    /// Whether `card`, turned this way, reads the same as `onto`.
+    fn maps_card(&self, card: &Card, onto: &Card) -> bool {
+        card.suit == onto.suit
+            && SIDES
+                .iter()
+                .all(|&side| card.value(side) == onto.value(self.sides[side as usize]))
+    }
+
+    /// Whether `state` looks the same turned this way, hands included.
+    fn preserves(&self, state: &GameState, cards: &CardDb) -> bool {
+        let board = (0..9).all(
+            |pos| match (state.board[pos], state.board[self.cells[pos]]) {
+                (None, None) => true,
+                (Some((id, owner)), Some((other_id, other_owner))) => {
+                    owner == other_owner && self.maps_card(&cards[id], &cards[other_id])
+                }
+                _ => false,
+            },
+        );
+        board
+            && state
+                .hands
+                .iter()
+                .flatten()
+                .flatten()
+                .all(|&id| self.maps_card(&cards[id], &cards[id]))
+    }
+}
+
+/// Every symmetry of the board besides leaving it as it is.
+fn symmetries() -> [Symmetry; 7] {
+    use Direction::*;
+    [
+        // Mirrored left to right
+        Symmetry::new(
+            |row, column| (row, 2 - column),
+            |side| match side {
+                East => West,
+                West => East,
+                side => side,
+            },
+        ),
+        // Mirrored top to bottom
+        Symmetry::new(
+            |row, column| (2 - row, column),
+            |side| match side {
+                North => South,
+                South => North,
+                side => side,
+            },
+        ),
+        // Turned halfway
+        Symmetry::new(|row, column| (2 - row, 2 - column), |side| side.opposite()),
+        // Mirrored along the diagonal from the top left
+        Symmetry::new(
+            |row, column| (column, row),
+            |side| match side {
+                North => West,
+                West => North,
+                South => East,
+                East => South,
+            },
+        ),
+        // Mirrored along the diagonal from the top right
+        Symmetry::new(
+            |row, column| (2 - column, 2 - row),
+            |side| match side {
+                North => East,
+                East => North,
+                South => West,
+                West => South,
+            },
+        ),
+        // Turned a quarter clockwise
+        Symmetry::new(
+            |row, column| (column, 2 - row),
+            |side| match side {
+                North => East,
+                East => South,
+                South => West,
+                West => North,
+            },
+        ),
+        // Turned a quarter counterclockwise
+        Symmetry::new(
+            |row, column| (2 - column, row),
+            |side| match side {
+                North => West,
+                West => South,
+                South => East,
+                East => North,
+            },
+        ),
+    ]
+}
+
+/// The lowest cell that playing in `pos` is the same as, by the symmetries `state` has. Playing any
+/// card from hand there plays out just like playing it in `pos`.
+pub(super) fn canonical_cell(state: &GameState, cards: &CardDb, pos: usize) -> usize {
+    symmetries()
+        .iter()
+        .filter(|symmetry| symmetry.preserves(state, cards))
+        .map(|symmetry| symmetry.cells[pos])
+        .fold(pos, usize::min)
+}
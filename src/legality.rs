@@ -0,0 +1,153 @@
+//! Which decks can be played. The game itself limits how many rare cards a deck holds, and some
+//! tournaments restrict decks further, so each set of limits is a [`DeckLegality`] that says which
+//! limit a deck breaks.
+
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+
+use crate::data::Data;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LegalityError {
+    #[error("Card #{0} isn't in the card data")]
+    UnknownCard(i32),
+
+    #[error("{0} is in the deck more than once, but there's only one of each card")]
+    Duplicate(String),
+
+    #[error("The deck has {count} 5-star cards ({cards}), but at most {max} are allowed under {legality}")]
+    TooManyFiveStars {
+        count: usize,
+        cards: String,
+        max: usize,
+        legality: String,
+    },
+
+    #[error("The deck has {count} cards of 4 stars or above ({cards}), but at most {max} are allowed under {legality}")]
+    TooManyFourStars {
+        count: usize,
+        cards: String,
+        max: usize,
+        legality: String,
+    },
+
+    #[error("{card} has {stars} stars, but only cards of up to {max} stars are allowed under {legality}")]
+    TooRare {
+        card: String,
+        stars: u8,
+        max: u8,
+        legality: String,
+    },
+}
+
+/// Limits on what a deck can hold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeckLegality {
+    /// Shown in errors, like "the standard limits".
+    pub name: String,
+    pub max_five_stars: usize,
+    /// Counting the 5-star cards too.
+    pub max_four_stars_or_above: usize,
+    /// The most stars a card can have to be allowed at all.
+    pub max_stars: u8,
+}
+impl DeckLegality {
+    /// The limits the game puts on every deck: one 5-star card, and two cards of 4 stars or above.
+    pub fn standard() -> DeckLegality {
+        DeckLegality {
+            name: String::from("the standard limits"),
+            max_five_stars: 1,
+            max_four_stars_or_above: 2,
+            max_stars: 5,
+        }
+    }
+
+    /// The standard limits, with no card rarer than `max_stars`, for tournaments that restrict
+    /// rarity.
+    pub fn limited(max_stars: u8) -> DeckLegality {
+        DeckLegality {
+            name: format!("the {}-star limit", max_stars),
+            max_stars,
+            ..DeckLegality::standard()
+        }
+    }
+
+    /// The limits to pick from when preparing for a tournament.
+    pub fn all() -> Vec<DeckLegality> {
+        vec![
+            DeckLegality::standard(),
+            DeckLegality::limited(4),
+            DeckLegality::limited(3),
+        ]
+    }
+
+    /// Checks `deck` against every limit, returning the first one it breaks.
+    pub fn check(&self, data: &Data, deck: &[i32; 5]) -> Result<(), LegalityError> {
+        let mut stars = Vec::with_capacity(deck.len());
+        for (i, &id) in deck.iter().enumerate() {
+            let info = data
+                .card_info
+                .get(&id)
+                .ok_or(LegalityError::UnknownCard(id))?;
+            if deck[..i].contains(&id) {
+                return Err(LegalityError::Duplicate(
+                    data.card_display_name(id).to_string(),
+                ));
+            }
+            if info.stars > self.max_stars {
+                return Err(LegalityError::TooRare {
+                    card: data.card_display_name(id).to_string(),
+                    stars: info.stars,
+                    max: self.max_stars,
+                    legality: self.name.clone(),
+                });
+            }
+            stars.push((id, info.stars));
+        }
+
+        let names_of = |min_stars: u8| {
+            let names: Vec<&str> = stars
+                .iter()
+                .filter(|&&(_, stars)| stars >= min_stars)
+                .map(|&(id, _)| data.card_display_name(id))
+                .collect();
+            (names.len(), names.join(", "))
+        };
+        let (count, cards) = names_of(5);
+        if count > self.max_five_stars {
+            return Err(LegalityError::TooManyFiveStars {
+                count,
+                cards,
+                max: self.max_five_stars,
+                legality: self.name.clone(),
+            });
+        }
+        let (count, cards) = names_of(4);
+        if count > self.max_four_stars_or_above {
+            return Err(LegalityError::TooManyFourStars {
+                count,
+                cards,
+                max: self.max_four_stars_or_above,
+                legality: self.name.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn is_legal(&self, data: &Data, deck: &[i32; 5]) -> bool {
+        self.check(data, deck).is_ok()
+    }
+}
+impl Default for DeckLegality {
+    fn default() -> Self {
+        DeckLegality::standard()
+    }
+}
+impl Display for DeckLegality {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max_stars {
+            5 => write!(f, "Standard limits"),
+            max => write!(f, "Standard limits, cards of up to {} stars", max),
+        }
+    }
+}
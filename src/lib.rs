@@ -0,0 +1,36 @@
+//! The game rules, solver, and card/NPC data shared by the terminal and graphical frontends
+//! and the WebAssembly build.
+
+pub mod advice;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod bundled;
+pub mod data;
+pub mod decks;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod dirs;
+#[cfg(not(target_arch = "wasm32"))]
+mod exd;
+pub mod export;
+pub mod game;
+pub mod legality;
+pub mod notes;
+pub mod optimize;
+pub mod perft;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod profiles;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod prompt;
+pub mod rating;
+pub mod scenario;
+pub mod search;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod settings;
+pub mod solve;
+pub mod stats;
+mod store;
+#[cfg(feature = "vision")]
+pub mod vision;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::store;
+
+#[derive(Debug, Error)]
+pub enum NpcNotesError {
+    #[error("Could not read/write NPC notes file")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Could not parse NPC notes file")]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// The player's own notes on how NPCs play (like "opens in a corner with Ixion"), shown whenever
+/// they pick that NPC. Shared by every profile.
+#[derive(Serialize, Deserialize)]
+pub struct NpcNotes {
+    /// By English NPC name.
+    notes: BTreeMap<String, String>,
+
+    #[serde(skip)]
+    notes_path: PathBuf,
+}
+impl NpcNotes {
+    pub fn new(config_dir: &Path) -> Result<Self, NpcNotesError> {
+        let notes_path = config_dir.join("npc_notes.json");
+
+        let saved = store::load(&notes_path, |text| {
+            serde_json::from_str::<NpcNotes>(text).map_err(NpcNotesError::from)
+        })?;
+        if let Some(mut result) = saved {
+            result.notes_path = notes_path;
+            Ok(result)
+        } else {
+            std::fs::create_dir_all(config_dir)?;
+            let result = NpcNotes {
+                notes: BTreeMap::new(),
+                notes_path,
+            };
+            result.save()?;
+            Ok(result)
+        }
+    }
+
+    pub fn get(&self, npc_name: &str) -> Option<&str> {
+        self.notes.get(npc_name).map(String::as_str)
+    }
+
+    /// Replaces the notes on `npc_name`, or removes them if `notes` is blank.
+    pub fn set(&mut self, npc_name: &str, notes: &str) -> Result<(), NpcNotesError> {
+        let notes = notes.trim();
+        if notes.is_empty() {
+            self.notes.remove(npc_name);
+        } else {
+            self.notes.insert(npc_name.to_string(), notes.to_string());
+        }
+        self.save()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Adds the notes from `imported` on NPCs that don't have any here, returning how many there
+    /// were.
+    pub fn merge(&mut self, imported: NpcNotes) -> Result<usize, NpcNotesError> {
+        let count = self.notes.len();
+        for (npc_name, notes) in imported.notes {
+            self.notes.entry(npc_name).or_insert(notes);
+        }
+        self.save()?;
+        Ok(self.notes.len() - count)
+    }
+
+    fn save(&self) -> Result<(), NpcNotesError> {
+        store::save(
+            &self.notes_path,
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+//! The walkthrough on first launch: where to get the card data from and which language to show it
+//! in, before the data is loaded, then bringing in data from another computer, registering a
+//! first deck, and a screen on how a match against an NPC goes.
+
+use inquire::Text;
+use std::fmt::{Display, Formatter};
+
+use triple_triad_solver::{
+    data::{Data, DataSource, Language},
+    decks::SavedDecks,
+    notes::NpcNotes,
+    prompt::{Confirm, Select},
+    settings::Settings,
+    stats::MatchStats,
+};
+
+#[derive(Clone, Copy)]
+enum SourceChoice {
+    Defaults,
+    Repository,
+    LocalDirectory,
+}
+impl Display for SourceChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                SourceChoice::Defaults => "Download it from the usual place (recommended)",
+                SourceChoice::Repository => "Download it from another GitHub repository or URL",
+                SourceChoice::LocalDirectory =>
+                    "Read it from a directory on this computer (a SaintCoinach export or the game's sheet files)",
+            }
+        )
+    }
+}
+
+/// Asks where to get the card data from and which language to show it in, if this is the first
+/// launch and the user wants the walkthrough. Returns whether the walkthrough is going on, so the
+/// rest of it can follow once the data is loaded with [`finish`].
+pub fn start(settings: &mut Settings) -> bool {
+    if settings.onboarded {
+        return false;
+    }
+
+    println!("Welcome to the Triple Triad solver!");
+    println!("It recommends the best move in matches against NPCs, given the cards on the board and in hand.");
+    if !Confirm::new("Set things up now?")
+        .with_default(true)
+        .with_help_message("Everything here can be changed later in the settings menu")
+        .prompt()
+        .unwrap()
+    {
+        settings.onboarded = true;
+        settings.save().unwrap();
+        return false;
+    }
+
+    println!();
+    if cfg!(feature = "bundled-data") {
+        println!("This build comes with a copy of the card and NPC data. Newer data is fetched once and kept.");
+    } else {
+        println!("The card and NPC data belongs to Square Enix, so it isn't included. It's fetched once and kept.");
+    }
+    let choices = vec![
+        SourceChoice::Defaults,
+        SourceChoice::Repository,
+        SourceChoice::LocalDirectory,
+    ];
+    settings.data_source_url = match Select::new("Where should the card data come from?", choices)
+        .prompt()
+        .unwrap()
+    {
+        SourceChoice::Defaults => String::new(),
+        choice => loop {
+            let prompt = match choice {
+                SourceChoice::LocalDirectory => "Which directory?",
+                _ => "Which repository (owner/name) or URL?",
+            };
+            let input = Text::new(prompt).prompt().unwrap();
+            match (choice, DataSource::parse(&input)) {
+                (SourceChoice::LocalDirectory, Some(DataSource::LocalDirectory(_))) => break input,
+                (SourceChoice::LocalDirectory, _) => println!("That directory doesn't exist."),
+                (_, Some(DataSource::GithubRepo { .. })) | (_, Some(DataSource::Url(_))) => {
+                    break input
+                }
+                _ => println!("That isn't a repository name or URL."),
+            }
+        },
+    };
+
+    settings.language = Select::new(
+        "Which language should card and NPC names be in?",
+        Language::ALL.to_vec(),
+    )
+    .with_help_message("Names can be searched in English whichever you pick")
+    .prompt()
+    .unwrap();
+    if settings.language != Language::English && settings.data_source_url.is_empty() {
+        println!("The usual place only has English names, so they'll be in English until you pick a source with localized sheets in the settings.");
+    }
+    settings.save().unwrap();
+    println!();
+    true
+}
+
+/// The rest of the walkthrough, once the data is loaded: importing data exported from another
+/// computer, registering a first deck, and how a match goes. Marks the walkthrough as done.
+pub fn finish(
+    data: &Data,
+    settings: &mut Settings,
+    saved_decks: &mut SavedDecks,
+    match_stats: &mut MatchStats,
+    npc_notes: &mut NpcNotes,
+) {
+    if Confirm::new("Do you have a file from \"Export my data\" on another computer to import?")
+        .with_default(false)
+        .prompt()
+        .unwrap()
+    {
+        crate::import_data(data, settings, saved_decks, match_stats, npc_notes);
+    }
+
+    println!();
+    println!("To play an NPC, you need a deck registered with the cards you play it with.");
+    println!("A few things to keep in mind when building one:");
+    println!("  - A deck holds at most one 5-star card, and at most two of 4 stars or above.");
+    println!("  - Cards with high values on two sides that meet (like north and west) hold a corner well.");
+    println!(
+        "  - Cards of one suit get stronger together under Ascension, and weaker under Descension."
+    );
+    println!(
+        "The solver warns you if a deck can't be played or a deck you already have is stronger."
+    );
+    if Confirm::new("Register a deck now?")
+        .with_default(true)
+        .prompt()
+        .unwrap()
+    {
+        crate::register_deck(data, saved_decks);
+    }
+
+    println!();
+    println!("How a match against an NPC goes:");
+    println!("  1. Pick \"Play against an NPC\", then the NPC and the deck you're bringing.");
+    println!("  2. Check the rules in play, turning on any regional rules or roulette picks the NPC doesn't list.");
+    println!("  3. Read the matchup summary, then say who goes first, as the game shows it.");
+    println!("  4. On your turns, the solver recommends a move, like \"Play your Dodo card in the Center (B2)\"; play it in the game.");
+    println!("  5. On the NPC's turns, enter the card they played and the cell, like `3 B1`, or leave it empty to pick from menus.");
+    println!("  6. Afterward, the result is recorded, and you can look back over the match.");
+    println!("Cells are A1 (top left) to C3 (bottom right). The hint under each prompt lists what else you can type there.");
+    Text::new("Press enter to go to the main menu")
+        .prompt()
+        .unwrap();
+
+    settings.onboarded = true;
+    settings.save().unwrap();
+}
@@ -0,0 +1,200 @@
+//! Finding a strong deck for a set of rules by trying decks against a field of opponents. Starting
+//! from a deck, each card in turn is swapped for whichever candidate card does best, over a few
+//! rounds, until no swap helps.
+
+use std::{convert::TryInto, sync::Arc};
+
+use crate::{
+    data::Data,
+    game::{CardId, Direction, Game, Player, Rules},
+    legality::DeckLegality,
+    search::{self, FirstMove},
+};
+
+/// Random matches simulated against each opponent for every deck tried. This is low, since a
+/// handful of rounds try a few hundred decks against the whole field.
+const SIMULATIONS_PER_OPPONENT: usize = 200;
+/// How many cards are tried in each slot of the deck, picked by how well their values suit the
+/// rules.
+const CANDIDATES: usize = 30;
+const MAX_ROUNDS: usize = 3;
+/// How much better a swap has to score to be taken, so the noise in the simulations doesn't make
+/// the deck wander.
+const MIN_IMPROVEMENT: f64 = 0.01;
+
+/// The opponents a deck is tried against, playing under a fixed set of rules.
+pub struct Field {
+    /// English NPC names, whose card pools stand in for the opponents.
+    pub npc_names: Vec<String>,
+    pub rules: Rules,
+}
+impl Field {
+    /// The `size` NPCs with the rarest cards, as a stand-in for the players in a tournament.
+    pub fn strongest_npcs(data: &Data, rules: Rules, size: usize) -> Field {
+        let average_stars = |npc_name: &str| {
+            let npc = &data.npcs_by_name[npc_name];
+            let stars: Vec<u8> = npc
+                .fixed
+                .iter()
+                .chain(&npc.variable)
+                .filter_map(|id| data.card_info.get(&id.get()))
+                .map(|info| info.stars)
+                .collect();
+            stars.iter().map(|&stars| stars as f64).sum::<f64>() / stars.len().max(1) as f64
+        };
+
+        let mut npc_names: Vec<&String> = data.npcs_by_name.keys().collect();
+        npc_names.sort_by(|a, b| {
+            average_stars(b)
+                .partial_cmp(&average_stars(a))
+                .unwrap()
+                .then_with(|| a.cmp(b))
+        });
+        Field {
+            npc_names: npc_names.into_iter().take(size).cloned().collect(),
+            rules,
+        }
+    }
+
+    /// How well `deck` does against the field, from 0 to 1: the share of matches won, with ties
+    /// counting half.
+    pub fn score(&self, data: &Data, deck: &[i32; 5]) -> f64 {
+        let hand = deck.map(|id| CardId::new(id).unwrap());
+        let total: f64 = self
+            .npc_names
+            .iter()
+            .map(|npc_name| {
+                let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+                game.set_cards_in_hand(Player::Blue, &hand, 5);
+                game.set_cards_for_npc(Player::Red, data, npc_name);
+                game.set_rules(self.rules);
+                let rates = search::estimate_outcomes(
+                    &game,
+                    FirstMove::Random,
+                    Player::Blue,
+                    SIMULATIONS_PER_OPPONENT,
+                    None,
+                );
+                rates.win + rates.tie / 2.0
+            })
+            .sum();
+        total / self.npc_names.len().max(1) as f64
+    }
+}
+
+/// What [`optimize_deck`] found.
+pub struct Optimized {
+    pub deck: [i32; 5],
+    pub score: f64,
+    /// The score of the deck it started from, for comparison.
+    pub start_score: f64,
+}
+
+/// How good a card's values are on their own under `rules`: high values, or low ones with Reverse.
+fn card_strength(data: &Data, id: i32, rules: &Rules) -> i32 {
+    let card = match data.get_card(id) {
+        Some(card) => card,
+        None => return i32::MIN,
+    };
+    let total: i32 = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ]
+    .iter()
+    .map(|&direction| card.value(direction))
+    .sum();
+    if rules.reverse {
+        -total
+    } else {
+        total
+    }
+}
+
+/// The deck with the strongest values on their own under `rules` that the standard limits allow,
+/// taking rare cards only while there's room for them. Not the best deck against anyone in
+/// particular, but the same yardstick for every opponent. `None` if there aren't five cards.
+pub fn reference_deck(data: &Data, rules: &Rules) -> Option<[i32; 5]> {
+    let legality = DeckLegality::standard();
+    let mut candidates: Vec<i32> = data
+        .card_info
+        .keys()
+        .copied()
+        .filter(|&id| data.get_card(id).is_some())
+        .collect();
+    candidates.sort_by_key(|&id| (-card_strength(data, id, rules), id));
+
+    let mut deck = Vec::with_capacity(5);
+    let (mut five_stars, mut four_stars_or_above) = (0, 0);
+    for id in candidates {
+        let stars = data.card_info[&id].stars;
+        if (stars >= 5 && five_stars == legality.max_five_stars)
+            || (stars >= 4 && four_stars_or_above == legality.max_four_stars_or_above)
+        {
+            continue;
+        }
+        if stars >= 5 {
+            five_stars += 1;
+        }
+        if stars >= 4 {
+            four_stars_or_above += 1;
+        }
+        deck.push(id);
+        if deck.len() == 5 {
+            break;
+        }
+    }
+    deck.try_into().ok()
+}
+
+/// Improves `start` against `field`, only ever trying decks that `legality` allows.
+pub fn optimize_deck(
+    data: &Data,
+    field: &Field,
+    start: [i32; 5],
+    legality: &DeckLegality,
+) -> Optimized {
+    let mut candidates: Vec<i32> = data
+        .card_info
+        .keys()
+        .copied()
+        .filter(|&id| data.get_card(id).is_some())
+        .collect();
+    candidates.sort_by_key(|&id| (-card_strength(data, id, &field.rules), id));
+    candidates.truncate(CANDIDATES);
+
+    let start_score = field.score(data, &start);
+    let mut deck = start;
+    let mut score = start_score;
+    for _ in 0..MAX_ROUNDS {
+        let mut improved = false;
+        for slot in 0..deck.len() {
+            for &candidate in &candidates {
+                if deck.contains(&candidate) {
+                    continue;
+                }
+                let mut trial = deck;
+                trial[slot] = candidate;
+                if !legality.is_legal(data, &trial) {
+                    continue;
+                }
+                let trial_score = field.score(data, &trial);
+                if trial_score > score + MIN_IMPROVEMENT {
+                    deck = trial;
+                    score = trial_score;
+                    improved = true;
+                }
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    Optimized {
+        deck,
+        score,
+        start_score,
+    }
+}
@@ -0,0 +1,85 @@
+//! Move-path enumeration, like chess's perft: walks every sequence of moves to a given depth and
+//! counts what the moves at the last step do. Comparing the counts with another implementation of
+//! the rules finds where they disagree, and timing it measures how fast positions are generated.
+
+use std::ops::AddAssign;
+
+use crate::{
+    game::{Game, GameMove, Player},
+    search::{GamePlayer, SearchableGame, WinState},
+};
+
+/// What the moves at the last step of a perft did. A trigger is a move where the rule took at
+/// least one card.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PerftCounts {
+    pub positions: u64,
+    /// Cards flipped, in any way.
+    pub flips: u64,
+    pub same_triggers: u64,
+    pub plus_triggers: u64,
+    pub combo_triggers: u64,
+}
+impl AddAssign for PerftCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.positions += other.positions;
+        self.flips += other.flips;
+        self.same_triggers += other.same_triggers;
+        self.plus_triggers += other.plus_triggers;
+        self.combo_triggers += other.combo_triggers;
+    }
+}
+
+/// Counts the positions `depth` moves from the current one, with `player` to move. Lines that
+/// reach the end of the match sooner count as positions where they end.
+pub fn perft(game: &mut Game, player: Player, depth: usize) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    if depth == 0 || !matches!(game.win_state(), WinState::NotFinished) {
+        counts.positions = 1;
+        return counts;
+    }
+
+    let mut moves = Vec::with_capacity(50);
+    game.get_possible_moves(player, &mut moves);
+    for mv in moves {
+        game.apply_move(&mv);
+        counts += if depth == 1 {
+            last_move_counts(game)
+        } else {
+            perft(game, player.other(), depth - 1)
+        };
+        game.undo_last_moves(1);
+    }
+    counts
+}
+
+/// Like [`perft`], but split by the first move, for narrowing down where two implementations
+/// disagree.
+pub fn divide(game: &mut Game, player: Player, depth: usize) -> Vec<(GameMove, PerftCounts)> {
+    let mut moves = Vec::with_capacity(50);
+    game.get_possible_moves(player, &mut moves);
+    moves
+        .into_iter()
+        .map(|mv| {
+            game.apply_move(&mv);
+            let counts = if depth <= 1 {
+                last_move_counts(game)
+            } else {
+                perft(game, player.other(), depth - 1)
+            };
+            game.undo_last_moves(1);
+            (mv, counts)
+        })
+        .collect()
+}
+
+fn last_move_counts(game: &Game) -> PerftCounts {
+    let captures = game.last_move_captures();
+    PerftCounts {
+        positions: 1,
+        flips: captures.total() as u64,
+        same_triggers: (captures.same > 0) as u64,
+        plus_triggers: (captures.plus > 0) as u64,
+        combo_triggers: (captures.combo > 0) as u64,
+    }
+}
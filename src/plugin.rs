@@ -0,0 +1,386 @@
+// Follows a match against an NPC as reported by a companion plugin running alongside the game
+// (like a Dalamud or ACT plugin), so nothing has to be entered by hand. The plugin connects to a
+// local socket (a named pipe on Windows, a Unix socket elsewhere) and sends one JSON message per
+// line. Every message, in both directions, carries the protocol version:
+//
+//   {"version": 1, "type": "match_start", "npc": 1010396, "rules": ["Plus"],
+//    "hand": [1, 2, 3, 4, 5], "npc_hand": [], "first": "player"}
+//        `npc` is the NPC's ENpcResident ID, `rules` every rule in effect (NPC and regional), and
+//        `npc_hand` the NPC's cards if the rules reveal all five, or empty otherwise
+//   {"version": 1, "type": "move", "side": "npc", "card": 12, "cell": 4}
+//        a card was played; cells are 0 (top left) to 8 (bottom right), row by row
+//
+// The solver answers every message with a recommendation when it's the player's turn, the result
+// once the board is full, an error (which doesn't change the match), or just an acknowledgement:
+//
+//   {"version": 1, "type": "recommendation", "card": 3, "cell": 6, "cell_code": "C1", "score": 2.0,
+//    "expected": [{"side": "npc", "card": 12, "cell": 4}, {"side": "player", "card": 5, "cell": 2}]}
+//        `expected` is the NPC's likely reply and the player's answer to it, in the same form as
+//        `move` messages, for getting ready before the NPC plays. It's shorter near the end.
+//   {"version": 1, "type": "result", "result": "Win"}
+//   {"version": 1, "type": "error", "message": "..."}
+//   {"version": 1, "type": "ok"}
+
+use interprocess::local_socket::{prelude::*, GenericNamespaced, ListenerOptions, Stream};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    io::{self, BufRead, BufReader, Write},
+    sync::Arc,
+};
+use thiserror::Error;
+use triple_triad_solver::{
+    data::Data,
+    game::{self, CardId, Game, GameMove, Player, Rules},
+    search::{self, GamePlayer, SearchableGame, WinState},
+    settings::Settings,
+    stats::MatchResult,
+};
+
+use crate::PossiblePlacement;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const SOCKET_NAME: &str = "tripletriadsolver.sock";
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("couldn't talk to the plugin")]
+    Io(#[from] io::Error),
+
+    #[error("the plugin disconnected before the match ended")]
+    Disconnected,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum Side {
+    Player,
+    Npc,
+}
+impl Side {
+    // The human is always Blue against NPCs
+    fn color(self) -> Player {
+        match self {
+            Side::Player => Player::Blue,
+            Side::Npc => Player::Red,
+        }
+    }
+
+    fn of(player: Player) -> Self {
+        match player {
+            Player::Blue => Side::Player,
+            Player::Red => Side::Npc,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PluginMessage {
+    MatchStart {
+        npc: i32,
+        #[serde(default)]
+        rules: Vec<String>,
+        hand: Vec<i32>,
+        #[serde(default)]
+        npc_hand: Vec<i32>,
+        first: Side,
+    },
+    Move {
+        side: Side,
+        card: i32,
+        cell: usize,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SolverMessage {
+    Recommendation {
+        card: i32,
+        cell: usize,
+        cell_code: String,
+        score: f64,
+        expected: Vec<ExpectedMove>,
+    },
+    Result {
+        result: MatchResult,
+    },
+    Error {
+        message: String,
+    },
+    Ok,
+}
+
+#[derive(Serialize)]
+struct ExpectedMove {
+    side: Side,
+    card: i32,
+    cell: usize,
+}
+
+#[derive(Serialize)]
+struct Reply {
+    version: u32,
+    #[serde(flatten)]
+    message: SolverMessage,
+}
+
+/// A match the plugin reported from start to finish.
+pub struct FollowedMatch<'a> {
+    pub game: Game,
+    pub npc_name: &'a str,
+    pub hand: [i32; 5],
+    pub result: MatchResult,
+}
+
+struct MatchInProgress<'a> {
+    game: Game,
+    npc_name: &'a str,
+    hand: [i32; 5],
+    current_player: Player,
+}
+
+/// Waits for the plugin to connect, then follows the match it reports, printing the board and the
+/// recommended moves as it goes.
+pub fn follow_match<'a>(
+    data: &'a Data,
+    settings: &Settings,
+) -> Result<FollowedMatch<'a>, PluginError> {
+    let name = SOCKET_NAME.to_ns_name::<GenericNamespaced>()?;
+    let listener = ListenerOptions::new().name(name).create_sync()?;
+    println!(
+        "Waiting for the game plugin to connect to {}...",
+        SOCKET_NAME
+    );
+    let connection = listener.accept()?;
+    println!("The game plugin connected. Start the match in game.");
+
+    let mut reader = BufReader::new(&connection);
+    let mut current_match = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(PluginError::Disconnected);
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let reply = match handle_message(&line, &mut current_match, data, settings) {
+            Ok(reply) => reply,
+            Err(message) => SolverMessage::Error { message },
+        };
+
+        if let Some(result) = current_match.as_ref().and_then(match_result) {
+            let finished = current_match.unwrap();
+            send(&connection, SolverMessage::Result { result })?;
+            return Ok(FollowedMatch {
+                game: finished.game,
+                npc_name: finished.npc_name,
+                hand: finished.hand,
+                result,
+            });
+        }
+        send(&connection, reply)?;
+    }
+}
+
+fn send(mut connection: &Stream, message: SolverMessage) -> io::Result<()> {
+    let reply = Reply {
+        version: PROTOCOL_VERSION,
+        message,
+    };
+    writeln!(connection, "{}", serde_json::to_string(&reply).unwrap())
+}
+
+fn match_result(current_match: &MatchInProgress) -> Option<MatchResult> {
+    match current_match.game.win_state() {
+        WinState::NotFinished => None,
+        WinState::Tie => Some(MatchResult::Tie),
+        WinState::Winner(Player::Blue) => Some(MatchResult::Win),
+        WinState::Winner(Player::Red) => Some(MatchResult::Loss),
+    }
+}
+
+fn handle_message<'a>(
+    line: &str,
+    current_match: &mut Option<MatchInProgress<'a>>,
+    data: &'a Data,
+    settings: &Settings,
+) -> Result<SolverMessage, String> {
+    let version = serde_json::from_str::<Version>(line)
+        .map_err(|e| format!("invalid message: {}", e))?
+        .version;
+    if version != PROTOCOL_VERSION {
+        return Err(format!(
+            "unsupported protocol version {} (expected {})",
+            version, PROTOCOL_VERSION
+        ));
+    }
+
+    let current_match =
+        match serde_json::from_str(line).map_err(|e| format!("invalid message: {}", e))? {
+            PluginMessage::MatchStart {
+                npc,
+                rules,
+                hand,
+                npc_hand,
+                first,
+            } => current_match.insert(start_match(data, npc, &rules, &hand, &npc_hand, first)?),
+            PluginMessage::Move { side, card, cell } => {
+                let current_match = current_match.as_mut().ok_or("no match has been started")?;
+                play_move(current_match, data, side.color(), card, cell)?;
+                current_match
+            }
+        };
+
+    print!(
+        "{}",
+        current_match
+            .game
+            .display_with_hand_panel(Player::Red, data)
+    );
+    if current_match.current_player != Player::Blue || match_result(current_match).is_some() {
+        return Ok(SolverMessage::Ok);
+    }
+
+    println!("Finding optimal move (Ctrl-C to stop early)...");
+    let game = &current_match.game;
+    let (((recommended_move, (score, rates)), line), _) = crate::search_interruptibly(
+        &settings.search_config_against(current_match.npc_name),
+        |config| {
+            let best = search::get_best_move_for_player(game, Player::Blue, config);
+            let line = best
+                .0
+                .as_ref()
+                .map(|mv| search::expected_line(game, Player::Blue, mv, config))
+                .unwrap_or_default();
+            (best, line)
+        },
+    );
+    let recommended_move = recommended_move.ok_or("no legal moves")?;
+    println!(
+        "Recommended move: Play your {} card in the {} ({}). (Score: {}{})",
+        current_match
+            .game
+            .player_hand_card_name(Player::Blue, recommended_move.card_idx, data),
+        PossiblePlacement(recommended_move.placement),
+        game::cell_code(recommended_move.placement),
+        score,
+        rates
+            .map(|rates| format!("; random playouts: {}", rates))
+            .unwrap_or_default()
+    );
+    if let Some(description) =
+        game.describe_expected_line(&line, data.npc_display_name(current_match.npc_name), data)
+    {
+        println!("{}", description);
+    }
+
+    Ok(SolverMessage::Recommendation {
+        card: current_match.hand[recommended_move.card_idx],
+        cell: recommended_move.placement,
+        cell_code: game::cell_code(recommended_move.placement),
+        score,
+        expected: line
+            .iter()
+            .map(|mv| ExpectedMove {
+                side: Side::of(mv.player),
+                card: game.player_hand_card_id(mv.player, mv.card_idx).get(),
+                cell: mv.placement,
+            })
+            .collect(),
+    })
+}
+
+fn start_match<'a>(
+    data: &'a Data,
+    npc_id: i32,
+    rules: &[String],
+    hand: &[i32],
+    npc_hand: &[i32],
+    first: Side,
+) -> Result<MatchInProgress<'a>, String> {
+    let npc_name = data
+        .npcs_by_name
+        .iter()
+        .find(|(_, npc)| npc.resident_id == npc_id)
+        .map(|(name, _)| name.as_str())
+        .ok_or_else(|| format!("unknown NPC {}", npc_id))?;
+    let hand: [i32; 5] = hand
+        .try_into()
+        .map_err(|_| format!("expected 5 cards in hand, got {}", hand.len()))?;
+    let cards = |ids: &[i32]| {
+        ids.iter()
+            .map(|&id| {
+                CardId::new(id)
+                    .filter(|&card_id| data.cards.contains(card_id))
+                    .ok_or_else(|| format!("unknown card {}", id))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+    game.set_cards_in_hand(Player::Blue, &cards(&hand)?, 5);
+    game.set_cards_for_npc(Player::Red, data, npc_name);
+    match npc_hand.len() {
+        0 => {}
+        5 => game.set_cards_in_hand(Player::Red, &cards(npc_hand)?, 5),
+        len => return Err(format!("expected 0 or 5 revealed NPC cards, got {}", len)),
+    }
+
+    let mut match_rules = Rules::default();
+    for name in rules {
+        if !Rules::NAMES.contains(&name.as_str()) {
+            return Err(format!("unknown rule {}", name));
+        }
+        match_rules.add_rule_by_name(name);
+    }
+    game.add_rules(&match_rules);
+
+    Ok(MatchInProgress {
+        game,
+        npc_name,
+        hand,
+        current_player: first.color(),
+    })
+}
+
+fn play_move(
+    current_match: &mut MatchInProgress,
+    data: &Data,
+    player: Player,
+    card: i32,
+    cell: usize,
+) -> Result<(), String> {
+    if cell >= 9 {
+        return Err(format!("there's no cell {}", cell));
+    }
+    if current_match.game.board_cell(cell).is_some() {
+        return Err(format!("cell {} already has a card", cell));
+    }
+    let card_idx = CardId::new(card)
+        .and_then(|id| current_match.game.find_in_hand(player, id))
+        .ok_or_else(|| {
+            format!(
+                "{} isn't in {}'s hand",
+                data.card_display_name(card),
+                player
+            )
+        })?;
+
+    current_match.game.apply_move(&GameMove {
+        player,
+        card_idx,
+        placement: cell,
+    });
+    current_match.current_player = player.other();
+    Ok(())
+}
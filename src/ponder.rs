@@ -0,0 +1,140 @@
+//! Pondering: searching during the NPC's turn, while the player is waiting to see its move. Each of
+//! the NPC's likeliest replies is played out in the background and the player's answer to it worked
+//! out, so once the actual reply is entered the recommendation is usually already there. The
+//! searches share the match's transposition table, so even a reply that wasn't reached yet is
+//! quicker to search afterwards.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+use triple_triad_solver::{
+    game::{Game, GameMove, Player},
+    search::{self, CancelToken, ScoredLine, SearchConfig, SearchableGame},
+};
+
+/// How far the quick search that picks which replies to ponder first looks ahead, against an NPC
+/// that plays perfectly.
+const ORDERING_DEPTH: usize = 2;
+
+#[derive(Default)]
+struct Progress {
+    /// The replies searched so far, with the lines found after each and whether that search was
+    /// stopped before it finished.
+    done: Vec<(GameMove, Vec<ScoredLine<GameMove>>, bool)>,
+    /// The reply being searched now.
+    current: Option<GameMove>,
+}
+
+pub struct Ponder {
+    cancel: CancelToken,
+    /// Set to stop once the reply being searched is done, instead of dropping it.
+    stop_after_current: Arc<AtomicBool>,
+    progress: Arc<Mutex<Progress>>,
+    thread: Option<JoinHandle<()>>,
+}
+impl Ponder {
+    /// Starts searching `game`, with the NPC (Red) to move, in the background.
+    pub fn start(game: &Game, search_config: &SearchConfig, lines_shown: usize) -> Ponder {
+        let cancel = CancelToken::new();
+        let config = SearchConfig {
+            verbose: false,
+            progress: None,
+            cancel: Some(cancel.clone()),
+            ..search_config.clone()
+        };
+        let stop_after_current = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let mut game = game.truncate_history_and_clone();
+        let thread = thread::spawn({
+            let cancel = cancel.clone();
+            let stop_after_current = Arc::clone(&stop_after_current);
+            let progress = Arc::clone(&progress);
+            move || {
+                for reply in likely_replies(&mut game, &config) {
+                    if cancel.is_cancelled() || stop_after_current.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    progress.lock().unwrap().current = Some(reply);
+                    game.apply_move(&reply);
+                    let lines = crate::recommended_lines(&game, Player::Blue, &config, lines_shown);
+                    game.undo_last_moves(1);
+                    let mut progress = progress.lock().unwrap();
+                    progress.current = None;
+                    progress.done.push((reply, lines, cancel.is_cancelled()));
+                }
+            }
+        });
+        Ponder {
+            cancel,
+            stop_after_current,
+            progress,
+            thread: Some(thread),
+        }
+    }
+
+    /// Stops pondering, returning the lines found after `reply` (and whether that search was
+    /// stopped early), if it got to it. If `reply` is being searched right now, that search is
+    /// finished first, and Ctrl-C stops it like any other search.
+    pub fn finish(mut self, reply: &GameMove) -> Option<(Vec<ScoredLine<GameMove>>, bool)> {
+        // Decided with the lock held, so the thread can't move on to another reply in between
+        let searching_reply = {
+            let progress = self.progress.lock().unwrap();
+            let searching_reply = progress.current == Some(*reply);
+            if searching_reply {
+                self.stop_after_current.store(true, Ordering::Relaxed);
+            }
+            searching_reply
+        };
+        if searching_reply {
+            println!(
+                "Finishing the search started during the NPC's turn (Ctrl-C to stop early)..."
+            );
+            *crate::RUNNING_SEARCH.lock().unwrap() = Some(self.cancel.clone());
+        } else {
+            self.cancel.cancel();
+        }
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+        *crate::RUNNING_SEARCH.lock().unwrap() = None;
+
+        let progress = std::mem::take(&mut *self.progress.lock().unwrap());
+        let (_, lines, stopped) = progress
+            .done
+            .into_iter()
+            .find(|(searched, lines, _)| searched == reply && !lines.is_empty())?;
+        if stopped {
+            println!("Search stopped, so this is the best move it found so far.");
+        }
+        Some((lines, stopped))
+    }
+}
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The NPC's replies, likeliest first: the ones its opponent model leaves it, or for an NPC that
+/// plays perfectly, the best ones a quick search finds. The rest follow, in case it surprises.
+fn likely_replies(game: &mut Game, config: &SearchConfig) -> Vec<GameMove> {
+    let mut replies = Vec::with_capacity(100);
+    game.get_possible_moves(Player::Red, &mut replies);
+    let model = config.opponent.model::<Game>();
+    let mut likely = replies.clone();
+    if model.plays_best() {
+        likely = search::search_to_depth(&*game, Player::Red, ORDERING_DEPTH, config).0;
+    } else {
+        model.narrow_moves(game, Player::Red, &mut likely);
+    }
+    replies.retain(|reply| !likely.contains(reply));
+    likely.extend(replies);
+    likely
+}
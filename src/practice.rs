@@ -0,0 +1,192 @@
+//! Practice matches against the solver itself, for trying out rules and setups without an NPC.
+
+use inquire::MultiSelect;
+use rand::seq::SliceRandom;
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
+
+use triple_triad_solver::{
+    data::Data,
+    decks::SavedDecks,
+    game::{self, CardId, Game, GameMove, Player, Rules},
+    prompt::Select,
+    search::{
+        self, GamePlayer, GreedyFlips, OpponentModel, SearchConfig, SearchableGame, WinState,
+    },
+    settings::{HintLevel, Settings},
+};
+
+/// How far ahead the engine looks by default when its search depth is limited.
+const DEFAULT_DEPTH: usize = 2;
+
+/// Past this, limiting the depth hardly makes the engine weaker than playing perfectly.
+const MAX_DEPTH: usize = 6;
+
+/// How well the engine plays in a practice match.
+#[derive(Clone, Copy)]
+enum Strength {
+    Random,
+    Greedy,
+    /// Searches this many moves ahead, then plays one of the moves that looked best.
+    Depth(usize),
+    Full,
+}
+impl Strength {
+    fn pick_move(
+        self,
+        game: &mut Game,
+        moves: &mut Vec<GameMove>,
+        settings: &Settings,
+    ) -> GameMove {
+        let mut rng = rand::thread_rng();
+        match self {
+            Strength::Random => *moves.choose(&mut rng).unwrap(),
+            Strength::Greedy => {
+                GreedyFlips.narrow_moves(game, Player::Red, moves);
+                *moves.choose(&mut rng).unwrap()
+            }
+            Strength::Depth(depth) => {
+                *search::search_to_depth(&*game, Player::Red, depth, &settings.search_config())
+                    .0
+                    .choose(&mut rng)
+                    .unwrap()
+            }
+            Strength::Full => {
+                let search_config = SearchConfig {
+                    verbose: false,
+                    ..settings.search_config()
+                };
+                println!("The engine is thinking (Ctrl-C to hurry it up)...");
+                crate::search_interruptibly(&search_config, |config| {
+                    search::get_best_move_for_player(&*game, Player::Red, config)
+                })
+                .0
+                 .0
+                .unwrap()
+            }
+        }
+    }
+}
+impl Display for Strength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Strength::Random => write!(f, "Plays randomly"),
+            Strength::Greedy => write!(f, "Flips as many cards as it can"),
+            Strength::Depth(_) => write!(f, "Looks a few moves ahead"),
+            Strength::Full => write!(f, "Plays perfectly"),
+        }
+    }
+}
+
+/// Plays a match against the engine with one of the user's decks, without recording it.
+pub fn practice(data: &Data, saved_decks: &SavedDecks, settings: &Settings) {
+    if saved_decks.get_deck_count() == 0 {
+        println!("You must have at least 1 registered deck to practice!");
+        return;
+    }
+
+    let deck_name = crate::select_deck("Which deck are you using?", saved_decks);
+    let engine_deck_name = crate::select_deck("Which deck does the engine use?", saved_decks);
+
+    let strength = match Select::new(
+        "How well does the engine play?",
+        vec![
+            Strength::Random,
+            Strength::Greedy,
+            Strength::Depth(DEFAULT_DEPTH),
+            Strength::Full,
+        ],
+    )
+    .prompt()
+    .unwrap()
+    {
+        Strength::Depth(_) => Strength::Depth(
+            Select::new("How many moves ahead?", (1..=MAX_DEPTH).collect())
+                .with_starting_cursor(DEFAULT_DEPTH - 1)
+                .prompt()
+                .unwrap(),
+        ),
+        strength => strength,
+    };
+
+    let mut rules = Rules::default();
+    for name in MultiSelect::new("Which rules?", Rules::NAMES.to_vec())
+        .prompt()
+        .unwrap()
+    {
+        rules.add_rule_by_name(name);
+    }
+
+    let mut current_player = Select::new("Who goes first?", vec![Player::Blue, Player::Red])
+        .prompt()
+        .unwrap();
+
+    let to_ids = |name: &str| {
+        saved_decks
+            .get_deck(name)
+            .unwrap()
+            .map(|id| CardId::new(id).unwrap())
+    };
+    let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+    game.set_cards_in_hand(Player::Blue, &to_ids(&deck_name), 5);
+    game.set_cards_in_hand(Player::Red, &to_ids(&engine_deck_name), 5);
+    game.add_rules(&rules);
+
+    let mut possible_moves = Vec::with_capacity(100);
+    crate::print_rule_hints(game.rules());
+    loop {
+        print!("{}", game.display_with_hand_panel(Player::Red, data));
+
+        match game.win_state() {
+            WinState::NotFinished => {}
+            WinState::Tie => break println!("Tie!"),
+            WinState::Winner(Player::Blue) => break println!("You win!"),
+            WinState::Winner(Player::Red) => break println!("You lose!"),
+        }
+
+        game.get_possible_moves(current_player, &mut possible_moves);
+        if game.rules().chaos {
+            // The practice match stands in for the game, so it rolls the card itself
+            let mut slots = possible_moves
+                .iter()
+                .map(|mv| mv.card_idx)
+                .collect::<Vec<_>>();
+            slots.sort_unstable();
+            slots.dedup();
+            let slot = *slots.choose(&mut rand::thread_rng()).unwrap();
+            game.set_chaos_pick(Some(slot));
+            game.get_possible_moves(current_player, &mut possible_moves);
+            if current_player == Player::Blue {
+                println!(
+                    "Chaos picked your {} card.",
+                    game.player_hand_card_name(current_player, slot, data)
+                );
+            }
+        }
+        let mv = match current_player {
+            Player::Blue if settings.hint_level != HintLevel::Full => {
+                possible_moves[crate::coach::pick_coached_move(
+                    &possible_moves,
+                    &game,
+                    data,
+                    &settings.search_config(),
+                    settings,
+                )]
+            }
+            Player::Blue => possible_moves[crate::pick_move(&possible_moves, &game, data)],
+            Player::Red => {
+                let mv = strength.pick_move(&mut game, &mut possible_moves, settings);
+                println!(
+                    "The engine plays its {} card in {}.",
+                    game.player_hand_card_name(Player::Red, mv.card_idx, data),
+                    game::cell_code(mv.placement)
+                );
+                mv
+            }
+        };
+        game.apply_move(&mv);
+        current_player = current_player.other();
+    }
+}
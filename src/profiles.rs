@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::{dirs::DataDirs, store};
+
+pub const DEFAULT_PROFILE: &str = "Default";
+
+#[derive(Debug, Error)]
+pub enum ProfileError {
+    #[error("Could not read/write profiles file")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Could not parse profiles file")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Invalid profile name {0:?}")]
+    InvalidName(String),
+}
+
+/// The set of known profiles (one per character), and which one is active. Each profile keeps its
+/// decks and match statistics in its own directory under the config dir.
+#[derive(Serialize, Deserialize)]
+pub struct Profiles {
+    active: String,
+    names: Vec<String>,
+
+    #[serde(skip)]
+    config_dir: PathBuf,
+}
+impl Profiles {
+    pub fn new(data_dirs: &DataDirs) -> Result<Self, ProfileError> {
+        let config_dir = data_dirs.config_dir().to_path_buf();
+        let path = profiles_path(&config_dir);
+
+        let saved = store::load(&path, |text| {
+            serde_json::from_str::<Profiles>(text).map_err(ProfileError::from)
+        })?;
+        if let Some(mut result) = saved {
+            result.config_dir = config_dir;
+            Ok(result)
+        } else {
+            std::fs::create_dir_all(&config_dir)?;
+            let result = Profiles {
+                active: DEFAULT_PROFILE.to_string(),
+                names: vec![DEFAULT_PROFILE.to_string()],
+                config_dir,
+            };
+            result.save()?;
+            Ok(result)
+        }
+    }
+
+    pub fn get_active(&self) -> &str {
+        &self.active
+    }
+
+    pub fn get_names(&self) -> Vec<String> {
+        self.names.clone()
+    }
+
+    /// Returns the directory holding the active profile's files. The default profile uses the config
+    /// dir itself so that data saved before profiles existed is picked up.
+    pub fn active_dir(&self) -> PathBuf {
+        self.profile_dir(&self.active)
+    }
+
+    pub fn add_profile(&mut self, name: String) -> Result<(), ProfileError> {
+        if name.trim().is_empty() || name.contains(|c: char| std::path::is_separator(c) || c == '.')
+        {
+            return Err(ProfileError::InvalidName(name));
+        }
+
+        if !self.names.contains(&name) {
+            std::fs::create_dir_all(self.profile_dir(&name))?;
+            self.names.push(name);
+        }
+        self.save()
+    }
+
+    pub fn set_active(&mut self, name: &str) -> Result<(), ProfileError> {
+        if !self.names.iter().any(|n| n == name) {
+            return Err(ProfileError::InvalidName(name.to_string()));
+        }
+
+        self.active = name.to_string();
+        self.save()
+    }
+
+    fn profile_dir(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            self.config_dir.clone()
+        } else {
+            let mut dir = self.config_dir.clone();
+            dir.push("profiles");
+            dir.push(name);
+            dir
+        }
+    }
+
+    fn save(&self) -> Result<(), ProfileError> {
+        store::save(
+            &profiles_path(&self.config_dir),
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
+
+fn profiles_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("profiles.json")
+}
@@ -0,0 +1,533 @@
+//! Prompts answered with a single keystroke, in place of inquire's where typing a word and pressing
+//! enter is slower than it needs to be: yes/no questions, and numbered menus where an item's
+//! number picks it.
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::Stylize,
+    terminal::{self, ClearType},
+};
+use inquire::{
+    error::{InquireError, InquireResult},
+    list_option::ListOption,
+};
+use std::{
+    fmt::Display,
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use crate::game;
+
+/// A yes/no question answered by pressing `y` or `n`, or enter for the default. Built like
+/// [`inquire::Confirm`], which it falls back to when input isn't coming from a terminal.
+pub struct Confirm<'a> {
+    message: &'a str,
+    default: Option<bool>,
+    help_message: Option<&'a str>,
+}
+impl<'a> Confirm<'a> {
+    pub fn new(message: &'a str) -> Self {
+        Confirm {
+            message,
+            default: None,
+            help_message: None,
+        }
+    }
+
+    pub fn with_default(mut self, default: bool) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    pub fn with_help_message(mut self, message: &'a str) -> Self {
+        self.help_message = Some(message);
+        self
+    }
+
+    /// Waits for an answer. Escape cancels, and Ctrl-C interrupts, as in inquire's prompts.
+    pub fn prompt(self) -> InquireResult<bool> {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            let mut confirm = inquire::Confirm::new(self.message);
+            if let Some(default) = self.default {
+                confirm = confirm.with_default(default);
+            }
+            if let Some(help_message) = self.help_message {
+                confirm = confirm.with_help_message(help_message);
+            }
+            return confirm.prompt();
+        }
+
+        let choices = match self.default {
+            Some(true) => "(Y/n)",
+            Some(false) => "(y/N)",
+            None => "(y/n)",
+        };
+        let mut stdout = io::stdout();
+        let width = terminal::size().map_or(80, |(columns, _)| columns.max(1) as usize);
+        // How far the question runs, and so where the cursor ends up, if it wraps
+        let question = self.message.chars().count() + choices.len() + 4;
+        print!("{} {} {} ", prefix(), self.message, choices);
+        if let Some(help_message) = self.help_message {
+            // Below the question, with the cursor put back after it
+            let help = help_message.chars().count() + 2;
+            print!("\n{}", styled_help(help_message));
+            execute!(
+                stdout,
+                cursor::MoveToPreviousLine(((help - 1) / width + 1) as u16),
+                cursor::MoveToColumn((question % width) as u16)
+            )?;
+        }
+        stdout.flush()?;
+
+        let answer = read_answer(self.default);
+        // Replaced by the question with its answer, the way inquire leaves a finished prompt
+        if question >= width {
+            execute!(
+                stdout,
+                cursor::MoveToPreviousLine((question / width) as u16)
+            )?;
+        }
+        print!("\r");
+        execute!(stdout, terminal::Clear(ClearType::FromCursorDown))?;
+        match answer {
+            Ok(answer) => {
+                let answer = if answer { "Yes" } else { "No" };
+                if game::color_enabled() {
+                    println!("{} {} {}", prefix(), self.message, answer.cyan());
+                } else {
+                    println!("{} {} {}", prefix(), self.message, answer);
+                }
+            }
+            Err(InquireError::OperationCanceled) => {
+                if game::color_enabled() {
+                    println!("{} {} {}", prefix(), self.message, "<canceled>".dark_red());
+                } else {
+                    println!("{} {} <canceled>", prefix(), self.message);
+                }
+            }
+            Err(_) => println!(),
+        }
+        answer
+    }
+}
+
+fn prefix() -> String {
+    if game::color_enabled() {
+        "?".green().to_string()
+    } else {
+        String::from("?")
+    }
+}
+
+fn styled_help(help_message: &str) -> String {
+    if game::color_enabled() {
+        format!("[{}]", help_message).cyan().to_string()
+    } else {
+        format!("[{}]", help_message)
+    }
+}
+
+/// Reads keys in raw mode until one of them answers the question.
+fn read_answer(default: Option<bool>) -> InquireResult<bool> {
+    terminal::enable_raw_mode()?;
+    let answer = loop {
+        let key = match event::read() {
+            Ok(Event::Key(key)) => key,
+            Ok(_) => continue,
+            Err(e) => break Err(e.into()),
+        };
+        match key {
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+            } => break Err(InquireError::OperationInterrupted),
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => break Err(InquireError::OperationCanceled),
+            KeyEvent {
+                code: KeyCode::Char('y'),
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('Y'),
+                ..
+            } => break Ok(true),
+            KeyEvent {
+                code: KeyCode::Char('n'),
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('N'),
+                ..
+            } => break Ok(false),
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => {
+                if let Some(default) = default {
+                    break Ok(default);
+                }
+            }
+            _ => {}
+        }
+    };
+    terminal::disable_raw_mode()?;
+    answer
+}
+
+/// The help line for a menu, unless it has one of its own.
+const SELECT_HELP: &str =
+    "↑↓ to move, enter to select, press a number to pick that item, or type to filter";
+
+/// The help line for the menu inquire shows in place of [`Select`]'s.
+const BY_NUMBER_HELP: &str = "↑↓ to move, enter to select, type a number or words to filter";
+
+/// Items shown at once, as in inquire's menus.
+const PAGE_SIZE: usize = 7;
+
+/// How long a number that could still go on (`1` in a menu with a 12th item) waits for its next
+/// digit before picking the item it already names.
+const NEXT_DIGIT_TIMEOUT: Duration = Duration::from_millis(600);
+
+/// A menu where pressing an item's number picks it straight away, without enter. Each item's
+/// number is the one it starts with ("3. Settings"), so menus that leave items out keep the same
+/// numbers for the rest, or else its place in the list, which is shown in front of it. When a
+/// number could go on to a longer one, the menu waits a moment for the next digit. The arrow keys
+/// and enter work as in inquire's menus, and typing anything else filters the list. Built like
+/// [`inquire::Select`], which it falls back to when input isn't coming from a terminal.
+pub struct Select<'a, T> {
+    message: &'a str,
+    options: Vec<T>,
+    help_message: Option<&'a str>,
+    starting_cursor: usize,
+}
+impl<'a, T: Display> Select<'a, T> {
+    pub fn new(message: &'a str, options: Vec<T>) -> Self {
+        Select {
+            message,
+            options,
+            help_message: None,
+            starting_cursor: 0,
+        }
+    }
+
+    pub fn with_help_message(mut self, message: &'a str) -> Self {
+        self.help_message = Some(message);
+        self
+    }
+
+    pub fn with_starting_cursor(mut self, starting_cursor: usize) -> Self {
+        self.starting_cursor = starting_cursor;
+        self
+    }
+
+    /// Waits for an item to be picked. Escape cancels, and Ctrl-C interrupts, as in inquire's
+    /// prompts.
+    pub fn prompt(self) -> InquireResult<T> {
+        self.raw_prompt().map(|picked| picked.value)
+    }
+
+    /// Like [`Select::prompt`], along with where the item picked was in the list.
+    pub fn raw_prompt(mut self) -> InquireResult<ListOption<T>> {
+        if self.options.is_empty() {
+            return Err(InquireError::InvalidConfiguration(
+                "Available options can not be empty".to_string(),
+            ));
+        }
+        let labels = self
+            .options
+            .iter()
+            .map(|option| option.to_string())
+            .collect::<Vec<_>>();
+        let own_numbers = labels
+            .iter()
+            .map(|label| label_number(label))
+            .collect::<Option<Vec<_>>>();
+        let (numbers, labels) = match own_numbers {
+            Some(numbers) => (numbers, labels),
+            None => (
+                (1..=labels.len()).collect(),
+                labels
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, label)| format!("{}. {}", idx + 1, label))
+                    .collect(),
+            ),
+        };
+        let starting_cursor = self.starting_cursor.min(labels.len() - 1);
+
+        let index = if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            inquire::Select::new(self.message, labels)
+                .with_filter(&by_number)
+                .with_help_message(self.help_message.unwrap_or(BY_NUMBER_HELP))
+                .with_starting_cursor(starting_cursor)
+                .raw_prompt()?
+                .index
+        } else {
+            let menu = Menu {
+                message: self.message,
+                help_message: self.help_message.unwrap_or(SELECT_HELP),
+                labels: &labels,
+                numbers: &numbers,
+            };
+            let picked = menu.pick(starting_cursor);
+            menu.finish(&picked);
+            picked?
+        };
+        Ok(ListOption::new(index, self.options.swap_remove(index)))
+    }
+}
+
+/// The number an item's label starts with, like the 3 in "3. Settings".
+fn label_number(label: &str) -> Option<usize> {
+    let (number, _) = label.split_once(". ")?;
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+/// A [`Select`] being shown, with each item's label (starting with its number) and number.
+struct Menu<'a> {
+    message: &'a str,
+    help_message: &'a str,
+    labels: &'a [String],
+    numbers: &'a [usize],
+}
+impl Menu<'_> {
+    /// Reads keys in raw mode, drawing the menu again after each, until an item is picked.
+    /// Returns its index.
+    fn pick(&self, starting_cursor: usize) -> InquireResult<usize> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, cursor::Hide)?;
+
+        let mut filter = String::new();
+        let mut digits = String::new();
+        // Whether the digits so far name the item under the cursor, which waits in case another
+        // digit follows
+        let mut waiting = false;
+        let mut visible = (0..self.labels.len()).collect::<Vec<_>>();
+        let mut cursor = starting_cursor;
+        let mut first_shown = 0;
+        let mut rows = 0;
+        let picked = loop {
+            if cursor < first_shown {
+                first_shown = cursor;
+            } else if cursor >= first_shown + PAGE_SIZE {
+                first_shown = cursor + 1 - PAGE_SIZE;
+            }
+            match self.draw(&filter, &visible, cursor, first_shown, rows) {
+                Ok(drawn) => rows = drawn,
+                Err(e) => break Err(e),
+            }
+
+            if waiting {
+                match event::poll(NEXT_DIGIT_TIMEOUT) {
+                    Ok(true) => {}
+                    Ok(false) => break Ok(visible[cursor]),
+                    Err(e) => break Err(e.into()),
+                }
+            }
+            let key = match event::read() {
+                Ok(Event::Key(key)) => key,
+                Ok(_) => continue,
+                Err(e) => break Err(e.into()),
+            };
+            let digit = match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() => Some(c),
+                _ => None,
+            };
+            if digit.is_none() {
+                digits.clear();
+                waiting = false;
+            }
+            match key {
+                KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                } => break Err(InquireError::OperationInterrupted),
+                KeyEvent {
+                    code: KeyCode::Esc, ..
+                } => break Err(InquireError::OperationCanceled),
+                KeyEvent {
+                    code: KeyCode::Enter,
+                    ..
+                } => {
+                    if let Some(&idx) = visible.get(cursor) {
+                        break Ok(idx);
+                    }
+                }
+                KeyEvent {
+                    code: KeyCode::Up, ..
+                } if !visible.is_empty() => {
+                    cursor = (cursor + visible.len() - 1) % visible.len();
+                }
+                KeyEvent {
+                    code: KeyCode::Down,
+                    ..
+                } if !visible.is_empty() => {
+                    cursor = (cursor + 1) % visible.len();
+                }
+                KeyEvent {
+                    code: KeyCode::Backspace,
+                    ..
+                } => {
+                    filter.pop();
+                    visible = self.matching(&filter);
+                    cursor = 0;
+                }
+                KeyEvent {
+                    code: KeyCode::Char(c),
+                    modifiers,
+                } if digit.is_none() && !modifiers.contains(KeyModifiers::CONTROL) => {
+                    filter.push(c);
+                    visible = self.matching(&filter);
+                    cursor = 0;
+                }
+                _ => {}
+            }
+
+            if let Some(digit) = digit {
+                digits.push(digit);
+                if !self.number_starts_with(&visible, &digits) {
+                    // Not the start of any number, so it may start a new one
+                    digits = digit.to_string();
+                    if !self.number_starts_with(&visible, &digits) {
+                        digits.clear();
+                    }
+                }
+                let named = visible
+                    .iter()
+                    .position(|&idx| self.numbers[idx].to_string() == digits);
+                let longer = visible.iter().any(|&idx| {
+                    let number = self.numbers[idx].to_string();
+                    number.len() > digits.len() && number.starts_with(&digits)
+                });
+                waiting = false;
+                match named {
+                    Some(position) if !longer => break Ok(visible[position]),
+                    Some(position) => {
+                        cursor = position;
+                        waiting = true;
+                    }
+                    None => {}
+                }
+            }
+        };
+
+        execute!(stdout, cursor::Show)?;
+        terminal::disable_raw_mode()?;
+        // Back to the top of the menu, to be replaced by the answer
+        if rows > 1 {
+            execute!(stdout, cursor::MoveToPreviousLine((rows - 1) as u16))?;
+        }
+        print!("\r");
+        execute!(stdout, terminal::Clear(ClearType::FromCursorDown))?;
+        picked
+    }
+
+    /// The items whose labels contain `filter`, ignoring case.
+    fn matching(&self, filter: &str) -> Vec<usize> {
+        let filter = filter.to_lowercase();
+        (0..self.labels.len())
+            .filter(|&idx| self.labels[idx].to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    fn number_starts_with(&self, visible: &[usize], digits: &str) -> bool {
+        visible
+            .iter()
+            .any(|&idx| self.numbers[idx].to_string().starts_with(digits))
+    }
+
+    /// Draws the menu over the `rows` it took up last time, returning how many it takes up now.
+    fn draw(
+        &self,
+        filter: &str,
+        visible: &[usize],
+        cursor: usize,
+        first_shown: usize,
+        rows: usize,
+    ) -> InquireResult<usize> {
+        let mut stdout = io::stdout();
+        if rows > 1 {
+            execute!(stdout, cursor::MoveToPreviousLine((rows - 1) as u16))?;
+        }
+        print!("\r");
+        execute!(stdout, terminal::Clear(ClearType::FromCursorDown))?;
+
+        let width = terminal::size().map_or(80, |(columns, _)| columns.max(1) as usize);
+        let question = format!("{} {} {}", prefix(), self.message, filter);
+        let mut lines = vec![(question, self.message.chars().count() + filter.len() + 3)];
+        for (position, &idx) in visible.iter().enumerate().skip(first_shown).take(PAGE_SIZE) {
+            let label = &self.labels[idx];
+            let line = if position != cursor {
+                format!("  {}", label)
+            } else if game::color_enabled() {
+                format!("{} {}", ">".cyan(), label.as_str().cyan())
+            } else {
+                format!("> {}", label)
+            };
+            lines.push((line, label.chars().count() + 2));
+        }
+        lines.push((
+            styled_help(self.help_message),
+            self.help_message.chars().count() + 2,
+        ));
+
+        let mut drawn = 0;
+        for (idx, (line, length)) in lines.iter().enumerate() {
+            if idx > 0 {
+                print!("\r\n");
+            }
+            print!("{}", line);
+            drawn += (length.max(&1) - 1) / width + 1;
+        }
+        stdout.flush()?;
+        Ok(drawn)
+    }
+
+    /// Prints the question with the label of the item picked, the way inquire leaves a finished
+    /// prompt.
+    fn finish(&self, picked: &InquireResult<usize>) {
+        match picked {
+            Ok(idx) => {
+                if game::color_enabled() {
+                    println!(
+                        "{} {} {}",
+                        prefix(),
+                        self.message,
+                        self.labels[*idx].as_str().cyan()
+                    );
+                } else {
+                    println!("{} {} {}", prefix(), self.message, self.labels[*idx]);
+                }
+            }
+            Err(InquireError::OperationCanceled) => {
+                if game::color_enabled() {
+                    println!("{} {} {}", prefix(), self.message, "<canceled>".dark_red());
+                } else {
+                    println!("{} {} <canceled>", prefix(), self.message);
+                }
+            }
+            Err(_) => println!(),
+        }
+    }
+}
+
+/// A filter for the menu [`Select`] falls back to, where the numbers in front of the items work
+/// as hotkeys: typing only digits leaves just the item with that number, so `3` and enter picks
+/// item 3 and not 13 or 23. Anything else typed is matched the way inquire does by default,
+/// ignoring case.
+fn by_number(filter: &str, _: &String, label: &str, _: usize) -> bool {
+    if !filter.is_empty() && filter.chars().all(|c| c.is_ascii_digit()) {
+        if let Some(number) = label_number(label) {
+            return number.to_string() == filter;
+        }
+    }
+    label.to_lowercase().contains(&filter.to_lowercase())
+}
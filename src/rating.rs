@@ -0,0 +1,71 @@
+//! Elo-style ratings for decks and NPCs, so decks can be ranked by who they've beaten and not just
+//! how often they've won: a win against a hard NPC counts for more than one against an easy one, and
+//! an NPC's rating goes up each time it beats a deck.
+
+use std::collections::HashMap;
+
+/// Where every deck and NPC starts.
+pub const INITIAL_RATING: f64 = 1500.0;
+/// How far one match can move a rating.
+const K_FACTOR: f64 = 32.0;
+/// The rating difference at which the higher one is expected to score ten times as much.
+const SCALE: f64 = 400.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Rating {
+    pub value: f64,
+    /// Matches played, real or simulated, that went into it.
+    pub matches: usize,
+}
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            value: INITIAL_RATING,
+            matches: 0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Ratings {
+    decks: HashMap<String, Rating>,
+    npcs: HashMap<String, Rating>,
+}
+impl Ratings {
+    /// Updates both ratings after `deck` played `npc`. `score` is what the deck got out of it: 1 for
+    /// a win, 0.5 for a tie, and 0 for a loss, or in between for the average of many matches.
+    pub fn record(&mut self, deck: &str, npc: &str, score: f64) {
+        let deck_rating = self.decks.entry(deck.to_string()).or_default();
+        let npc_rating = self.npcs.entry(npc.to_string()).or_default();
+        let expected = 1.0 / (1.0 + 10f64.powf((npc_rating.value - deck_rating.value) / SCALE));
+        let change = K_FACTOR * (score - expected);
+        deck_rating.value += change;
+        deck_rating.matches += 1;
+        npc_rating.value -= change;
+        npc_rating.matches += 1;
+    }
+
+    /// Every rated deck, best first.
+    pub fn decks(&self) -> Vec<(&str, Rating)> {
+        ranked(&self.decks)
+    }
+
+    /// Every rated NPC, hardest first.
+    pub fn npcs(&self) -> Vec<(&str, Rating)> {
+        ranked(&self.npcs)
+    }
+}
+
+fn ranked(ratings: &HashMap<String, Rating>) -> Vec<(&str, Rating)> {
+    let mut ranked = ratings
+        .iter()
+        .map(|(name, &rating)| (name.as_str(), rating))
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a_name, a), (b_name, b)| {
+        b.value
+            .partial_cmp(&a.value)
+            .unwrap()
+            .then_with(|| a_name.cmp(b_name))
+    });
+    ranked
+}
@@ -0,0 +1,376 @@
+//! Scenario files: positions written out with the result the solver should find, for checking the
+//! rules and the search against known puzzles. They live in `scenarios/` as TOML, like:
+//!
+//! ```toml
+//! name = "Fallen Ace: a 1 takes an A"
+//! rules = ["Fallen Ace"]
+//! first = "Red"
+//! blue = ["5-5-5-5", "5-7-5-5", "5-5-5-5", "9-1-1-1", "1-9-1-1"]
+//! red = ["5-5-5-5", "5-5-5-5", "5-5-5-5", "5-5-5-A", "2-1-1-6"]
+//! moves = ["5-5-5-5@A1", "5-5-5-5@A2", "5-5-5-5@A3", "5-7-5-5@B1", "5-5-5-5@B3", "5-5-5-5@C1",
+//!          "5-5-5-A@C3"]
+//!
+//! [expect]
+//! best_moves = ["9-1-1-1@C2"]
+//! value = 100
+//! ```
+//!
+//! Cards are written by their values clockwise from the top (`A` for 10), optionally followed by a
+//! space and their suit's letter, like `4-A-3-2 P`, so scenarios don't depend on the card data.
+//! Both hands hold five cards. A hand can also list cards it might hold, like an NPC's variable
+//! cards, as `blue_variable` or `red_variable`: it then holds the cards it lists and the rest of its
+//! five from these, without saying which. `moves` are played first, alternating from `first`, each as a card
+//! from the mover's hand and the cell it goes in. The player to move after that is the one solved
+//! for, and `expect` says which moves should come out best (all of them, in any order) and the
+//! search's score for them: 100 for a win, -100 for a loss, and -30 for a tie once the search
+//! reaches the end of the match. A scenario with `skip` set is listed but not checked, for rules the
+//! solver doesn't handle yet. The tutorial's lessons are set up the same way, without `expect`.
+
+use serde::Deserialize;
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+use crate::{
+    data::Data,
+    game::{
+        self, Card, CardDb, CardId, Game, GameBuildError, GameBuilder, GameMove, HandCard, Player,
+        Rules, Suit,
+    },
+    search::{self, GamePlayer, SearchConfig, SearchableGame},
+};
+
+const HAND_SIZE: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("could not read {}", .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("invalid scenario {}", .0.display())]
+    Parse(PathBuf, #[source] toml::de::Error),
+
+    #[error("invalid card {0}")]
+    InvalidCard(String),
+
+    #[error("unknown rule {0}")]
+    UnknownRule(String),
+
+    #[error("{player} has {count} cards, but hands hold {}", HAND_SIZE)]
+    HandSize { player: Player, count: usize },
+
+    #[error(
+        "{player} has {count} cards and {variable} it might hold, which can't make a hand of {}",
+        HAND_SIZE
+    )]
+    VariableHandSize {
+        player: Player,
+        count: usize,
+        variable: usize,
+    },
+
+    #[error("invalid move {0}")]
+    InvalidMove(String),
+
+    #[error("illegal move {mv}: {reason}")]
+    IllegalMove { mv: String, reason: &'static str },
+
+    #[error(transparent)]
+    InvalidGame(#[from] GameBuildError),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub first: Player,
+    pub blue: Vec<String>,
+    pub red: Vec<String>,
+    /// Cards Blue might hold, drawn to make up the rest of its hand.
+    #[serde(default)]
+    pub blue_variable: Vec<String>,
+    /// Cards Red might hold, drawn to make up the rest of its hand.
+    #[serde(default)]
+    pub red_variable: Vec<String>,
+    #[serde(default)]
+    pub moves: Vec<String>,
+    /// Why the scenario isn't checked yet, if it isn't.
+    #[serde(default)]
+    pub skip: Option<String>,
+    /// Left out by positions that aren't checked, like the tutorial's.
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct Expectation {
+    #[serde(default)]
+    pub best_moves: Vec<String>,
+    pub value: Option<f64>,
+}
+
+/// What happened when a scenario was checked.
+#[derive(Debug)]
+pub enum Verdict {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, ScenarioError> {
+        let text =
+            fs::read_to_string(path).map_err(|e| ScenarioError::Io(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(|e| ScenarioError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Loads every `.toml` file in `dir`, sorted by file name.
+    pub fn load_dir(dir: &Path) -> Result<Vec<(PathBuf, Self)>, ScenarioError> {
+        let mut paths = fs::read_dir(dir)
+            .map_err(|e| ScenarioError::Io(dir.to_path_buf(), e))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| ScenarioError::Io(dir.to_path_buf(), e))?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+        paths.sort();
+
+        paths
+            .into_iter()
+            .map(|path| Scenario::load(&path).map(|scenario| (path, scenario)))
+            .collect()
+    }
+
+    /// The player to move once the scenario's moves have been played.
+    pub fn to_move(&self) -> Player {
+        if self.moves.len().is_multiple_of(2) {
+            self.first
+        } else {
+            self.first.other()
+        }
+    }
+
+    /// Solves the scenario and compares the result against what it expects.
+    pub fn verify(&self) -> Result<Verdict, ScenarioError> {
+        if let Some(reason) = &self.skip {
+            return Ok(Verdict::Skipped(reason.clone()));
+        }
+
+        let game = Game::from_scenario(self)?;
+        let player = self.to_move();
+        // Without the Monte Carlo tiebreak, every move gets its exact score
+        let config = SearchConfig {
+            monte_carlo_iterations: 0,
+            verbose: false,
+            ..SearchConfig::default()
+        };
+        let ranked = search::rank_moves(&game, player, &config);
+        let best_score = match ranked.first() {
+            Some((_, score, _)) => *score,
+            None => return Ok(Verdict::Failed("there are no moves to play".to_string())),
+        };
+
+        let mut problems = vec![];
+        if let Some(value) = self.expect.value {
+            if value != best_score {
+                problems.push(format!("expected a value of {}, got {}", value, best_score));
+            }
+        }
+        if !self.expect.best_moves.is_empty() {
+            let expected = self
+                .expect
+                .best_moves
+                .iter()
+                .map(|mv| normalize_move(mv))
+                .collect::<Result<BTreeSet<_>, _>>()?;
+            let best = ranked
+                .iter()
+                .filter(|(_, score, _)| *score == best_score)
+                .map(|(mv, _, _)| move_name(&game, mv))
+                .collect::<BTreeSet<_>>();
+            if expected != best {
+                problems.push(format!(
+                    "expected the best moves to be {}, got {}",
+                    expected.into_iter().collect::<Vec<_>>().join(", "),
+                    best.into_iter().collect::<Vec<_>>().join(", ")
+                ));
+            }
+        }
+
+        Ok(if problems.is_empty() {
+            Verdict::Passed
+        } else {
+            Verdict::Failed(problems.join("; "))
+        })
+    }
+}
+
+impl Scenario {
+    /// The cards in both hands, and which player holds or might hold each. The cards don't come
+    /// from the data, so their IDs are just their place in the two hands, Blue's first.
+    fn cards(&self) -> Result<(CardDb, Vec<(Player, HandCard)>), ScenarioError> {
+        let hands = [
+            (Player::Blue, &self.blue, &self.blue_variable),
+            (Player::Red, &self.red, &self.red_variable),
+        ];
+        let mut cards = CardDb::default();
+        let mut hand_cards = vec![];
+        for (player, hand, variable) in hands {
+            if variable.is_empty() && hand.len() != HAND_SIZE {
+                return Err(ScenarioError::HandSize {
+                    player,
+                    count: hand.len(),
+                });
+            }
+            if hand.len() >= HAND_SIZE && !variable.is_empty()
+                || hand.len() + variable.len() < HAND_SIZE
+            {
+                return Err(ScenarioError::VariableHandSize {
+                    player,
+                    count: hand.len(),
+                    variable: variable.len(),
+                });
+            }
+            let given = hand
+                .iter()
+                .map(|card| (card, true))
+                .chain(variable.iter().map(|card| (card, false)));
+            for (card, known) in given {
+                let id = CardId::new(hand_cards.len() as i32 + 1).unwrap();
+                cards.insert(id, parse_card(card)?);
+                hand_cards.push((player, HandCard { id, known }));
+            }
+        }
+        Ok((cards, hand_cards))
+    }
+
+    /// Card data holding just the scenario's cards, each named by its values (like `4-A-3-2`), for
+    /// showing a game from [`Game::from_scenario`] with what shows a match by card names.
+    pub fn card_data(&self) -> Result<Data, ScenarioError> {
+        let (cards, _) = self.cards()?;
+        let names = cards
+            .iter()
+            .map(|(id, card)| (id.get(), card.to_string()))
+            .collect();
+        Ok(Data::from_cards(cards, names))
+    }
+}
+
+impl Game {
+    /// Sets up the scenario's hands and rules and plays its moves, leaving the player solved for
+    /// (see [`Scenario::to_move`]) to move.
+    pub fn from_scenario(scenario: &Scenario) -> Result<Game, ScenarioError> {
+        let mut rules = Rules::default();
+        for name in &scenario.rules {
+            if !Rules::NAMES.contains(&name.as_str()) {
+                return Err(ScenarioError::UnknownRule(name.clone()));
+            }
+            rules.add_rule_by_name(name);
+        }
+
+        let (cards, hand_cards) = scenario.cards()?;
+        let mut builder = GameBuilder::new(scenario.to_move(), Arc::new(cards))
+            .rules(rules)
+            .to_move(scenario.first)
+            .hand_size(Player::Blue, HAND_SIZE)
+            .hand_size(Player::Red, HAND_SIZE);
+        for (player, card) in hand_cards {
+            builder = builder.hand_card(player, card);
+        }
+        let mut game = builder.build()?;
+
+        let mut player = scenario.first;
+        for mv in &scenario.moves {
+            let mv = find_move(&game, player, mv)?;
+            game.apply_move(&mv);
+            player = player.other();
+        }
+        Ok(game)
+    }
+}
+
+/// Finds the move `text` (like `4-A-3-2@B2`) stands for when `player` is to move: the card from
+/// their hand and the cell it goes in.
+pub fn find_move(game: &Game, player: Player, text: &str) -> Result<GameMove, ScenarioError> {
+    let (card, cell) = parse_move(text)?;
+    let illegal = |reason| ScenarioError::IllegalMove {
+        mv: text.to_string(),
+        reason,
+    };
+    if game.board_cell(cell).is_some() {
+        return Err(illegal("the cell is taken"));
+    }
+    let card_idx = (0..game::HAND_SLOTS)
+        .find(|&idx| {
+            game.hand_card(player, idx)
+                .is_some_and(|in_hand| in_hand.to_string() == card.to_string())
+        })
+        .ok_or_else(|| illegal("the card isn't in the mover's hand"))?;
+    Ok(GameMove {
+        player,
+        card_idx,
+        placement: cell,
+    })
+}
+
+/// Parses a card written like [`Card`]'s `Display`: `4-A-3-2`, or `4-A-3-2 P` with a suit.
+fn parse_card(text: &str) -> Result<Card, ScenarioError> {
+    let invalid = || ScenarioError::InvalidCard(text.to_string());
+    let mut parts = text.split_whitespace();
+    let values = parts
+        .next()
+        .ok_or_else(invalid)?
+        .split('-')
+        .map(|value| match value {
+            "A" => Some(10),
+            _ => value
+                .parse::<u8>()
+                .ok()
+                .filter(|value| (1..=9).contains(value)),
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    let suit = match parts.next() {
+        None => Suit::None,
+        Some(letter) => match letter.chars().collect::<Vec<_>>().as_slice() {
+            &[letter] => Suit::from_letter(letter).ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        },
+    };
+    match (values.as_slice(), parts.next()) {
+        // North, east, south, west, into the constructor's north, south, west, east
+        (&[n, e, s, w], None) => Ok(Card::new(n, s, w, e, suit)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a move like `4-A-3-2@B2` into the card and the cell.
+fn parse_move(text: &str) -> Result<(Card, usize), ScenarioError> {
+    let invalid = || ScenarioError::InvalidMove(text.to_string());
+    let (card, cell) = text.rsplit_once('@').ok_or_else(invalid)?;
+    let cell_code = cell.trim().to_ascii_uppercase();
+    let cell = (0..9)
+        .find(|&cell| game::cell_code(cell) == cell_code)
+        .ok_or_else(invalid)?;
+    Ok((parse_card(card)?, cell))
+}
+
+/// Writes a move the same way the scenario would, so they can be compared.
+fn normalize_move(text: &str) -> Result<String, ScenarioError> {
+    let (card, cell) = parse_move(text)?;
+    Ok(format!("{}@{}", card, game::cell_code(cell)))
+}
+
+fn move_name(game: &Game, mv: &GameMove) -> String {
+    format!(
+        "{}@{}",
+        game.hand_card(mv.player, mv.card_idx).unwrap(),
+        game::cell_code(mv.placement)
+    )
+}
@@ -0,0 +1,137 @@
+// Reads a position from a screenshot of the match (see the library's `vision` module) and solves
+// it. Face-down NPC cards can't be read, so for those the NPC's whole deck stands in.
+
+use inquire::Text;
+use std::path::Path;
+use triple_triad_solver::{
+    data::Data,
+    game::{self, Player},
+    prompt::Confirm,
+    settings::Settings,
+    solve,
+    vision::{self, BoardLayout, CardImages},
+};
+
+use crate::{adjust_rules, select_npc, PossiblePlacement};
+
+/// The layout file looked for next to the card images, for screenshots the default layout doesn't
+/// fit.
+const LAYOUT_FILE: &str = "layout.json";
+
+pub fn read_screenshot(data: &Data, settings: &mut Settings) {
+    let card_images_dir = Text::new("Where are the card images (named by card ID, like 12.png)?")
+        .with_initial_value(&settings.card_images_dir)
+        .prompt()
+        .unwrap();
+    if card_images_dir != settings.card_images_dir {
+        settings.card_images_dir = card_images_dir.clone();
+        settings.save().unwrap();
+    }
+
+    let card_images_dir = Path::new(&card_images_dir);
+    let card_images = match CardImages::load_dir(card_images_dir) {
+        Ok(card_images) => card_images,
+        Err(e) => {
+            println!("Could not load the card images: {}", e);
+            return;
+        }
+    };
+    let layout_path = card_images_dir.join(LAYOUT_FILE);
+    let layout = if layout_path.exists() {
+        match BoardLayout::load(&layout_path) {
+            Ok(layout) => layout,
+            Err(e) => {
+                println!("Could not load the layout: {}", e);
+                return;
+            }
+        }
+    } else {
+        BoardLayout::default()
+    };
+
+    let screenshot = Text::new("Which screenshot?").prompt().unwrap();
+    let mut position = match vision::read_position(Path::new(&screenshot), &layout, &card_images) {
+        Ok(position) => position,
+        Err(e) => {
+            println!("Could not read the screenshot: {}", e);
+            return;
+        }
+    };
+
+    let npc_name = select_npc(data);
+    let npc = &data.npcs_by_name[npc_name];
+    let rules = adjust_rules(&npc.rules, npc.uses_regional_rules);
+    position.rules = rules
+        .active_rule_names()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if rules.ascension || rules.decension {
+        // Counting the cards of each suit on the board is usually right, but the game's numbers
+        // are what count
+        position.modifiers = loop {
+            let text = Text::new("What modifier does each suit show?")
+                .with_help_message("Like P+2,S+1; leave empty to count the cards on the board")
+                .prompt()
+                .unwrap();
+            if text.trim().is_empty() {
+                break vec![];
+            }
+            match solve::parse_modifiers(text.trim()) {
+                Ok(modifiers) => break modifiers,
+                Err(e) => println!("{}", e),
+            }
+        };
+    }
+
+    if position.opponent_hand.is_empty() {
+        // The NPC might hold any of its cards that aren't already on the board
+        let mut candidates = npc
+            .fixed
+            .iter()
+            .chain(&npc.variable)
+            .map(|id| id.get())
+            .collect::<Vec<_>>();
+        for placed in position.board.iter().flatten() {
+            if let Some(idx) = candidates.iter().position(|&id| id == placed.card) {
+                candidates.remove(idx);
+            }
+        }
+        // Owners change as cards flip, so count how many the NPC played from how many you did
+        let cards_on_board = position.board.iter().flatten().count();
+        let npc_cards_played = cards_on_board.saturating_sub(5 - position.hand.len().min(5));
+        position.opponent_hand = candidates;
+        position.opponent_hand_size =
+            Some((npc.fixed.len() + npc.draws).saturating_sub(npc_cards_played));
+    }
+
+    println!("Read the position as: {}", position);
+    let game = match solve::game_for_position(data, &position) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("The screenshot doesn't make a valid position: {}", e);
+            return;
+        }
+    };
+    print!("{}", game.display_with_hand_panel(Player::Red, data));
+    if !Confirm::new("Does this look right?")
+        .with_default(true)
+        .with_help_message("If not, copy the position above, fix it, and use the clipboard watch")
+        .prompt()
+        .unwrap()
+    {
+        return;
+    }
+
+    println!("Finding optimal move...");
+    match solve::solve(data, &position, &settings.search_config()) {
+        Ok(recommendation) => println!(
+            "Recommended move: Play your {} card in the {} ({}). (Score: {})",
+            data.card_display_name(recommendation.card),
+            PossiblePlacement(recommendation.position),
+            game::cell_code(recommendation.position),
+            recommendation.score
+        ),
+        Err(e) => println!("Could not solve the position: {}", e),
+    }
+}
@@ -1,8 +1,245 @@
-use rand::{seq::SliceRandom, Rng};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use rayon::prelude::*;
-use std::{cmp::Ordering, fmt::Debug, time::Instant};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::{Ordering, Reverse},
+    fmt::{Debug, Display, Formatter},
+    io::{self, Write},
+    sync::{
+        atomic::{self, AtomicBool, AtomicUsize},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-const MONTE_CARLO_ITERATIONS: usize = 100_000;
+mod opponent;
+mod transposition;
+
+use opponent::FULL_DEPTH;
+pub use opponent::{GreedyFlips, Opponent, OpponentModel, PerfectPlay, RandomPlay};
+use transposition::Bound;
+pub use transposition::{TranspositionTable, DEFAULT_ENTRIES};
+
+// Positions this close to the end of the search are quicker to search again than to look up in the
+// transposition table
+const MIN_TABLE_DEPTH: usize = 2;
+
+// The most moves a player can have for their moves' exact results to be worked out too, which is
+// about from the fifth move on; before that, proving them takes as long as the search did
+const PROOF_MAX_MOVES: usize = 16;
+
+// Entries in the table the proof searches use, which can't share the main search's since they value
+// positions differently
+const PROOF_TABLE_ENTRIES: usize = 1 << 16;
+
+// How many simulations to run between checks of the time budget
+const MONTE_CARLO_BATCH_SIZE: usize = 1_000;
+
+// How many rounds the tiebreak's playout budget is split into, at most; after each, the moves that
+// are clearly worse are dropped
+const TIEBREAK_ROUNDS: usize = 10;
+
+#[derive(Clone, Debug)]
+pub struct SearchConfig {
+    /// Number of random games simulated for each tied move, if they all needed the full count.
+    pub monte_carlo_iterations: usize,
+
+    /// How many random games the tiebreak simulates in all, shared out between the tied moves as it
+    /// goes: moves that are clearly worse stop getting any, so the close ones get more. `None`
+    /// allows `monte_carlo_iterations` for each tied move.
+    pub playout_budget: Option<usize>,
+
+    /// How sure the tiebreak has to be that a move is worse than another before it stops simulating
+    /// games after it, from 0.5 to 1. At 1, none are dropped and every move gets an even share.
+    pub tiebreak_confidence: f64,
+
+    /// If set, the Monte Carlo tiebreak for each move stops early once this much time has passed.
+    pub time_budget: Option<Duration>,
+
+    /// How much a tie counts compared to a win in the Monte Carlo win ratio.
+    pub tie_value: f64,
+
+    /// Print progress and timings while searching. Needs the clock, like `time_budget`, so it has
+    /// to be off in WebAssembly.
+    pub verbose: bool,
+
+    /// Seeds the Monte Carlo simulations, so that a run can be repeated exactly, on any number of
+    /// threads. Each tied move's simulations get a seed of their own, made from this one and the
+    /// move's place among the tied moves, so it doesn't matter which thread plays them.
+    pub seed: Option<u64>,
+
+    /// Searches only one of each group of first moves that play out the same because the position
+    /// is symmetric, like on an empty board where every card in play has matching sides. It only
+    /// ever skips exact copies of other moves, but checking for them costs a little every search.
+    pub symmetry_pruning: bool,
+
+    /// Has the alpha-beta search try first the moves that caused cutoffs elsewhere in the search
+    /// (killer moves and the history heuristic), so more of the rest is pruned. The scores are the
+    /// same either way; it's only turned off to measure how much it saves.
+    pub move_ordering: bool,
+
+    /// Remembers what positions the alpha-beta search reached were worth, so they aren't searched
+    /// again, within one search or on later turns. Clones of the config share the table, so it
+    /// has to be kept to one match (or at least one set of card data); see
+    /// [`TranspositionTable`]. `None` searches without one.
+    pub transposition_table: Option<Arc<TranspositionTable>>,
+
+    /// How the opponent is expected to pick its moves, in the search and the Monte Carlo playouts.
+    pub opponent: Opponent,
+
+    /// What the search counts positions as worth; see [`EvalWeights`]. A transposition table
+    /// remembers values worked out with one set of weights, so it mustn't be shared between
+    /// configs with different ones.
+    pub eval_weights: EvalWeights,
+
+    /// Lets another thread stop the search early, which then settles for the best move found so
+    /// far.
+    pub cancel: Option<CancelToken>,
+
+    /// Told how far along [`get_best_move_for_player`] is as it goes, to show progress while it
+    /// runs. With `verbose` set, the progress is also printed.
+    pub progress: Option<ProgressReporter>,
+}
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            monte_carlo_iterations: 100_000,
+            playout_budget: None,
+            tiebreak_confidence: 0.99,
+            time_budget: None,
+            tie_value: 0.3,
+            verbose: true,
+            seed: None,
+            symmetry_pruning: true,
+            move_ordering: true,
+            transposition_table: Some(Arc::default()),
+            opponent: Opponent::Perfect,
+            eval_weights: EvalWeights::default(),
+            cancel: None,
+            progress: None,
+        }
+    }
+}
+
+/// What the search counts positions as worth, for games that take them; see
+/// [`SearchableGame::set_eval_weights`]. A win is always worth 100 and a loss -100, and a match
+/// that isn't over yet is worth 1 for each card a player is ahead by, with the rest measured
+/// against those. The defaults are hand-picked; the `tune` command looks for better ones.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct EvalWeights {
+    /// A match that ends in a tie. Against an opponent that plays its best, anything between a
+    /// loss and a win picks the same moves, but averaged over the replies of an opponent model it
+    /// decides how much a chance of winning is worth risking a tie for.
+    pub tie: f64,
+    /// Each card a finished match is won or lost by, on top of the win or loss, so bigger wins are
+    /// preferred and smaller losses settled for.
+    pub margin: f64,
+    /// Each point of side value a player's cards on the board turn toward empty cells, where they
+    /// can still be attacked, in a match that isn't over yet. Strong sides there are hard to take.
+    pub sides: f64,
+}
+impl Default for EvalWeights {
+    fn default() -> Self {
+        EvalWeights {
+            tie: -30.0,
+            margin: 0.0,
+            sides: 0.0,
+        }
+    }
+}
+impl Display for EvalWeights {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tie {}, margin {}, sides {}",
+            self.tie, self.margin, self.sides
+        )
+    }
+}
+
+/// Stops a search running on another thread. Clones share the same flag, so keep one and put the
+/// other in the search's [`SearchConfig`].
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed)
+    }
+}
+
+/// How far along [`get_best_move_for_player`] is.
+#[derive(Clone, Debug)]
+pub enum SearchProgress {
+    /// `done` of the `total` first moves have been searched.
+    Search { done: usize, total: usize },
+    /// How much of the Monte Carlo playout budget for breaking a tie between `moves` moves has
+    /// been used, from 0 to 1, with `remaining` of them still in the running. With a time budget,
+    /// or once only one move is left, the tiebreak may stop before the budget is used up.
+    Tiebreak {
+        moves: usize,
+        remaining: usize,
+        fraction: f64,
+    },
+}
+impl Display for SearchProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchProgress::Search { done, total } => {
+                write!(f, "Searched {}/{} first moves", done, total)
+            }
+            SearchProgress::Tiebreak {
+                moves,
+                remaining,
+                fraction,
+            } => write!(
+                f,
+                "Breaking a tie between {} moves: {:.0}% of the playouts done, {} still in the running",
+                moves,
+                fraction * 100.0,
+                remaining
+            ),
+        }
+    }
+}
+
+/// Called with each [`SearchProgress`] update. Updates can come from several threads at once.
+#[derive(Clone)]
+pub struct ProgressReporter(Arc<dyn Fn(&SearchProgress) + Send + Sync>);
+impl ProgressReporter {
+    pub fn new(report: impl Fn(&SearchProgress) + Send + Sync + 'static) -> Self {
+        ProgressReporter(Arc::new(report))
+    }
+}
+impl Debug for ProgressReporter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressReporter")
+    }
+}
+
+/// Passes `progress` on to the config's reporter, and prints it over the last update if verbose.
+fn report_progress(config: &SearchConfig, progress: &SearchProgress) {
+    if let Some(reporter) = &config.progress {
+        (reporter.0)(progress);
+    }
+    if config.verbose {
+        print!("\r{}", progress);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Whether the search with `cancel` has been stopped.
+fn cancelled(cancel: Option<&CancelToken>) -> bool {
+    cancel.is_some_and(CancelToken::is_cancelled)
+}
 
 pub trait GamePlayer: Copy + Clone + Debug + Send + Sync + Eq {
     fn other(&self) -> Self;
@@ -15,7 +252,7 @@ pub enum WinState<G: SearchableGame> {
 }
 
 pub trait SearchableGame: Send + Sized {
-    type Move: Debug + Sized + Send + Sync + Clone;
+    type Move: Debug + Sized + Send + Sync + Clone + PartialEq;
     type Player: GamePlayer;
 
     fn get_possible_moves(&self, player: Self::Player, buffer: &mut Vec<Self::Move>);
@@ -23,107 +260,831 @@ pub trait SearchableGame: Send + Sized {
     fn win_state(&self) -> WinState<Self>;
     fn truncate_history_and_clone(&self) -> Self;
 
+    /// The move standing in for every move that plays out the same as `mv` because the position is
+    /// symmetric, which has to be one of the possible moves. Games that don't look for symmetries
+    /// leave every move as it is.
+    fn canonical_move(&self, mv: &Self::Move) -> Self::Move {
+        mv.clone()
+    }
+
+    /// Under rules that leave part of a turn to chance, like Chaos picking the card to play, splits
+    /// `moves` (everything the player to move could play) into the moves each outcome of the roll
+    /// leaves them, with the chance of that outcome. The chances add up to 1. A turn with nothing
+    /// left to chance leaves `outcomes` empty, which is all games without chance ever do.
+    fn chance_outcomes(&self, _moves: &[Self::Move], outcomes: &mut Vec<(Vec<Self::Move>, f64)>) {
+        outcomes.clear();
+    }
+
+    /// Whether nothing about the rest of the match is hidden or left to chance: every card either
+    /// player could still play is known to be in their hand. Searched to the end against an
+    /// opponent that plays its best, such a position's scores are proven, so the Monte Carlo
+    /// tiebreak has nothing to add. Games that don't say are assumed not to be.
+    fn is_perfect_information(&self) -> bool {
+        false
+    }
+
+    /// Numbers `mv` for the history heuristic, which remembers how often each move caused a cutoff
+    /// so it can be tried early wherever it's possible. The same move in different positions should
+    /// get the same number, and the numbers should be small, since they index a table. Games that
+    /// leave this out only get killer moves.
+    fn history_key(&self, _mv: &Self::Move) -> Option<usize> {
+        None
+    }
+
+    /// Identifies the current position with `to_move` to play, for the transposition table.
+    /// Positions that play out differently (including under different rules) need different keys,
+    /// but how the position was reached shouldn't matter. Games that leave this out aren't looked
+    /// up in the table.
+    fn position_key(&self, _to_move: Self::Player) -> Option<u64> {
+        None
+    }
+
+    /// Has [`SearchableGame::evaluate_current_position_for`] value positions by `weights` from now
+    /// on, in this game and the copies made of it. Games with a fixed evaluation ignore them.
+    fn set_eval_weights(&mut self, _weights: &EvalWeights) {}
+
+    /// How far ahead `player` is on material alone, whatever the evaluation weighs besides, for
+    /// opponent models that count what a move takes. Games that leave this out are counted by
+    /// their evaluation.
+    fn material_lead(&self, player: Self::Player) -> f64 {
+        self.evaluate_current_position_for(player)
+    }
+
     fn apply_move(&mut self, mv: &Self::Move);
     fn undo_last_moves(&mut self, n: usize);
 }
 
-pub fn get_best_move_for_player<G: SearchableGame>(
-    game: &G,
-    player: G::Player,
-) -> (Option<G::Move>, (f64, Option<f64>)) {
+/// The copy of `game` a search works on, without its history and valuing positions the way
+/// `config` says.
+fn search_copy<G: SearchableGame>(game: &G, config: &SearchConfig) -> G {
     let mut game = game.truncate_history_and_clone();
-    let alphabeta_start = Instant::now();
-    let (best_moves, score) = alpha_beta(&mut game, 10, f64::NEG_INFINITY, f64::INFINITY, player);
-    println!(
-        "Found {} moves with best score {} (search duration: {:?}).",
-        best_moves.len(),
-        score,
-        Instant::now() - alphabeta_start
-    );
+    game.set_eval_weights(&config.eval_weights);
+    game
+}
 
-    struct MoveSelection<G: SearchableGame> {
-        mv: Option<G::Move>,
-        win_ratio: f64,
-    }
-    fn no_move_selection<G: SearchableGame>() -> MoveSelection<G> {
-        MoveSelection {
-            mv: None,
-            win_ratio: f64::NEG_INFINITY,
-        }
-    }
-    fn combine_move_selection<G: SearchableGame>(
-        sel1: MoveSelection<G>,
-        sel2: MoveSelection<G>,
-    ) -> MoveSelection<G> {
-        if sel1.mv.is_none() {
-            sel2
-        } else if sel2.mv.is_none() {
-            sel1
-        } else if sel1.win_ratio > sel2.win_ratio {
-            sel1
-        } else {
-            sel2
+/// Whether the search's scores for `game` are proven, so moves tied for the best score are equally
+/// good and there's no point breaking the tie with Monte Carlo playouts.
+fn scores_are_proven<G: SearchableGame>(game: &G, config: &SearchConfig) -> bool {
+    config.opponent.model::<G>().plays_best()
+        && game.is_perfect_information()
+        && !cancelled(config.cancel.as_ref())
+}
+
+/// Narrows `moves` down to what one randomly rolled outcome of the turn's chance leaves, if part of
+/// the turn is left to chance, for the playouts.
+fn roll_chance<G: SearchableGame>(
+    game: &G,
+    moves: &mut Vec<G::Move>,
+    outcomes: &mut Vec<(Vec<G::Move>, f64)>,
+    rng: &mut impl Rng,
+) {
+    game.chance_outcomes(moves, outcomes);
+    let mut roll = rng.gen::<f64>();
+    // The last outcome also takes whatever rounding leaves past the end
+    let last = outcomes.len().saturating_sub(1);
+    for (idx, (outcome_moves, chance)) in outcomes.drain(..).enumerate() {
+        if roll < chance || idx == last {
+            *moves = outcome_moves;
+            break;
         }
+        roll -= chance;
+    }
+}
+
+/// Finds `player`'s best move, returning it with its score and, if several moves were tied for the
+/// best score, how the Monte Carlo playouts that picked between them turned out. Ties aren't broken
+/// when every card is known, since the scores are then proven; see
+/// [`SearchableGame::is_perfect_information`].
+pub fn get_best_move_for_player<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    config: &SearchConfig,
+) -> (Option<G::Move>, (f64, Option<OutcomeRates>)) {
+    let mut game = search_copy(game, config);
+    if let Some(table) = &config.transposition_table {
+        table.new_search();
+    }
+    // The clock isn't available in WebAssembly, so it's only read when something needs it
+    let alphabeta_start = config.verbose.then(Instant::now);
+    let mut first_moves = Vec::with_capacity(100);
+    game.get_possible_moves(player, &mut first_moves);
+    if config.symmetry_pruning {
+        first_moves.retain(|mv| game.canonical_move(mv) == *mv);
+    }
+    // Stopped before a single move was searched, the first one is as good a guess as any
+    let fallback = first_moves.first().cloned();
+    let total = first_moves.len();
+    let mut done = 0;
+    report_progress(config, &SearchProgress::Search { done, total });
+    let (mut best_moves, mut score) =
+        search_moves(&mut game, first_moves, player, config, 0, || {
+            done += 1;
+            report_progress(config, &SearchProgress::Search { done, total });
+        });
+    if let Some(mv) = fallback.filter(|_| best_moves.is_empty()) {
+        game.apply_move(&mv);
+        score = game.evaluate_current_position_for(player);
+        game.undo_last_moves(1);
+        best_moves.push(mv);
+    }
+    if let Some(alphabeta_start) = alphabeta_start {
+        // Ends the progress line
+        println!();
+        println!(
+            "Found {} moves with best score {} (search duration: {:?}).",
+            best_moves.len(),
+            score,
+            alphabeta_start.elapsed()
+        );
     }
 
     match best_moves.len() {
         0 => (None, (score, None)),
         1 => (Some(best_moves[0].clone()), (score, None)),
+        len if scores_are_proven(&game, config) => {
+            if config.verbose {
+                println!(
+                    "Every card is known, so the {} tied moves are equally good; skipping the Monte Carlo tiebreak.",
+                    len
+                );
+            }
+            (Some(best_moves[0].clone()), (score, None))
+        }
         len => {
-            println!(
-                "Entering Monte Carlo simulation to tiebreak {} possible moves...",
-                len
-            );
-            let monte_carlo_start = Instant::now();
-            let best_best_move = best_moves
-                .into_iter()
+            if config.verbose {
+                println!(
+                    "Entering Monte Carlo simulation to tiebreak {} possible moves...",
+                    len
+                );
+            }
+            let monte_carlo_start =
+                (config.verbose || config.time_budget.is_some()).then(Instant::now);
+            let deadline = monte_carlo_start
+                .zip(config.time_budget)
+                .map(|(start, budget)| start + budget);
+            let games = best_moves
+                .iter()
                 .map(|mv| {
                     let mut game = game.truncate_history_and_clone();
-                    game.apply_move(&mv);
-                    (mv, game)
-                })
-                .collect::<Vec<_>>()
-                .into_par_iter()
-                .map(move |(mv, game)| MoveSelection::<G> {
-                    mv: Some(mv),
-                    win_ratio: monte_carlo(game, player, MONTE_CARLO_ITERATIONS),
+                    game.apply_move(mv);
+                    game
                 })
-                .reduce(no_move_selection, combine_move_selection);
+                .collect();
+            let (best_move, rates) = best_moves
+                .into_iter()
+                .zip(tiebreak(games, player, config, deadline))
+                .filter(|(_, (_, in_running))| *in_running)
+                .map(|(mv, (rates, _))| (mv, rates))
+                .fold(
+                    None,
+                    |best: Option<(G::Move, OutcomeRates)>, (mv, rates)| match best {
+                        Some(best)
+                            if best.1.value(config.tie_value) >= rates.value(config.tie_value) =>
+                        {
+                            Some(best)
+                        }
+                        _ => Some((mv, rates)),
+                    },
+                )
+                .unwrap();
 
-            println!(
-                "Monte carlo finished (duration: {:?}).",
-                Instant::now() - monte_carlo_start
-            );
-            (best_best_move.mv, (score, Some(best_best_move.win_ratio)))
+            if let Some(monte_carlo_start) = monte_carlo_start.filter(|_| config.verbose) {
+                // Ends the progress line
+                println!();
+                println!(
+                    "Monte carlo finished (duration: {:?}).",
+                    monte_carlo_start.elapsed()
+                );
+            }
+            (Some(best_move), (score, Some(rates)))
         }
     }
 }
 
-// Evaluates the current game using a Monte-Carlo search (random moves), with "player" having just moved, and returns the fraction
-// of games won by "player".
-fn monte_carlo<G: SearchableGame>(mut game: G, player: G::Player, iterations: usize) -> f64 {
-    let mut wins = 0;
-    let mut ties = 0;
+/// What `mv` is worth to `player`, searched as fully as [`get_best_move_for_player`] searches each
+/// move, for comparing a move someone picked against the best one.
+pub fn score_move<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    mv: &G::Move,
+    config: &SearchConfig,
+) -> f64 {
+    let mut game = search_copy(game, config);
+    search_moves(&mut game, vec![mv.clone()], player, config, 0, || {}).1
+}
+
+/// Searches `depth` moves ahead without the Monte Carlo tiebreak, returning the moves tied for the
+/// best score and that score. Like [`get_best_move_for_player`], the first move is picked from all
+/// of `player`'s moves, so anything left to chance on this turn has to be settled in `game` first.
+/// Of `config`, only `move_ordering`, `transposition_table`, and `eval_weights` are used.
+pub fn search_to_depth<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    depth: usize,
+    config: &SearchConfig,
+) -> (Vec<G::Move>, f64) {
+    let mut game = search_copy(game, config);
+    if depth == 0 {
+        return (vec![], game.evaluate_current_position_for(player));
+    }
+    let mut possible_moves = Vec::with_capacity(10);
+    game.get_possible_moves(player, &mut possible_moves);
+    alpha_beta_over(
+        &mut game,
+        possible_moves,
+        depth,
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+        player,
+        None,
+        &mut MoveOrdering::new(config.move_ordering),
+        config.transposition_table.as_deref(),
+        || {},
+    )
+}
+
+/// The Monte Carlo win ratio the tiebreak uses, for `player` having just moved: how often they
+/// win (with ties counting as `config.tie_value` of a win) if both sides play randomly from here.
+pub fn monte_carlo_win_ratio<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    config: &SearchConfig,
+) -> f64 {
+    let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+    monte_carlo(search_copy(game, config), player, config, deadline).value(config.tie_value)
+}
+
+/// Scores every move `player` could make, best first, as `(move, score, outcomes)`. Like
+/// [`get_best_move_for_player`], the moves tied for the best score are told apart by Monte Carlo
+/// playouts unless their scores are proven, and get how the playouts turned out; the rest don't.
+/// Slower than finding just the best move, since every move has to be searched fully instead of
+/// being pruned once it's known to be worse.
+pub fn rank_moves<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    config: &SearchConfig,
+) -> Vec<(G::Move, f64, Option<OutcomeRates>)> {
+    let game = search_copy(game, config);
+    if let Some(table) = &config.transposition_table {
+        table.new_search();
+    }
+    let mut possible_moves = Vec::with_capacity(100);
+    game.get_possible_moves(player, &mut possible_moves);
+
+    // A move that the position's symmetry makes a copy of another takes its scores, kept as the
+    // index of the move it copies
+    let copies = possible_moves
+        .iter()
+        .map(|mv| {
+            config
+                .symmetry_pruning
+                .then(|| game.canonical_move(mv))
+                .filter(|canonical| canonical != mv)
+                .and_then(|canonical| possible_moves.iter().position(|other| *other == canonical))
+        })
+        .collect::<Vec<_>>();
+    // Every move is searched fully, so they're searched at once, sharing the transposition table
+    let values = possible_moves
+        .iter()
+        .zip(&copies)
+        .map(|(mv, copy_of)| {
+            copy_of
+                .is_none()
+                .then(|| (mv.clone(), game.truncate_history_and_clone()))
+        })
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|search| {
+            search
+                .map(|(mv, mut game)| search_moves(&mut game, vec![mv], player, config, 0, || {}).1)
+        })
+        .collect::<Vec<_>>();
+    let mut ranked: Vec<(G::Move, f64, Option<OutcomeRates>)> = possible_moves
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mv)| {
+            let value = values[idx]
+                .or_else(|| values[copies[idx]?])
+                .unwrap_or(f64::NEG_INFINITY);
+            (mv, value, None)
+        })
+        .collect();
+
+    let best_score = ranked
+        .iter()
+        .map(|(_, score, _)| *score)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let tied = ranked
+        .iter()
+        .filter(|(_, score, _)| *score == best_score)
+        .count();
+    // Whether each move was still in the running when the tiebreak ended, so the ones it dropped
+    // early come after the rest even if their fewer playouts happened to go better
+    let mut in_running = vec![true; ranked.len()];
+    if tied > 1 && !scores_are_proven(&game, config) {
+        let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+        let (indices, games): (Vec<_>, Vec<_>) = ranked
+            .iter()
+            .zip(&copies)
+            .enumerate()
+            .filter(|(_, ((_, score, _), copy_of))| *score == best_score && copy_of.is_none())
+            .map(|(idx, ((mv, _, _), _))| {
+                let mut game = game.truncate_history_and_clone();
+                game.apply_move(mv);
+                (idx, game)
+            })
+            .unzip();
+        for (idx, (rates, running)) in indices
+            .into_iter()
+            .zip(tiebreak(games, player, config, deadline))
+        {
+            ranked[idx].2 = Some(rates);
+            in_running[idx] = running;
+        }
+        for (idx, copy_of) in copies.iter().enumerate() {
+            if let Some(copy_of) = *copy_of {
+                ranked[idx].2 = ranked[copy_of].2;
+                in_running[idx] = in_running[copy_of];
+            }
+        }
+    }
+
+    let win_ratio = |rates: &Option<OutcomeRates>| rates.map(|rates| rates.value(config.tie_value));
+    let mut ranked = ranked.into_iter().zip(in_running).collect::<Vec<_>>();
+    ranked.sort_by(
+        |((_, score1, rates1), running1), ((_, score2, rates2), running2)| {
+            score2
+                .partial_cmp(score1)
+                .unwrap_or(Ordering::Equal)
+                .then(running2.cmp(running1))
+                .then(
+                    win_ratio(rates2)
+                        .partial_cmp(&win_ratio(rates1))
+                        .unwrap_or(Ordering::Equal),
+                )
+        },
+    );
+    ranked.into_iter().map(|(ranked, _)| ranked).collect()
+}
+
+/// One of the lines [`top_lines`] finds: a first move, what it's worth, and how the match is
+/// expected to go after it.
+#[derive(Clone, Debug)]
+pub struct ScoredLine<M> {
+    pub mv: M,
+    pub score: f64,
+    /// How the Monte Carlo playouts went, for the moves tied for the best score.
+    pub outcomes: Option<OutcomeRates>,
+    /// The [`expected_line`] after `mv`.
+    pub line: Vec<M>,
+    /// What `mv` is proven to lead to, once the match is far enough along; see [`prove_move`].
+    pub proven: Option<ProvenResult>,
+}
+
+/// What a move leads to with every card known and the opponent playing its best, and by how many
+/// cards, which the search's scores alone don't say.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvenResult {
+    Win(u32),
+    Tie,
+    Loss(u32),
+}
+impl Display for ProvenResult {
+    /// Like `a forced win by 2`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ProvenResult::Win(margin) => write!(f, "a forced win by {}", margin),
+            ProvenResult::Tie => write!(f, "a tie at best"),
+            ProvenResult::Loss(margin) => write!(f, "a loss by {} at best", margin),
+        }
+    }
+}
+
+/// Works out exactly what `mv` leads to for `player`, if the scores for the position are proven
+/// (see [`SearchableGame::is_perfect_information`]) and few enough moves are left that it's quick.
+/// It's another search, with every card a match is won by counted, so the best play is the one
+/// that wins by the most or loses by the least.
+pub fn prove_move<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    mv: &G::Move,
+    config: &SearchConfig,
+) -> Option<ProvenResult> {
+    let mut moves = Vec::with_capacity(100);
+    game.get_possible_moves(player, &mut moves);
+    if moves.len() > PROOF_MAX_MOVES || !scores_are_proven(game, config) {
+        return None;
+    }
+    let config = SearchConfig {
+        verbose: false,
+        progress: None,
+        transposition_table: Some(Arc::new(TranspositionTable::new(PROOF_TABLE_ENTRIES))),
+        eval_weights: EvalWeights {
+            margin: 1.0,
+            ..config.eval_weights
+        },
+        ..config.clone()
+    };
+    let value = score_move(game, player, mv, &config);
+    if cancelled(config.cancel.as_ref()) {
+        return None;
+    }
+    // A win by one card is worth 101, and a tie anything between the closest loss and win
+    Some(if value > 100.0 {
+        ProvenResult::Win((value - 100.0).round() as u32)
+    } else if value < -100.0 {
+        ProvenResult::Loss((-100.0 - value).round() as u32)
+    } else {
+        ProvenResult::Tie
+    })
+}
+
+/// The `count` best first moves for `player`, best first, each with its score and the line expected
+/// after it, like chess engines' MultiPV, for seeing what the alternatives to the best move cost.
+/// Moves that the position's symmetry makes copies of another are left out, so every line is
+/// different. Like [`rank_moves`], every move has to be searched fully.
+pub fn top_lines<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    config: &SearchConfig,
+    count: usize,
+) -> Vec<ScoredLine<G::Move>> {
+    let ranked = rank_moves(game, player, config);
+    ranked
+        .into_iter()
+        .filter(|(mv, _, _)| !config.symmetry_pruning || game.canonical_move(mv) == *mv)
+        .take(count)
+        .map(|(mv, score, outcomes)| {
+            let line = if cancelled(config.cancel.as_ref()) {
+                vec![]
+            } else {
+                expected_line(game, player, &mv, config)
+            };
+            ScoredLine {
+                proven: prove_move(game, player, &mv, config),
+                mv,
+                score,
+                outcomes,
+                line,
+            }
+        })
+        .collect()
+}
+
+/// The line the search expects after `player` plays `mv`: the opponent's likeliest reply, then
+/// `player`'s best answer to it, as far as the match goes. Against an opponent that plays its best
+/// move, that's the reply the search finds for it. Otherwise the opponent model leaves several
+/// replies as likely as each other, and the one expected is the worst of them for `player`, since
+/// that's the one to be ready for.
+pub fn expected_line<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    mv: &G::Move,
+    config: &SearchConfig,
+) -> Vec<G::Move> {
+    let mut game = search_copy(game, config);
+    let model = config.opponent.model::<G>();
+    let opponent = player.other();
+    game.apply_move(mv);
+
+    let mut replies = Vec::with_capacity(100);
+    game.get_possible_moves(opponent, &mut replies);
+    let reply = if model.plays_best() {
+        search_moves(&mut game, replies, opponent, config, 1, || {})
+            .0
+            .into_iter()
+            .next()
+    } else {
+        model.narrow_moves(&mut game, opponent, &mut replies);
+        replies
+            .into_iter()
+            .map(|reply| {
+                game.apply_move(&reply);
+                let mut answers = Vec::with_capacity(100);
+                game.get_possible_moves(player, &mut answers);
+                let (_, value) = search_moves(&mut game, answers, player, config, 2, || {});
+                game.undo_last_moves(1);
+                (reply, value)
+            })
+            .min_by(|(_, value1), (_, value2)| {
+                value1.partial_cmp(value2).unwrap_or(Ordering::Equal)
+            })
+            .map(|(reply, _)| reply)
+    };
+    let reply = match reply {
+        Some(reply) => reply,
+        None => return vec![],
+    };
+
+    game.apply_move(&reply);
+    let mut answers = Vec::with_capacity(100);
+    game.get_possible_moves(player, &mut answers);
+    let answer = search_moves(&mut game, answers, player, config, 2, || {})
+        .0
+        .into_iter()
+        .next();
+    std::iter::once(reply).chain(answer).collect()
+}
+
+/// A move in [`top_branches`], with what it's worth to the player the search was for and the
+/// branches that follow it.
+#[derive(Clone, Debug)]
+pub struct Branch<M> {
+    pub mv: M,
+    pub score: f64,
+    pub replies: Vec<Branch<M>>,
+}
 
-    let mut rng = rand::thread_rng();
+/// The `width` best moves for `player`, each followed by the `width` replies the opponent is
+/// likeliest to make (the worst for `player` first), and so on for `plies` moves, for seeing what
+/// the search expected. Every score is for `player`. Replies are picked the way
+/// [`expected_line`] picks them: the ones `config.opponent` leaves the opponent, ranked by `player`'s
+/// best answer to each.
+pub fn top_branches<G: SearchableGame>(
+    game: &G,
+    player: G::Player,
+    config: &SearchConfig,
+    width: usize,
+    plies: usize,
+) -> Vec<Branch<G::Move>> {
+    let mut game = search_copy(game, config);
+    branches_for(&mut game, player, player, config, width, plies, 0)
+}
 
-    for _ in 0..iterations {
-        match simulate_game_once(&mut game, player, &mut rng) {
-            SimulationResult::PlayerWin => wins += 1,
-            SimulationResult::Tie => ties += 1,
-            SimulationResult::OpponentWin => {}
+fn branches_for<G: SearchableGame>(
+    game: &mut G,
+    player: G::Player,
+    to_move: G::Player,
+    config: &SearchConfig,
+    width: usize,
+    plies: usize,
+    played: usize,
+) -> Vec<Branch<G::Move>> {
+    if plies == 0 || cancelled(config.cancel.as_ref()) {
+        return vec![];
+    }
+    let mut moves = Vec::with_capacity(100);
+    game.get_possible_moves(to_move, &mut moves);
+    let model = config.opponent.model::<G>();
+    if to_move != player && !model.plays_best() {
+        model.narrow_moves(game, to_move, &mut moves);
+    }
+
+    let mut branches = moves
+        .into_iter()
+        .map(|mv| {
+            let score = if to_move == player {
+                search_moves(game, vec![mv.clone()], player, config, played, || {}).1
+            } else {
+                game.apply_move(&mv);
+                let mut answers = Vec::with_capacity(100);
+                game.get_possible_moves(player, &mut answers);
+                let score = search_moves(game, answers, player, config, played + 1, || {}).1;
+                game.undo_last_moves(1);
+                score
+            };
+            Branch {
+                mv,
+                score,
+                replies: vec![],
+            }
+        })
+        .collect::<Vec<_>>();
+    // The best for whoever's moving first
+    branches.sort_by(|branch1, branch2| {
+        let order = branch2
+            .score
+            .partial_cmp(&branch1.score)
+            .unwrap_or(Ordering::Equal);
+        if to_move == player {
+            order
+        } else {
+            order.reverse()
         }
+    });
+    branches.truncate(width);
+
+    for branch in &mut branches {
+        game.apply_move(&branch.mv);
+        branch.replies = branches_for(
+            game,
+            player,
+            to_move.other(),
+            config,
+            width,
+            plies - 1,
+            played + 1,
+        );
+        game.undo_last_moves(1);
     }
+    branches
+}
+
+// Evaluates the current game using a Monte-Carlo search (random moves for "player", and the opponent model's for the other),
+// with "player" having just moved, and returns how often "player" won, tied, and lost. Stops early if the deadline passes
+// or the search is cancelled.
+fn monte_carlo<G: SearchableGame>(
+    game: G,
+    player: G::Player,
+    config: &SearchConfig,
+    deadline: Option<Instant>,
+) -> OutcomeRates {
+    let mut playouts = Playouts::new(game, config, 0);
+    playouts.run(
+        config.monte_carlo_iterations,
+        player,
+        config,
+        deadline,
+        |_| {},
+    );
+    playouts.rates()
+}
 
-    // Ties count as 30% of a win
-    // println!(
-    //     "Monte Carlo simulation result: {} wins, {} ties, {} losses; ratio: {}",
-    //     wins,
-    //     ties,
-    //     iterations - wins - ties,
-    //     ((wins as f64) + (ties as f64 * 0.3)) / (iterations as f64)
-    // );
-    ((wins as f64) + (ties as f64 * 0.3)) / (iterations as f64)
+// The seed for the simulations numbered "task" of those run from "root", so that each parallel task gets the same seed
+// whichever thread runs it and however many threads there are. Mixed like SplitMix64, so nearby tasks get unrelated seeds.
+fn task_seed(root: u64, task: usize) -> u64 {
+    let mut x = root
+        ^ (task as u64)
+            .wrapping_add(1)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+// Whether the Monte Carlo simulations have to stop, because the deadline passed or the search was cancelled
+fn out_of_time(config: &SearchConfig, deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline) || cancelled(config.cancel.as_ref())
+}
+
+/// The Monte Carlo simulations run so far from one position, which can be added to a batch at a
+/// time.
+struct Playouts<G: SearchableGame> {
+    game: G,
+    rng: StdRng,
+    wins: usize,
+    ties: usize,
+    played: usize,
+}
+impl<G: SearchableGame> Playouts<G> {
+    // "task" tells apart the simulations run from the same config, which each get their own seed
+    fn new(game: G, config: &SearchConfig, task: usize) -> Self {
+        Playouts {
+            game,
+            rng: config.seed.map_or_else(StdRng::from_entropy, |seed| {
+                StdRng::seed_from_u64(task_seed(seed, task))
+            }),
+            wins: 0,
+            ties: 0,
+            played: 0,
+        }
+    }
+
+    // Simulates up to "count" more games, with "player" having just moved, telling "on_batch" how many were played after
+    // each batch of them. Stops early if the deadline passes or the search is cancelled.
+    fn run(
+        &mut self,
+        count: usize,
+        player: G::Player,
+        config: &SearchConfig,
+        deadline: Option<Instant>,
+        on_batch: impl Fn(usize),
+    ) {
+        let model = config.opponent.model::<G>();
+        let mut iterations = 0;
+        while iterations < count {
+            let batch = MONTE_CARLO_BATCH_SIZE.min(count - iterations);
+            for _ in 0..batch {
+                match simulate_game_once(&mut self.game, player, &mut self.rng, model) {
+                    SimulationResult::PlayerWin => self.wins += 1,
+                    SimulationResult::Tie => self.ties += 1,
+                    SimulationResult::OpponentWin => {}
+                }
+            }
+            iterations += batch;
+            self.played += batch;
+            on_batch(batch);
+
+            if out_of_time(config, deadline) {
+                break;
+            }
+        }
+    }
+
+    fn rates(&self) -> OutcomeRates {
+        if self.played == 0 {
+            return OutcomeRates::default();
+        }
+        let played = self.played as f64;
+        OutcomeRates {
+            win: self.wins as f64 / played,
+            tie: self.ties as f64 / played,
+            loss: (self.played - self.wins - self.ties) as f64 / played,
+        }
+    }
+
+    // The range the position's value (see OutcomeRates::value) is in with the confidence "z" stands for: the value the
+    // games played so far give, "z" standard errors to either side
+    fn value_bounds(&self, tie_value: f64, z: f64) -> (f64, f64) {
+        let rates = self.rates();
+        let value = rates.value(tie_value);
+        if self.played == 0 || z.is_infinite() {
+            return (f64::NEG_INFINITY, f64::INFINITY);
+        }
+        let variance = (rates.win + rates.tie * tie_value * tie_value - value * value).max(0.0);
+        let error = z * (variance / self.played as f64).sqrt();
+        (value - error, value + error)
+    }
+}
+
+// How many standard deviations above the mean a normally distributed value is below with probability "confidence", from
+// Abramowitz and Stegun's approximation 26.2.23 (good to about 0.0005). Under 0.5 counts as 0.5, and 1 or over is infinite.
+fn confidence_z(confidence: f64) -> f64 {
+    if confidence >= 1.0 {
+        return f64::INFINITY;
+    }
+    let p = 1.0 - confidence.max(0.5);
+    let t = (-2.0 * p.ln()).sqrt();
+    let z = t
+        - (2.515517 + 0.802853 * t + 0.010328 * t * t)
+            / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t);
+    z.max(0.0)
+}
+
+// Breaks a tie between moves by Monte Carlo simulations from "games", the positions after each of them with "player"
+// having just moved. Instead of simulating the same number of games after every move, the playout budget is spent in
+// rounds, and after each round the moves whose value is (with the config's tiebreak confidence) below another's stop
+// getting any, so the moves that are close get more. Returns how each move's simulations turned out, and whether it was
+// still in the running at the end; the best of those is the pick.
+fn tiebreak<G: SearchableGame>(
+    games: Vec<G>,
+    player: G::Player,
+    config: &SearchConfig,
+    deadline: Option<Instant>,
+) -> Vec<(OutcomeRates, bool)> {
+    let moves = games.len();
+    let budget = config
+        .playout_budget
+        .unwrap_or_else(|| config.monte_carlo_iterations.saturating_mul(moves));
+    let z = confidence_z(config.tiebreak_confidence);
+    let round_size = (budget / moves.max(1) / TIEBREAK_ROUNDS).max(MONTE_CARLO_BATCH_SIZE);
+    let mut candidates = games
+        .into_iter()
+        .enumerate()
+        .map(|(task, game)| (Playouts::new(game, config, task), true))
+        .collect::<Vec<_>>();
+
+    let played = AtomicUsize::new(0);
+    let report = |remaining| {
+        let fraction = played.load(atomic::Ordering::Relaxed) as f64 / budget.max(1) as f64;
+        report_progress(
+            config,
+            &SearchProgress::Tiebreak {
+                moves,
+                remaining,
+                fraction: fraction.min(1.0),
+            },
+        );
+    };
+    loop {
+        let remaining = candidates
+            .iter()
+            .filter(|(_, in_running)| *in_running)
+            .count();
+        report(remaining);
+        let left = budget.saturating_sub(played.load(atomic::Ordering::Relaxed));
+        if remaining <= 1 || left == 0 || out_of_time(config, deadline) {
+            break;
+        }
+
+        // The last round splits whatever is left of the budget
+        let count = round_size.min(left.div_ceil(remaining));
+        candidates
+            .par_iter_mut()
+            .filter(|(_, in_running)| *in_running)
+            .for_each(|(playouts, _)| {
+                playouts.run(count, player, config, deadline, |batch| {
+                    played.fetch_add(batch, atomic::Ordering::Relaxed);
+                    report(remaining);
+                });
+            });
+
+        let best_lower = candidates
+            .iter()
+            .filter(|(_, in_running)| *in_running)
+            .map(|(playouts, _)| playouts.value_bounds(config.tie_value, z).0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        for (playouts, in_running) in &mut candidates {
+            if *in_running && playouts.value_bounds(config.tie_value, z).1 < best_lower {
+                *in_running = false;
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|(playouts, in_running)| (playouts.rates(), in_running))
+        .collect()
 }
 enum SimulationResult {
     PlayerWin,
@@ -134,11 +1095,13 @@ fn simulate_game_once<G: SearchableGame>(
     game: &mut G,
     player: G::Player,
     rng: &mut impl Rng,
+    model: &dyn OpponentModel<G>,
 ) -> SimulationResult {
     let mut moves_taken = 0;
     let mut current_player = player.other();
 
     let mut possible_moves = Vec::with_capacity(100);
+    let mut outcomes = Vec::new();
 
     let result = loop {
         match game.win_state() {
@@ -154,6 +1117,10 @@ fn simulate_game_once<G: SearchableGame>(
 
         possible_moves.clear();
         game.get_possible_moves(current_player, &mut possible_moves);
+        roll_chance(game, &mut possible_moves, &mut outcomes, rng);
+        if current_player != player {
+            model.narrow_moves(game, current_player, &mut possible_moves);
+        }
         let mv = possible_moves.choose(rng).unwrap();
         game.apply_move(mv);
 
@@ -165,22 +1132,573 @@ fn simulate_game_once<G: SearchableGame>(
     result
 }
 
+/// Who makes the first move in a simulated match. Before a match starts it isn't known, since
+/// either player can go first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstMove<P: GamePlayer> {
+    Known(P),
+    /// Either player, with even odds.
+    Random,
+}
+impl<P: GamePlayer> FirstMove<P> {
+    /// Each player that might move first, with the chance that they do. `player` is either of
+    /// them.
+    pub fn cases(self, player: P) -> Vec<(P, f64)> {
+        match self {
+            FirstMove::Known(first) => vec![(first, 1.0)],
+            FirstMove::Random => vec![(player, 0.5), (player.other(), 0.5)],
+        }
+    }
+}
+
+/// How often each result comes up, as fractions adding up to 1. With no simulations run, they're
+/// all 0.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct OutcomeRates {
+    pub win: f64,
+    pub tie: f64,
+    pub loss: f64,
+}
+impl OutcomeRates {
+    /// A single number to rank moves by, with a tie counting as `tie_value` of a win. Only for
+    /// comparing: it isn't a chance of anything.
+    pub fn value(&self, tie_value: f64) -> f64 {
+        self.win + self.tie * tie_value
+    }
+}
+impl Display for OutcomeRates {
+    /// Like `62% win, 8% tie, 30% loss`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0}% win, {:.0}% tie, {:.0}% loss",
+            self.win * 100.0,
+            self.tie * 100.0,
+            self.loss * 100.0
+        )
+    }
+}
+
+/// Estimates how often `player` wins from the current position if it always takes the move that
+/// looks best one move ahead while the opponent plays randomly, which is roughly how NPCs play.
+/// This is much cheaper than running the full search for every move. When who moves first isn't
+/// known, the simulations are split evenly between both cases. With a `seed`, the estimate is the
+/// same every time, on any number of threads.
+pub fn estimate_win_rate<G: SearchableGame + Sync>(
+    game: &G,
+    first: FirstMove<G::Player>,
+    player: G::Player,
+    iterations: usize,
+    seed: Option<u64>,
+) -> f64 {
+    estimate_outcomes(game, first, player, iterations, seed).win
+}
+
+/// Like [`estimate_win_rate`], but also estimates how often the match is tied or lost.
+pub fn estimate_outcomes<G: SearchableGame + Sync>(
+    game: &G,
+    first: FirstMove<G::Player>,
+    player: G::Player,
+    iterations: usize,
+    seed: Option<u64>,
+) -> OutcomeRates {
+    let seed = seed.unwrap_or_else(rand::random);
+    first
+        .cases(player)
+        .into_iter()
+        .enumerate()
+        .map(|(case, (first_player, chance))| {
+            let iterations = (iterations as f64 * chance).round() as usize;
+            let rates = estimate_outcomes_moving_first(
+                game,
+                first_player,
+                player,
+                iterations,
+                task_seed(seed, case),
+            );
+            OutcomeRates {
+                win: chance * rates.win,
+                tie: chance * rates.tie,
+                loss: chance * rates.loss,
+            }
+        })
+        .fold(OutcomeRates::default(), |total, rates| OutcomeRates {
+            win: total.win + rates.win,
+            tie: total.tie + rates.tie,
+            loss: total.loss + rates.loss,
+        })
+}
+
+/// Like [`estimate_outcomes`] for each of `mover`'s moves from the current position: how the
+/// match goes for `player` when `mover` makes the move and the simulated play carries on from
+/// there. Moves that play out the same as another because the position is symmetric are left out.
+/// Cheap enough to rank the opening moves before a match, where the full search would take a
+/// while.
+pub fn estimate_move_outcomes<G: SearchableGame + Sync>(
+    game: &G,
+    mover: G::Player,
+    player: G::Player,
+    iterations_per_move: usize,
+    seed: Option<u64>,
+) -> Vec<(G::Move, OutcomeRates)> {
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut moves = Vec::with_capacity(100);
+    game.get_possible_moves(mover, &mut moves);
+    moves.retain(|mv| game.canonical_move(mv) == *mv);
+    moves
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mv)| {
+            let mut after = game.truncate_history_and_clone();
+            after.apply_move(&mv);
+            let rates = estimate_outcomes_moving_first(
+                &after,
+                mover.other(),
+                player,
+                iterations_per_move,
+                task_seed(seed, idx),
+            );
+            (mv, rates)
+        })
+        .collect()
+}
+
+fn estimate_outcomes_moving_first<G: SearchableGame + Sync>(
+    game: &G,
+    first_player: G::Player,
+    player: G::Player,
+    iterations: usize,
+    seed: u64,
+) -> OutcomeRates {
+    if iterations == 0 {
+        return OutcomeRates::default();
+    }
+
+    // Split into batches with seeds of their own, rather than a random number generator per
+    // thread, so the result doesn't depend on which thread plays which games
+    let (wins, ties): (usize, usize) = (0..iterations.div_ceil(MONTE_CARLO_BATCH_SIZE))
+        .into_par_iter()
+        .map(|batch| {
+            let mut game = game.truncate_history_and_clone();
+            let mut rng = StdRng::seed_from_u64(task_seed(seed, batch));
+            let count = MONTE_CARLO_BATCH_SIZE.min(iterations - batch * MONTE_CARLO_BATCH_SIZE);
+            (0..count).fold((0, 0), |(wins, ties), _| {
+                match simulate_greedy_game_once(&mut game, first_player, player, &mut rng) {
+                    SimulationResult::PlayerWin => (wins + 1, ties),
+                    SimulationResult::Tie => (wins, ties + 1),
+                    SimulationResult::OpponentWin => (wins, ties),
+                }
+            })
+        })
+        .reduce(
+            || (0, 0),
+            |(a_wins, a_ties), (b_wins, b_ties)| (a_wins + b_wins, a_ties + b_ties),
+        );
+    let iterations = iterations as f64;
+    OutcomeRates {
+        win: wins as f64 / iterations,
+        tie: ties as f64 / iterations,
+        loss: (iterations - wins as f64 - ties as f64) / iterations,
+    }
+}
+
+fn simulate_greedy_game_once<G: SearchableGame>(
+    game: &mut G,
+    first_player: G::Player,
+    player: G::Player,
+    rng: &mut impl Rng,
+) -> SimulationResult {
+    let mut moves_taken = 0;
+    let mut current_player = first_player;
+
+    let mut possible_moves = Vec::with_capacity(100);
+    let mut best_moves = Vec::with_capacity(100);
+    let mut outcomes = Vec::new();
+
+    let result = loop {
+        match game.win_state() {
+            WinState::NotFinished => {}
+            WinState::Tie => break SimulationResult::Tie,
+            WinState::Winner(winner) if winner == player => {
+                break SimulationResult::PlayerWin;
+            }
+            WinState::Winner(_) => {
+                break SimulationResult::OpponentWin;
+            }
+        }
+
+        possible_moves.clear();
+        game.get_possible_moves(current_player, &mut possible_moves);
+        roll_chance(game, &mut possible_moves, &mut outcomes, rng);
+        let mv = if current_player == player {
+            let mut best_value = f64::NEG_INFINITY;
+            best_moves.clear();
+            for mv in &possible_moves {
+                game.apply_move(mv);
+                let value = game.evaluate_current_position_for(player);
+                game.undo_last_moves(1);
+
+                if value > best_value {
+                    best_value = value;
+                    best_moves.clear();
+                }
+                if value >= best_value {
+                    best_moves.push(mv.clone());
+                }
+            }
+            best_moves.choose(rng).unwrap().clone()
+        } else {
+            possible_moves.choose(rng).unwrap().clone()
+        };
+        game.apply_move(&mv);
+
+        moves_taken += 1;
+        current_player = current_player.other();
+    };
+
+    game.undo_last_moves(moves_taken);
+    result
+}
+
+/// Searches each of `moves` for `player`, with the opponent's replies picked the way
+/// `config.opponent` expects, returning the moves tied for the best score and that score. `played`
+/// is how many moves into a line the search is, which it looks that much less far past, so it
+/// doesn't see further than the search at the start of the line did. `on_searched` is called as
+/// each of `moves` is finished.
+fn search_moves<G: SearchableGame>(
+    game: &mut G,
+    moves: Vec<G::Move>,
+    player: G::Player,
+    config: &SearchConfig,
+    played: usize,
+    on_searched: impl FnMut(),
+) -> (Vec<G::Move>, f64) {
+    let model = config.opponent.model::<G>();
+    if model.plays_best() {
+        alpha_beta_over(
+            game,
+            moves,
+            FULL_DEPTH.saturating_sub(played).max(1),
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            player,
+            config.cancel.as_ref(),
+            &mut MoveOrdering::new(config.move_ordering),
+            config.transposition_table.as_deref(),
+            on_searched,
+        )
+    } else {
+        let depth = model.search_depth().saturating_sub(played).max(1);
+        expectimax_over(
+            game,
+            moves,
+            depth,
+            player,
+            model,
+            config.cancel.as_ref(),
+            on_searched,
+        )
+    }
+}
+
+/// Like [`alpha_beta_over`], but with the opponent's replies picked by `model` instead of searched:
+/// a position with the opponent to move is worth the average over the moves the model leaves it.
+/// That means nothing can be pruned. Scores are always for `player`.
+fn expectimax_over<G: SearchableGame>(
+    game: &mut G,
+    possible_moves: Vec<G::Move>,
+    depth: usize,
+    player: G::Player,
+    model: &dyn OpponentModel<G>,
+    cancel: Option<&CancelToken>,
+    mut on_searched: impl FnMut(),
+) -> (Vec<G::Move>, f64) {
+    if depth == 0 || possible_moves.is_empty() {
+        return (vec![], game.evaluate_current_position_for(player));
+    }
+
+    let mut best_value = f64::NEG_INFINITY;
+    let mut best_moves = Vec::with_capacity(100);
+
+    for possible_move in possible_moves {
+        game.apply_move(&possible_move);
+        let move_value = expected_reply_value(game, depth - 1, player, model, cancel);
+        game.undo_last_moves(1);
+        if cancelled(cancel) {
+            break;
+        }
+        on_searched();
+
+        match move_value.partial_cmp(&best_value) {
+            Some(Ordering::Greater) => {
+                best_value = move_value;
+                best_moves.clear();
+                best_moves.push(possible_move);
+            }
+            Some(Ordering::Equal) => {
+                best_moves.push(possible_move);
+            }
+            _ => {}
+        }
+    }
+
+    (best_moves, best_value)
+}
+
+/// What the current position, with `player`'s opponent to move, is worth to `player` on average
+/// over the replies `model` leaves the opponent. When part of the opponent's turn is left to
+/// chance, that's averaged over each outcome, weighted by its chance.
+fn expected_reply_value<G: SearchableGame>(
+    game: &mut G,
+    depth: usize,
+    player: G::Player,
+    model: &dyn OpponentModel<G>,
+    cancel: Option<&CancelToken>,
+) -> f64 {
+    let opponent = player.other();
+    let mut replies = Vec::with_capacity(10);
+    game.get_possible_moves(opponent, &mut replies);
+    if depth == 0 || replies.is_empty() {
+        return game.evaluate_current_position_for(player);
+    }
+
+    let mut outcomes = Vec::new();
+    game.chance_outcomes(&replies, &mut outcomes);
+    if outcomes.is_empty() {
+        return average_reply_value(game, replies, depth, player, model, cancel);
+    }
+    outcomes
+        .into_iter()
+        .map(|(replies, chance)| {
+            chance * average_reply_value(game, replies, depth, player, model, cancel)
+        })
+        .sum()
+}
+
+/// The average over the replies `model` picks from `replies` of what each is worth to `player`.
+fn average_reply_value<G: SearchableGame>(
+    game: &mut G,
+    mut replies: Vec<G::Move>,
+    depth: usize,
+    player: G::Player,
+    model: &dyn OpponentModel<G>,
+    cancel: Option<&CancelToken>,
+) -> f64 {
+    model.narrow_moves(game, player.other(), &mut replies);
+
+    let mut total = 0f64;
+    for reply in &replies {
+        game.apply_move(reply);
+        let mut possible_moves = Vec::with_capacity(10);
+        game.get_possible_moves(player, &mut possible_moves);
+        let mut outcomes = Vec::new();
+        game.chance_outcomes(&possible_moves, &mut outcomes);
+        total += if outcomes.is_empty() {
+            expectimax_over(
+                game,
+                possible_moves,
+                depth - 1,
+                player,
+                model,
+                cancel,
+                || {},
+            )
+            .1
+        } else {
+            outcomes
+                .into_iter()
+                .map(|(moves, chance)| {
+                    chance * expectimax_over(game, moves, depth - 1, player, model, cancel, || {}).1
+                })
+                .sum()
+        };
+        game.undo_last_moves(1);
+        if cancelled(cancel) {
+            break;
+        }
+    }
+    total / replies.len() as f64
+}
+
+/// What one alpha-beta search has learned about which moves to try first. A move that causes a
+/// cutoff prunes every sibling after it, so the earlier it's tried the better. Two kinds of moves
+/// tend to: the last two that caused a cutoff at the same depth (killer moves), since the positions
+/// beside each other in the tree are alike, and the moves that have caused the most cutoffs
+/// anywhere, weighted by how much of the tree each cutoff pruned (the history heuristic). The first
+/// move of the search is never reordered, so the moves tied for the best are found in the order
+/// they were given.
+struct MoveOrdering<M> {
+    enabled: bool,
+    /// Indexed by the depth left to search.
+    killers: Vec<[Option<M>; 2]>,
+    /// Indexed by [`SearchableGame::history_key`].
+    history: Vec<u64>,
+}
+impl<M: Clone + PartialEq> MoveOrdering<M> {
+    fn new(enabled: bool) -> Self {
+        MoveOrdering {
+            enabled,
+            killers: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Sorts `moves` so the killer moves for `depth` come first, then the rest by their history,
+    /// keeping the order they were in otherwise.
+    fn order<G: SearchableGame<Move = M>>(&self, game: &G, depth: usize, moves: &mut [M]) {
+        if !self.enabled {
+            return;
+        }
+        let killers = self.killers.get(depth);
+        moves.sort_by_key(|mv| {
+            let killer_rank = killers
+                .and_then(|killers| {
+                    killers
+                        .iter()
+                        .position(|killer| killer.as_ref() == Some(mv))
+                })
+                .unwrap_or(2);
+            let history = game
+                .history_key(mv)
+                .and_then(|key| self.history.get(key))
+                .copied()
+                .unwrap_or(0);
+            (killer_rank, Reverse(history))
+        });
+    }
+
+    fn record_cutoff<G: SearchableGame<Move = M>>(&mut self, game: &G, depth: usize, mv: &M) {
+        if !self.enabled {
+            return;
+        }
+        if self.killers.len() <= depth {
+            self.killers.resize(depth + 1, [None, None]);
+        }
+        let killers = &mut self.killers[depth];
+        if killers[0].as_ref() != Some(mv) {
+            killers[1] = killers[0].replace(mv.clone());
+        }
+        if let Some(key) = game.history_key(mv) {
+            if self.history.len() <= key {
+                self.history.resize(key + 1, 0);
+            }
+            self.history[key] += (depth * depth) as u64;
+        }
+    }
+}
+
 // Finds the best move for `player` given the current game state, with a maximum search depth.
-// This is basically negamax search (TT is a zero sum game) with alpha-beta pruning.
+// This is basically negamax search (TT is a zero sum game) with alpha-beta pruning. When part of
+// the turn is left to chance, the position is a chance node instead: it's worth the average over
+// the outcomes, weighted by their chances, of the best move each outcome leaves. Each outcome is
+// searched with the full window, since the window only bounds the average, and there are no best
+// moves to return. The moves are tried in the order `ordering` suggests, and positions already in
+// `table` to at least `depth` aren't searched again.
+#[allow(clippy::too_many_arguments)]
 fn alpha_beta<G: SearchableGame>(
     game: &mut G,
     depth: usize,
-    mut alpha: f64,
+    alpha: f64,
     beta: f64,
     player: G::Player,
+    cancel: Option<&CancelToken>,
+    ordering: &mut MoveOrdering<G::Move>,
+    table: Option<&TranspositionTable>,
 ) -> (Vec<G::Move>, f64) {
     if depth == 0 {
         return (vec![], game.evaluate_current_position_for(player));
     }
 
+    let table = table
+        .filter(|_| depth >= MIN_TABLE_DEPTH)
+        .and_then(|table| Some((table, game.position_key(player)?)));
+    if let Some((table, key)) = table {
+        if let Some(probe) = table.probe(key).filter(|probe| probe.depth >= depth) {
+            let usable = match probe.bound {
+                Bound::Exact => true,
+                Bound::Lower => probe.value >= beta,
+                Bound::Upper => probe.value <= alpha,
+            };
+            if usable {
+                return (vec![], probe.value);
+            }
+        }
+    }
+
     let mut possible_moves = Vec::with_capacity(10);
     game.get_possible_moves(player, &mut possible_moves);
+    let mut outcomes = Vec::new();
+    game.chance_outcomes(&possible_moves, &mut outcomes);
+    let (best_moves, value, bound) = if !outcomes.is_empty() {
+        let value = outcomes
+            .into_iter()
+            .map(|(mut moves, chance)| {
+                ordering.order(game, depth, &mut moves);
+                let (_, value) = alpha_beta_over(
+                    game,
+                    moves,
+                    depth,
+                    f64::NEG_INFINITY,
+                    f64::INFINITY,
+                    player,
+                    cancel,
+                    ordering,
+                    table.map(|(table, _)| table),
+                    || {},
+                );
+                chance * value
+            })
+            .sum();
+        (vec![], value, Bound::Exact)
+    } else {
+        ordering.order(game, depth, &mut possible_moves);
+        let (best_moves, value) = alpha_beta_over(
+            game,
+            possible_moves,
+            depth,
+            alpha,
+            beta,
+            player,
+            cancel,
+            ordering,
+            table.map(|(table, _)| table),
+            || {},
+        );
+        let bound = if value >= beta {
+            Bound::Lower
+        } else if value <= alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        (best_moves, value, bound)
+    };
 
+    // A cancelled search's value is only as good as the moves it got to
+    if let Some((table, key)) = table.filter(|_| !cancelled(cancel)) {
+        table.store(key, value, depth, bound);
+    }
+    (best_moves, value)
+}
+
+/// Like [`alpha_beta`], but only trying `possible_moves` for the first move. Once `cancel` is
+/// cancelled, the move being searched is dropped and the moves searched before it are returned,
+/// so a stopped search still gives the best of the moves it finished. `on_searched` is called as
+/// each move is finished. Cutoffs are recorded in `ordering`, but `possible_moves` are tried in the
+/// order they're given.
+#[allow(clippy::too_many_arguments)]
+fn alpha_beta_over<G: SearchableGame>(
+    game: &mut G,
+    possible_moves: Vec<G::Move>,
+    depth: usize,
+    mut alpha: f64,
+    beta: f64,
+    player: G::Player,
+    cancel: Option<&CancelToken>,
+    ordering: &mut MoveOrdering<G::Move>,
+    table: Option<&TranspositionTable>,
+    mut on_searched: impl FnMut(),
+) -> (Vec<G::Move>, f64) {
     if possible_moves.is_empty() {
         return (vec![], game.evaluate_current_position_for(player));
     }
@@ -190,9 +1708,22 @@ fn alpha_beta<G: SearchableGame>(
 
     for possible_move in possible_moves {
         game.apply_move(&possible_move);
-        let (_, mut move_value) = alpha_beta(game, depth - 1, -beta, -alpha, player.other());
+        let (_, mut move_value) = alpha_beta(
+            game,
+            depth - 1,
+            -beta,
+            -alpha,
+            player.other(),
+            cancel,
+            ordering,
+            table,
+        );
         move_value *= -1f64;
         game.undo_last_moves(1);
+        if cancelled(cancel) {
+            break;
+        }
+        on_searched();
 
         match move_value.partial_cmp(&best_value) {
             Some(Ordering::Greater) => {
@@ -208,6 +1739,10 @@ fn alpha_beta<G: SearchableGame>(
 
         alpha = alpha.max(best_value);
         if alpha >= beta {
+            // Only a move that beat everything before it can have raised alpha past beta
+            if let Some(mv) = best_moves.last() {
+                ordering.record_cutoff(game, depth, mv);
+            }
             break;
         }
     }
@@ -0,0 +1,116 @@
+//! How the opponent is expected to pick its moves. Searching the opponent's moves like the
+//! player's assumes it always plays its best one, but NPCs follow simple rules of thumb instead,
+//! and against them that passes up moves that win easily in practice.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+use super::SearchableGame;
+
+/// How many moves ahead the search looks when it can search to the end of the match.
+pub(super) const FULL_DEPTH: usize = 10;
+
+/// How many moves ahead the search looks against an opponent that always flips as many cards as it
+/// can. Its few replies keep the tree small, but without pruning the whole match is still too slow.
+const GREEDY_SEARCH_DEPTH: usize = 6;
+
+/// How many moves ahead the search looks against an opponent that might play any move. Every one
+/// of them has to be searched, so it can't look far.
+const RANDOM_SEARCH_DEPTH: usize = 4;
+
+pub trait OpponentModel<G: SearchableGame>: Sync {
+    /// Whether the opponent plays its best move, which the search finds by searching its moves
+    /// like the player's. Otherwise the search averages over the moves left by
+    /// [`OpponentModel::narrow_moves`].
+    fn plays_best(&self) -> bool {
+        false
+    }
+
+    /// Narrows `moves`, the moves `opponent` could play in `game`, down to the ones it might
+    /// actually play, each as likely as the others. The Monte Carlo playouts pick one of them at
+    /// random.
+    fn narrow_moves(&self, game: &mut G, opponent: G::Player, moves: &mut Vec<G::Move>);
+
+    /// How many moves ahead the search looks against this opponent.
+    fn search_depth(&self) -> usize {
+        FULL_DEPTH
+    }
+}
+
+/// Always plays its best move. The Monte Carlo playouts can't afford to search for it, so there it
+/// plays randomly, like the player.
+pub struct PerfectPlay;
+impl<G: SearchableGame> OpponentModel<G> for PerfectPlay {
+    fn plays_best(&self) -> bool {
+        true
+    }
+
+    fn narrow_moves(&self, _game: &mut G, _opponent: G::Player, _moves: &mut Vec<G::Move>) {}
+}
+
+/// Plays whichever move flips the most cards right away, like most NPCs do.
+pub struct GreedyFlips;
+impl<G: SearchableGame> OpponentModel<G> for GreedyFlips {
+    fn narrow_moves(&self, game: &mut G, opponent: G::Player, moves: &mut Vec<G::Move>) {
+        // Every card flipped moves the score by two, so the moves raising it most flip the most
+        let values = moves
+            .iter()
+            .map(|mv| {
+                game.apply_move(mv);
+                let value = game.material_lead(opponent);
+                game.undo_last_moves(1);
+                value
+            })
+            .collect::<Vec<_>>();
+        let best_value = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut values = values.into_iter();
+        moves.retain(|_| values.next() == Some(best_value));
+    }
+
+    fn search_depth(&self) -> usize {
+        GREEDY_SEARCH_DEPTH
+    }
+}
+
+/// Plays any of its moves, each as likely as the others.
+pub struct RandomPlay;
+impl<G: SearchableGame> OpponentModel<G> for RandomPlay {
+    fn narrow_moves(&self, _game: &mut G, _opponent: G::Player, _moves: &mut Vec<G::Move>) {}
+
+    fn search_depth(&self) -> usize {
+        RANDOM_SEARCH_DEPTH
+    }
+}
+
+/// One of the opponent models, for choosing between them in the settings.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Opponent {
+    #[default]
+    Perfect,
+    GreedyFlips,
+    Random,
+}
+impl Opponent {
+    pub const ALL: [Opponent; 3] = [Opponent::Perfect, Opponent::GreedyFlips, Opponent::Random];
+
+    pub fn model<'a, G: SearchableGame>(self) -> &'a dyn OpponentModel<G> {
+        match self {
+            Opponent::Perfect => &PerfectPlay,
+            Opponent::GreedyFlips => &GreedyFlips,
+            Opponent::Random => &RandomPlay,
+        }
+    }
+}
+impl Display for Opponent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Opponent::Perfect => "Plays perfectly",
+                Opponent::GreedyFlips => "Flips as many cards as it can",
+                Opponent::Random => "Plays randomly",
+            }
+        )
+    }
+}
@@ -0,0 +1,158 @@
+//! Remembering what positions were worth, so a position the alpha-beta search reaches again (by
+//! playing the same cards in another order, or on a later turn of the match) isn't searched again.
+//! The table is a fixed number of entries that any number of threads read and write at once without
+//! locks: each entry is written as separate atomic words along with a check word made from all of
+//! them, so an entry torn by two threads writing it at once just doesn't match its key.
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicU8, Ordering},
+    OnceLock,
+};
+
+/// How many entries a table holds unless it's given a size: 24 MB, which holds most of what
+/// searching a whole match from the first move reaches.
+pub const DEFAULT_ENTRIES: usize = 1 << 20;
+
+/// How a stored value relates to what the position is worth, since alpha-beta only finds the exact
+/// value of positions whose value is inside the window they were searched with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(super) enum Bound {
+    Exact,
+    /// The position is worth at least the value; it was enough for a cutoff.
+    Lower,
+    /// The position is worth at most the value; none of its moves reached alpha.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(super) struct Probe {
+    pub value: f64,
+    /// How many moves past the position were searched.
+    pub depth: usize,
+    pub bound: Bound,
+}
+
+#[derive(Default)]
+struct Entry {
+    /// The position's key, xor the other two words.
+    check: AtomicU64,
+    value: AtomicU64,
+    /// The depth, the bound, and the generation it was written in; see [`pack_meta`].
+    meta: AtomicU64,
+}
+
+fn pack_meta(depth: usize, bound: Bound, generation: u8) -> u64 {
+    let bound = match bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    depth.min(u8::MAX as usize) as u64 | bound << 8 | (generation as u64) << 16
+}
+
+fn unpack_meta(meta: u64) -> (usize, Bound, u8) {
+    let bound = match (meta >> 8) & 0xff {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        _ => Bound::Upper,
+    };
+    ((meta & 0xff) as usize, bound, (meta >> 16) as u8)
+}
+
+/// A transposition table shared by every search that holds it, including rayon workers searching
+/// at the same time. Keys come from [`SearchableGame::position_key`], so one table must only be
+/// used for games with the same card data, like the turns of one match.
+///
+/// Entries from earlier searches are kept and used, since a position is worth the same on a later
+/// turn, but [`TranspositionTable::new_search`] ages them, so they give way to the current search's
+/// entries when two positions want the same slot.
+///
+/// [`SearchableGame::position_key`]: super::SearchableGame::position_key
+pub struct TranspositionTable {
+    size: usize,
+    /// Allocated on first use, since most searches are configured without ever searching.
+    entries: OnceLock<Box<[Entry]>>,
+    /// Never 0, so an entry that was never written doesn't look like one from this search.
+    generation: AtomicU8,
+}
+impl TranspositionTable {
+    /// A table of `size` entries (rounded up to a power of two), taking 24 bytes each.
+    pub fn new(size: usize) -> Self {
+        TranspositionTable {
+            size: size.max(1).next_power_of_two(),
+            entries: OnceLock::new(),
+            generation: AtomicU8::new(1),
+        }
+    }
+
+    /// Marks the start of a new search, like the next turn of a match, aging the entries written
+    /// so far.
+    pub fn new_search(&self) {
+        let generation = self.generation.load(Ordering::Relaxed);
+        self.generation
+            .store(generation.checked_add(1).unwrap_or(1), Ordering::Relaxed);
+    }
+
+    /// Forgets every entry, allocating the table if it hasn't been yet.
+    pub fn clear(&self) {
+        for entry in self.entries().iter() {
+            entry.meta.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn entries(&self) -> &[Entry] {
+        self.entries.get_or_init(|| {
+            std::iter::repeat_with(Entry::default)
+                .take(self.size)
+                .collect()
+        })
+    }
+
+    fn entry(&self, key: u64) -> &Entry {
+        &self.entries()[key as usize & (self.size - 1)]
+    }
+
+    pub(super) fn probe(&self, key: u64) -> Option<Probe> {
+        let entry = self.entry(key);
+        let check = entry.check.load(Ordering::Relaxed);
+        let value = entry.value.load(Ordering::Relaxed);
+        let meta = entry.meta.load(Ordering::Relaxed);
+        let (depth, bound, generation) = unpack_meta(meta);
+        if generation == 0 || check ^ value ^ meta != key {
+            return None;
+        }
+        Some(Probe {
+            value: f64::from_bits(value),
+            depth,
+            bound,
+        })
+    }
+
+    /// Stores what the position with `key` is worth, unless its slot holds a deeper search of
+    /// another position from the current search.
+    pub(super) fn store(&self, key: u64, value: f64, depth: usize, bound: Bound) {
+        let entry = self.entry(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+        let (old_depth, _, old_generation) = unpack_meta(entry.meta.load(Ordering::Relaxed));
+        if old_generation == generation && old_depth > depth {
+            return;
+        }
+        let value = value.to_bits();
+        let meta = pack_meta(depth, bound, generation);
+        entry.check.store(key ^ value ^ meta, Ordering::Relaxed);
+        entry.value.store(value, Ordering::Relaxed);
+        entry.meta.store(meta, Ordering::Relaxed);
+    }
+}
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new(DEFAULT_ENTRIES)
+    }
+}
+impl std::fmt::Debug for TranspositionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TranspositionTable")
+            .field("size", &self.size)
+            .finish()
+    }
+}
@@ -0,0 +1,238 @@
+// The `serve` subcommand: a small HTTP API over the loaded data and the solver, so overlays, bots,
+// and web pages can share one process instead of each loading the data themselves.
+//
+//   POST /solve  takes a position (see `solve::Position`) and returns every move, best first
+//   POST /board  takes a position the same way and returns its board drawn in the style given
+//                with `?style=` (ansi, plain, json, or html; plain by default)
+//   GET  /npcs   lists the NPCs with their rules, cards, and rewards
+//   GET  /cards  lists the cards with their values and details
+//   GET  /live   opens a WebSocket for following a match as it's played; see live.rs
+
+mod live;
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+use triple_triad_solver::{
+    data::{CardInfo, Data, NpcLocation},
+    game::{Direction, RenderStyle, Suit},
+    search::SearchConfig,
+    solve::{self, Recommendation},
+};
+use tungstenite::handshake::derive_accept_key;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct SolveBody {
+    moves: Vec<Recommendation>,
+}
+
+#[derive(Serialize)]
+struct CardEntry<'a> {
+    id: i32,
+    name: &'a str,
+    /// North, east, south, west.
+    values: [i32; 4],
+    suit: Option<Suit>,
+    info: Option<&'a CardInfo>,
+}
+
+#[derive(Serialize)]
+struct NpcEntry<'a> {
+    name: &'a str,
+    display_name: &'a str,
+    location: Option<&'a NpcLocation>,
+    rules: Vec<&'static str>,
+    uses_regional_rules: bool,
+    fee: i32,
+    fixed_cards: Vec<i32>,
+    variable_cards: Vec<i32>,
+    /// How many of the variable cards the NPC plays with.
+    variable_draws: usize,
+    reward_cards: &'a [i32],
+}
+
+/// Serves requests until the process is stopped. Each request is handled on its own thread, so a
+/// long search doesn't hold up the others.
+pub fn run(
+    address: &str,
+    data: &Data,
+    search_config: &SearchConfig,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let server = Server::http(address)?;
+    println!("Listening on http://{}", address);
+
+    std::thread::scope(|scope| {
+        for request in server.incoming_requests() {
+            scope.spawn(move || handle(request, data, search_config));
+        }
+    });
+    Ok(())
+}
+
+fn handle(mut request: Request, data: &Data, search_config: &SearchConfig) {
+    let path = request
+        .url()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if path == "/live" && *request.method() == Method::Get {
+        accept_live(request, data, search_config);
+        return;
+    }
+
+    let query = request
+        .url()
+        .split_once('?')
+        .map_or("", |(_, query)| query)
+        .to_string();
+    let mut content_type = "application/json";
+    let (status, body) = match (request.method(), path.as_str()) {
+        (Method::Post, "/solve") => {
+            let mut position_json = String::new();
+            match request.as_reader().read_to_string(&mut position_json) {
+                Ok(_) => solve_position(&position_json, data, search_config),
+                Err(e) => (400, error_body(e)),
+            }
+        }
+        (Method::Post, "/board") => {
+            let mut position_json = String::new();
+            match request.as_reader().read_to_string(&mut position_json) {
+                Ok(_) => match render_position(&position_json, &query, data) {
+                    Ok((style, board)) => {
+                        content_type = style.content_type();
+                        (200, board)
+                    }
+                    Err(error) => error,
+                },
+                Err(e) => (400, error_body(e)),
+            }
+        }
+        (Method::Get, "/npcs") => (200, to_json(&npc_entries(data))),
+        (Method::Get, "/cards") => (200, to_json(&card_entries(data))),
+        (_, "/solve") | (_, "/board") | (_, "/npcs") | (_, "/cards") | (_, "/live") => {
+            (405, error_body("method not allowed"))
+        }
+        _ => (404, error_body("not found")),
+    };
+
+    let content_type = Header::from_bytes("Content-Type", content_type).unwrap();
+    let response = Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(e) = request.respond(response) {
+        println!("Couldn't send response for {}: {}", path, e);
+    }
+}
+
+fn accept_live(request: Request, data: &Data, search_config: &SearchConfig) {
+    let key = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Sec-WebSocket-Key"))
+        .map(|header| derive_accept_key(header.value.as_bytes()));
+    let key = match key {
+        Some(key) => key,
+        None => {
+            let response = Response::from_string(error_body("expected a WebSocket handshake"))
+                .with_status_code(400);
+            let _ = request.respond(response);
+            return;
+        }
+    };
+
+    let response =
+        Response::empty(101).with_header(Header::from_bytes("Sec-WebSocket-Accept", key).unwrap());
+    let stream = request.upgrade("websocket", response);
+    live::run(stream, data, search_config);
+}
+
+fn solve_position(position_json: &str, data: &Data, search_config: &SearchConfig) -> (u16, String) {
+    let position = match serde_json::from_str::<solve::Position>(position_json) {
+        Ok(position) => position,
+        Err(e) => return (400, error_body(e)),
+    };
+    match solve::rank_moves(data, &position, search_config) {
+        Ok(moves) => (200, to_json(&SolveBody { moves })),
+        Err(e) => (422, error_body(e)),
+    }
+}
+
+/// Draws a position's board, in the style asked for in the query string. Errors come back as the
+/// status and body to respond with.
+fn render_position(
+    position_json: &str,
+    query: &str,
+    data: &Data,
+) -> Result<(RenderStyle, String), (u16, String)> {
+    let style = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("style="))
+        .map_or(Ok(RenderStyle::Plain), str::parse)
+        .map_err(|e| (400, error_body(e)))?;
+    let position =
+        serde_json::from_str::<solve::Position>(position_json).map_err(|e| (400, error_body(e)))?;
+    let game = solve::game_for_position(data, &position).map_err(|e| (422, error_body(e)))?;
+    let mut board = Vec::new();
+    game.render(style, &mut board)
+        .map_err(|e| (500, error_body(e)))?;
+    Ok((style, String::from_utf8_lossy(&board).into_owned()))
+}
+
+fn card_entries(data: &Data) -> Vec<CardEntry<'_>> {
+    // The table is in ID order already
+    data.cards
+        .iter()
+        .map(|(id, card)| CardEntry {
+            id: id.get(),
+            name: data.card_display_name(id.get()),
+            values: [
+                Direction::North,
+                Direction::East,
+                Direction::South,
+                Direction::West,
+            ]
+            .map(|direction| card.value(direction)),
+            // No suit stays null in the JSON
+            suit: Some(card.suit).filter(|&suit| suit != Suit::None),
+            info: data.card_info.get(&id.get()),
+        })
+        .collect()
+}
+
+fn npc_entries(data: &Data) -> Vec<NpcEntry<'_>> {
+    let mut entries = data
+        .npcs_by_name
+        .iter()
+        .map(|(name, npc)| NpcEntry {
+            name,
+            display_name: data.npc_display_name(name),
+            location: npc.location.as_ref(),
+            rules: npc.rules.active_rule_names(),
+            uses_regional_rules: npc.uses_regional_rules,
+            fee: npc.fee,
+            fixed_cards: npc.fixed.iter().map(|id| id.get()).collect(),
+            variable_cards: npc.variable.iter().map(|id| id.get()).collect(),
+            variable_draws: npc.draws,
+            reward_cards: &npc.reward_cards,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| entry.name);
+    entries
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn error_body(error: impl ToString) -> String {
+    to_json(&ErrorBody {
+        error: error.to_string(),
+    })
+}
@@ -0,0 +1,207 @@
+// Live assist over a WebSocket at `/live`: the client reports each change to the board as it
+// happens and gets back the updated recommendations, so it never has to resend the whole position.
+//
+// Client messages (JSON, tagged by "type"):
+//   {"type": "start", ...}   starts a match from a position, like `POST /solve` takes; `player`
+//                            says whose hand `hand` is, when that's not the player to move
+//   {"type": "place", "cell": 4, "card": 12, "owner": "Blue"}
+//                            a card from `owner`'s hand was played; flips are worked out here
+//   {"type": "set_owner", "cell": 3, "owner": "Red"}
+//                            corrects a card's owner, for flips the solver doesn't predict
+//   {"type": "undo"}         takes back the last card placed
+//
+// The server answers each one with either {"type": "update", ...} (the moves are only filled in
+// on the player's turn) or {"type": "error", "error": "..."}, which leaves the match unchanged.
+
+use serde::{Deserialize, Serialize};
+use tiny_http::ReadWrite;
+use triple_triad_solver::{
+    data::Data,
+    game::{CardId, Game, GameMove, Player},
+    search::{GamePlayer, SearchConfig, SearchableGame, WinState},
+    solve::{self, Position, Recommendation, SolveError},
+};
+use tungstenite::{protocol::Role, Message, WebSocket};
+
+#[derive(thiserror::Error, Debug)]
+enum LiveError {
+    #[error("invalid message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Solve(#[from] SolveError),
+
+    #[error("no match has been started")]
+    NotStarted,
+
+    #[error("there's no cell {0}")]
+    NoSuchCell(usize),
+
+    #[error("cell {0} already has a card")]
+    CellTaken(usize),
+
+    #[error("cell {0} is empty")]
+    CellEmpty(usize),
+
+    #[error("card {card} isn't in {player}'s hand")]
+    NotInHand { card: i32, player: Player },
+
+    #[error("no cards have been placed")]
+    NothingToUndo,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Start(Box<Position>),
+    Place {
+        cell: usize,
+        card: i32,
+        owner: Player,
+    },
+    SetOwner {
+        cell: usize,
+        owner: Player,
+    },
+    Undo,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Update {
+        to_move: Player,
+        /// Red, then blue.
+        scores: [usize; 2],
+        game_over: bool,
+        moves: Vec<Recommendation>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+struct Session {
+    game: Game,
+    player: Player,
+    to_move: Player,
+    /// The player's card IDs, in the order the game was given them.
+    hand: Vec<i32>,
+    moves_played: usize,
+}
+impl Session {
+    fn update(&self, search_config: &SearchConfig) -> Result<ServerMessage, LiveError> {
+        let game_over = !matches!(self.game.win_state(), WinState::NotFinished);
+        let moves = if !game_over && self.to_move == self.player {
+            solve::rank_game_moves(&self.game, self.player, &self.hand, search_config)?
+        } else {
+            vec![]
+        };
+
+        Ok(ServerMessage::Update {
+            to_move: self.to_move,
+            scores: self.game.scores(),
+            game_over,
+            moves,
+        })
+    }
+
+    fn place(&mut self, cell: usize, card: i32, owner: Player) -> Result<(), LiveError> {
+        if cell >= 9 {
+            return Err(LiveError::NoSuchCell(cell));
+        }
+        if self.game.board_cell(cell).is_some() {
+            return Err(LiveError::CellTaken(cell));
+        }
+        let card_idx = CardId::new(card)
+            .and_then(|id| self.game.find_in_hand(owner, id))
+            .ok_or(LiveError::NotInHand {
+                card,
+                player: owner,
+            })?;
+
+        self.game.apply_move(&GameMove {
+            player: owner,
+            card_idx,
+            placement: cell,
+        });
+        self.moves_played += 1;
+        self.to_move = owner.other();
+        Ok(())
+    }
+
+    fn set_owner(&mut self, cell: usize, owner: Player) -> Result<(), LiveError> {
+        if cell >= 9 {
+            return Err(LiveError::NoSuchCell(cell));
+        }
+        if !self.game.set_owner(cell, owner) {
+            return Err(LiveError::CellEmpty(cell));
+        }
+        Ok(())
+    }
+
+    fn undo(&mut self) -> Result<(), LiveError> {
+        if self.moves_played == 0 {
+            return Err(LiveError::NothingToUndo);
+        }
+        self.game.undo_last_moves(1);
+        self.moves_played -= 1;
+        self.to_move = self.to_move.other();
+        Ok(())
+    }
+}
+
+/// Runs one client's session until it disconnects.
+pub fn run(stream: Box<dyn ReadWrite + Send>, data: &Data, search_config: &SearchConfig) {
+    let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let mut session = None;
+
+    loop {
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => continue,
+        };
+
+        let reply = handle_message(&text, &mut session, data, search_config).unwrap_or_else(|e| {
+            ServerMessage::Error {
+                error: e.to_string(),
+            }
+        });
+        let reply = serde_json::to_string(&reply).unwrap();
+        if socket.send(Message::Text(reply)).is_err() {
+            break;
+        }
+    }
+}
+
+fn handle_message(
+    text: &str,
+    session: &mut Option<Session>,
+    data: &Data,
+    search_config: &SearchConfig,
+) -> Result<ServerMessage, LiveError> {
+    let session = match (serde_json::from_str(text)?, session) {
+        (ClientMessage::Start(position), session) => session.insert(Session {
+            game: solve::game_for_position(data, &position)?,
+            player: position.player(),
+            to_move: position.to_move,
+            hand: position.hand,
+            moves_played: 0,
+        }),
+        (_, None) => return Err(LiveError::NotStarted),
+        (ClientMessage::Place { cell, card, owner }, Some(session)) => {
+            session.place(cell, card, owner)?;
+            session
+        }
+        (ClientMessage::SetOwner { cell, owner }, Some(session)) => {
+            session.set_owner(cell, owner)?;
+            session
+        }
+        (ClientMessage::Undo, Some(session)) => {
+            session.undo()?;
+            session
+        }
+    };
+    session.update(search_config)
+}
@@ -0,0 +1,427 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{
+    data::Language,
+    dirs::DataDirs,
+    game::Player,
+    search::{EvalWeights, FirstMove, Opponent, SearchConfig},
+    store,
+};
+
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Could not read/write settings file")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Could not parse settings file")]
+    ParseError(#[from] toml::de::Error),
+
+    #[error("Could not serialize settings")]
+    Unserializable(#[from] toml::ser::Error),
+}
+
+/// How much a tie is worth compared to a win when breaking ties between equally good moves.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RiskPreference {
+    /// Prefer moves that are unlikely to lose, even if they win less often.
+    Cautious,
+    Balanced,
+    /// Only count outright wins.
+    Aggressive,
+}
+impl RiskPreference {
+    pub fn tie_value(&self) -> f64 {
+        match *self {
+            RiskPreference::Cautious => 0.5,
+            RiskPreference::Balanced => 0.3,
+            RiskPreference::Aggressive => 0.0,
+        }
+    }
+}
+impl Display for RiskPreference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirstPlayer {
+    Ask,
+    Me,
+    Npc,
+}
+impl FirstPlayer {
+    /// Who moves first against an NPC, with the player as Blue. When it's asked every match, it
+    /// isn't known ahead of the match.
+    pub fn first_move(&self) -> FirstMove<Player> {
+        match *self {
+            FirstPlayer::Ask => FirstMove::Random,
+            FirstPlayer::Me => FirstMove::Known(Player::Blue),
+            FirstPlayer::Npc => FirstMove::Known(Player::Red),
+        }
+    }
+}
+impl Display for FirstPlayer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                FirstPlayer::Ask => "Ask every match",
+                FirstPlayer::Me => "I go first",
+                FirstPlayer::Npc => "The NPC goes first",
+            }
+        )
+    }
+}
+
+/// Which characters and colors boards are drawn with.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalOutput {
+    /// Whatever the terminal looks like it supports.
+    Auto,
+    UnicodeAndColors,
+    /// Box drawing and stars, but no ANSI colors.
+    Unicode,
+    Ascii,
+}
+impl TerminalOutput {
+    pub fn has_colors(&self) -> bool {
+        *self == TerminalOutput::UnicodeAndColors
+    }
+}
+impl Display for TerminalOutput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                TerminalOutput::Auto => "Detect automatically",
+                TerminalOutput::UnicodeAndColors => "Unicode and colors",
+                TerminalOutput::Unicode => "Unicode without colors",
+                TerminalOutput::Ascii => "Plain ASCII (no colors or box drawing)",
+            }
+        )
+    }
+}
+
+/// How much the solver gives away on the player's turns.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintLevel {
+    /// Recommend the best move before the player picks theirs.
+    Full,
+    /// Say nothing unless the move the player picks is a blunder, and then only warn them.
+    WarnBlunders,
+    /// Grade the player's move once they've picked it, and show the best one then.
+    GradeAfter,
+}
+impl Display for HintLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                HintLevel::Full => "Recommend the best move",
+                HintLevel::WarnBlunders => "Only warn me before a blunder",
+                HintLevel::GradeAfter => "Grade my move after I pick it",
+            }
+        )
+    }
+}
+
+/// How the board is shown in the scrolling prompts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoardView {
+    Grid,
+    /// Each cell described in words, for screen readers.
+    Description,
+    Both,
+}
+impl BoardView {
+    pub fn shows_grid(&self) -> bool {
+        *self != BoardView::Description
+    }
+
+    pub fn shows_description(&self) -> bool {
+        *self != BoardView::Grid
+    }
+}
+impl Display for BoardView {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                BoardView::Grid => "Grid",
+                BoardView::Description => "Described in words",
+                BoardView::Both => "Grid and description",
+            }
+        )
+    }
+}
+
+/// What NPC matches pay out and how long they take, for estimating MGP earnings. The payouts
+/// aren't in the game data, so the player enters them; 0 means they haven't yet.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct MgpPayouts {
+    pub win: u32,
+    pub tie: u32,
+    pub loss: u32,
+    /// How long a match takes, including challenging the NPC again.
+    pub match_seconds: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Maximum time to spend on the Monte Carlo tiebreak for each move, in milliseconds. 0 means no limit.
+    pub search_time_budget_ms: u64,
+
+    /// Number of random games simulated for each tied move.
+    pub monte_carlo_iterations: usize,
+
+    /// Number of random games the tiebreak simulates in all, shared out between the tied moves so
+    /// the close ones get more. 0 means `monte_carlo_iterations` for each tied move.
+    pub playout_budget: usize,
+
+    /// How sure the tiebreak has to be that a tied move is worse before it stops simulating games
+    /// after it, from 0.5 to 1. 1 gives every tied move the same number of games.
+    pub tiebreak_confidence: f64,
+
+    pub color: bool,
+
+    /// Show row letters and column numbers around the board, and cell codes in empty cells.
+    pub board_labels: bool,
+
+    /// What the terminal can show. `Ascii` draws the board and decks without colors or box
+    /// drawing, marking card owners with letters instead, for limited terminals, screen readers, and
+    /// output piped to files.
+    pub terminal_output: TerminalOutput,
+
+    /// Whether boards are drawn, described in words after each move, or both.
+    pub board_view: BoardView,
+
+    /// Repository (`owner/name`), URL, or local directory the card and NPC sheets are loaded from.
+    /// Empty means use the built-in sources.
+    pub data_source_url: String,
+
+    /// Proxy URL to download data through. Empty means use the proxy environment variables, if set.
+    pub download_proxy: String,
+
+    /// PEM file of extra certificates to trust when downloading data, for networks that inspect
+    /// HTTPS traffic. Empty means only trust the system's certificates.
+    pub download_ca_certificate: String,
+
+    pub risk: RiskPreference,
+
+    pub first_player: FirstPlayer,
+
+    /// Language card and NPC names are shown and searched in, matching the game client.
+    pub language: Language,
+
+    /// Play matches in the full-screen view instead of the scrolling prompts. Only available in
+    /// builds with the `tui` feature.
+    pub full_screen: bool,
+
+    /// Take matches against NPCs from the game plugin instead of entering them by hand.
+    pub game_plugin: bool,
+
+    /// Directory of card images named by card ID, for recognizing cards in screenshots.
+    pub card_images_dir: String,
+
+    /// Directory to save a [`SearchDump`](crate::solve::SearchDump) to on each of the player's turns
+    /// in the scrolling prompts. Empty means off.
+    pub search_dumps_dir: String,
+
+    /// How much the solver tells the player on their turns, in the scrolling prompts and practice
+    /// matches.
+    pub hint_level: HintLevel,
+
+    /// How much worse than the best move a move has to score to count as a blunder. A win is worth
+    /// 100 and a loss -100, with a tie in between.
+    pub blunder_threshold: f64,
+
+    /// How many of the best moves to show on the player's turns in the scrolling prompts, each with
+    /// its score and the line expected after it. 1 shows only the recommended move.
+    pub lines_shown: usize,
+
+    /// Search in the background while waiting for the NPC's move in the scrolling prompts, so the
+    /// recommendation after it is ready sooner.
+    pub ponder: bool,
+
+    /// Show the board on the player's turns in the scrolling prompts with each empty cell shaded by
+    /// the score of the best move into it.
+    pub heatmap: bool,
+
+    /// Show the moves played so far under the board in the scrolling prompts, for checking the
+    /// match against the game's.
+    pub move_list: bool,
+
+    /// Whether the walkthrough on first launch has been shown (or skipped). Settings saved before
+    /// it existed come from someone who has already found their way around, so they count as
+    /// having seen it.
+    #[serde(default = "already_onboarded")]
+    pub onboarded: bool,
+
+    /// The payouts last entered for estimating MGP earnings.
+    pub mgp_payouts: MgpPayouts,
+
+    /// What the search counts positions as worth, as found by the `tune` command.
+    pub eval_weights: EvalWeights,
+
+    /// How each NPC is expected to play, by English name. NPCs that aren't listed are taken to play
+    /// perfectly.
+    pub opponent_models: BTreeMap<String, Opponent>,
+
+    #[serde(skip)]
+    settings_path: PathBuf,
+}
+
+fn already_onboarded() -> bool {
+    true
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            search_time_budget_ms: 0,
+            monte_carlo_iterations: 100_000,
+            playout_budget: 0,
+            tiebreak_confidence: 0.99,
+            color: true,
+            board_labels: true,
+            terminal_output: TerminalOutput::Auto,
+            board_view: BoardView::Grid,
+            data_source_url: String::new(),
+            download_proxy: String::new(),
+            download_ca_certificate: String::new(),
+            risk: RiskPreference::Balanced,
+            first_player: FirstPlayer::Ask,
+            language: Language::English,
+            full_screen: false,
+            game_plugin: false,
+            card_images_dir: String::new(),
+            search_dumps_dir: String::new(),
+            hint_level: HintLevel::Full,
+            blunder_threshold: 50.0,
+            lines_shown: 1,
+            ponder: true,
+            heatmap: false,
+            move_list: true,
+            onboarded: false,
+            mgp_payouts: MgpPayouts::default(),
+            eval_weights: EvalWeights::default(),
+            opponent_models: BTreeMap::new(),
+            settings_path: PathBuf::new(),
+        }
+    }
+}
+impl Settings {
+    pub fn new(data_dirs: &DataDirs) -> Result<Self, SettingsError> {
+        let settings_path = data_dirs.config_dir().join("settings.toml");
+
+        let saved = store::load(&settings_path, |text| {
+            toml::from_str::<Settings>(text).map_err(SettingsError::from)
+        })?;
+        if let Some(mut result) = saved {
+            result.settings_path = settings_path;
+            Ok(result)
+        } else {
+            std::fs::create_dir_all(settings_path.parent().unwrap())?;
+            let result = Settings {
+                settings_path,
+                ..Default::default()
+            };
+            result.save()?;
+            Ok(result)
+        }
+    }
+
+    pub fn search_config(&self) -> SearchConfig {
+        SearchConfig {
+            monte_carlo_iterations: self.monte_carlo_iterations,
+            playout_budget: (self.playout_budget != 0).then_some(self.playout_budget),
+            tiebreak_confidence: self.tiebreak_confidence,
+            time_budget: if self.search_time_budget_ms == 0 {
+                None
+            } else {
+                Some(Duration::from_millis(self.search_time_budget_ms))
+            },
+            tie_value: self.risk.tie_value(),
+            verbose: true,
+            seed: None,
+            symmetry_pruning: true,
+            move_ordering: true,
+            transposition_table: Some(Arc::default()),
+            opponent: Opponent::Perfect,
+            eval_weights: self.eval_weights,
+            cancel: None,
+            progress: None,
+        }
+    }
+
+    /// How `npc_name` is expected to play.
+    pub fn opponent_model(&self, npc_name: &str) -> Opponent {
+        self.opponent_models
+            .get(npc_name)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Where to save search dumps, if that's on.
+    pub fn search_dumps_dir(&self) -> Option<&Path> {
+        Some(Path::new(&self.search_dumps_dir)).filter(|_| !self.search_dumps_dir.is_empty())
+    }
+
+    /// The search settings for a match against `npc_name`, expecting it to play the way it was set
+    /// to.
+    pub fn search_config_against(&self, npc_name: &str) -> SearchConfig {
+        SearchConfig {
+            opponent: self.opponent_model(npc_name),
+            ..self.search_config()
+        }
+    }
+
+    /// Takes in settings exported from another computer (or profile). NPCs play the way they were set
+    /// to in `imported` unless they're already set here, and with `take_preferences`, the rest of
+    /// `imported` replaces these settings too, except for the ones that depend on this computer: the
+    /// data source and language, the terminal output, and paths and network settings.
+    pub fn merge(
+        &mut self,
+        imported: Settings,
+        take_preferences: bool,
+    ) -> Result<(), SettingsError> {
+        let mut opponent_models = imported.opponent_models.clone();
+        opponent_models.extend(std::mem::take(&mut self.opponent_models));
+
+        if take_preferences {
+            let current = std::mem::replace(self, imported);
+            self.data_source_url = current.data_source_url;
+            self.language = current.language;
+            self.terminal_output = current.terminal_output;
+            self.card_images_dir = current.card_images_dir;
+            self.search_dumps_dir = current.search_dumps_dir;
+            self.download_proxy = current.download_proxy;
+            self.download_ca_certificate = current.download_ca_certificate;
+            self.settings_path = current.settings_path;
+        }
+        self.opponent_models = opponent_models;
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<(), SettingsError> {
+        store::save(
+            &self.settings_path,
+            toml::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,579 @@
+//! Solves a single position described in JSON, for callers that don't drive a whole match, like
+//! the WebAssembly build.
+//!
+//! Positions can also be written on one line, in a notation modeled on chess's FEN:
+//!
+//! `1b,-,-/-,12r,-/-,-,- r 2,3,4 7,8,9,10,11:3 Plus,FallenAce`
+//!
+//! or, under Ascension with the modifiers set:
+//!
+//! `1b,-,-/-,12r,-/-,-,- r 2,3,4 7,8,9,10,11:3 Ascension P+2,S+1`
+//!
+//! The fields, separated by spaces, are:
+//! - the board, row by row from the top, with rows separated by `/` and cells by `,`. A cell is
+//!   either `-` when it's empty or the card's ID followed by its owner, `r` or `b`.
+//! - the player to move, `r` or `b`.
+//! - the card IDs in their hand, separated by `,`, or `-` when it's empty.
+//! - the card IDs the opponent might play, the same way, optionally followed by `:` and how many
+//!   cards they actually hold.
+//! - optionally, the rules, separated by `,` and written without spaces.
+//! - optionally, the Ascension or Descension modifier on each suit, separated by `,`, as the suit's
+//!   letter and its signed modifier, like `P+2` or `G-1`. Suits left out are counted from the
+//!   cards on the board, which isn't always right, so these are for setting them as the game
+//!   shows them.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{
+    data::Data,
+    game::{
+        self, CardId, Game, GameBuildError, GameBuilder, GameMove, HandCard, Player, Rules, Suit,
+    },
+    search::{self, GamePlayer, OutcomeRates, SearchConfig, SearchableGame},
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum SolveError {
+    #[error("invalid position JSON")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("unknown card ID {0}")]
+    UnknownCard(i32),
+
+    #[error("unknown rule {0}")]
+    UnknownRule(String),
+
+    #[error("a hand can have at most {max} cards, but {count} were given")]
+    TooManyCards { count: usize, max: usize },
+
+    #[error("a modifier of {modifier:+} on {suit:?} cards can't happen under these rules")]
+    InvalidModifier { suit: Suit, modifier: i32 },
+
+    #[error("the player to move has no legal moves")]
+    NoMoves,
+
+    #[error("it's the opponent's turn")]
+    OpponentToMove,
+
+    #[error(transparent)]
+    InvalidGame(#[from] GameBuildError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NotationError {
+    #[error("missing the {0}")]
+    MissingField(&'static str),
+
+    #[error("too many fields")]
+    TooManyFields,
+
+    #[error("the board needs 3 rows of 3 cells")]
+    BoardShape,
+
+    #[error("invalid {what} {text:?}")]
+    Invalid { what: &'static str, text: String },
+
+    #[error("unknown rule {0}")]
+    UnknownRule(String),
+}
+
+/// A card already on the board.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlacedCard {
+    pub card: i32,
+    pub owner: Player,
+}
+
+/// What Ascension or Descension currently adds to the values of a suit's cards.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct SuitModifier {
+    pub suit: Suit,
+    /// Positive under Ascension and negative under Descension, like the game shows it.
+    pub modifier: i32,
+}
+
+/// A position to solve. Cells are numbered 0 (top left) to 8 (bottom right), row by row.
+#[derive(Deserialize, Clone, Debug)]
+pub struct Position {
+    pub board: [Option<PlacedCard>; 9],
+
+    /// Card IDs in the player's hand.
+    pub hand: Vec<i32>,
+
+    /// Card IDs the opponent might still play. For NPCs this can be more cards than they hold.
+    pub opponent_hand: Vec<i32>,
+
+    /// How many cards the opponent actually holds, if fewer than `opponent_hand`.
+    #[serde(default)]
+    pub opponent_hand_size: Option<usize>,
+
+    pub to_move: Player,
+
+    /// Whose hand `hand` is. Defaults to the player to move; solving needs it to be their turn.
+    #[serde(default)]
+    pub player: Option<Player>,
+
+    /// Rule names, as in [`Rules::NAMES`].
+    #[serde(default)]
+    pub rules: Vec<String>,
+
+    /// Modifiers to use instead of counting the cards of each suit on the board.
+    #[serde(default)]
+    pub modifiers: Vec<SuitModifier>,
+}
+impl Position {
+    pub fn player(&self) -> Player {
+        self.player.unwrap_or(self.to_move)
+    }
+}
+impl FromStr for Position {
+    type Err = NotationError;
+
+    /// Parses the one-line notation described at the top of this module.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut fields = text.split_whitespace();
+        let mut field = |name| fields.next().ok_or(NotationError::MissingField(name));
+
+        let mut board: [Option<PlacedCard>; 9] = Default::default();
+        let rows = field("board")?.split('/').collect::<Vec<_>>();
+        let cells = rows
+            .iter()
+            .map(|row| row.split(',').collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        if cells.len() != 3 || cells.iter().any(|row| row.len() != 3) {
+            return Err(NotationError::BoardShape);
+        }
+        for (slot, cell) in board.iter_mut().zip(cells.into_iter().flatten()) {
+            if cell != "-" {
+                let placed = cell.chars().last().and_then(|last| {
+                    let owner = parse_player(last)?;
+                    let card = cell[..cell.len() - last.len_utf8()].parse().ok()?;
+                    Some(PlacedCard { card, owner })
+                });
+                *slot = Some(placed.ok_or_else(|| invalid("cell", cell))?);
+            }
+        }
+
+        let to_move = field("player to move")?;
+        let to_move = match to_move.chars().collect::<Vec<_>>().as_slice() {
+            [c] => parse_player(*c),
+            _ => None,
+        }
+        .ok_or_else(|| invalid("player", to_move))?;
+
+        let hand = parse_cards(field("hand")?)?;
+
+        let opponent_field = field("opponent's hand")?;
+        let (opponent_hand, opponent_hand_size) = match opponent_field.split_once(':') {
+            Some((cards, size)) => (
+                parse_cards(cards)?,
+                Some(size.parse().map_err(|_| invalid("hand size", size))?),
+            ),
+            None => (parse_cards(opponent_field)?, None),
+        };
+
+        let mut rules_field = fields.next();
+        let mut modifiers_field = fields.next();
+        // The rules can be left out while the modifiers are given, and only modifiers have signs
+        if modifiers_field.is_none() && rules_field.is_some_and(|field| field.contains(is_sign)) {
+            modifiers_field = rules_field.take();
+        }
+        let rules = match rules_field {
+            Some(rules) => rules
+                .split(',')
+                .map(|rule| {
+                    Rules::NAMES
+                        .iter()
+                        .find(|name| name.replace(' ', "").eq_ignore_ascii_case(rule))
+                        .map(|name| name.to_string())
+                        .ok_or_else(|| NotationError::UnknownRule(rule.to_string()))
+                })
+                .collect::<Result<_, _>>()?,
+            None => vec![],
+        };
+        let modifiers = modifiers_field.map_or(Ok(vec![]), parse_modifiers)?;
+        if fields.next().is_some() {
+            return Err(NotationError::TooManyFields);
+        }
+
+        Ok(Position {
+            board,
+            hand,
+            opponent_hand,
+            opponent_hand_size,
+            to_move,
+            player: None,
+            rules,
+            modifiers,
+        })
+    }
+}
+impl Display for Position {
+    /// Writes the position in the one-line notation described at the top of this module. The
+    /// hand is always the player to move's, so `player` is left out.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows = self
+            .board
+            .chunks(3)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        Some(placed) => format!("{}{}", placed.card, player_code(placed.owner)),
+                        None => String::from("-"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "{} {} {} {}",
+            rows.join("/"),
+            player_code(self.to_move),
+            cards_code(&self.hand),
+            cards_code(&self.opponent_hand)
+        )?;
+        if let Some(size) = self.opponent_hand_size {
+            write!(f, ":{}", size)?;
+        }
+        if !self.rules.is_empty() {
+            let rules = self
+                .rules
+                .iter()
+                .map(|rule| rule.replace(' ', ""))
+                .collect::<Vec<_>>();
+            write!(f, " {}", rules.join(","))?;
+        }
+        if !self.modifiers.is_empty() {
+            let modifiers = self
+                .modifiers
+                .iter()
+                .map(|modifier| format!("{}{:+}", modifier.suit, modifier.modifier))
+                .collect::<Vec<_>>();
+            write!(f, " {}", modifiers.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid(what: &'static str, text: &str) -> NotationError {
+    NotationError::Invalid {
+        what,
+        text: text.to_string(),
+    }
+}
+
+fn parse_player(c: char) -> Option<Player> {
+    match c.to_ascii_lowercase() {
+        'r' => Some(Player::Red),
+        'b' => Some(Player::Blue),
+        _ => None,
+    }
+}
+
+fn player_code(player: Player) -> char {
+    match player {
+        Player::Red => 'r',
+        Player::Blue => 'b',
+    }
+}
+
+fn parse_cards(text: &str) -> Result<Vec<i32>, NotationError> {
+    if text == "-" {
+        return Ok(vec![]);
+    }
+    text.split(',')
+        .map(|id| id.parse().map_err(|_| invalid("card ID", id)))
+        .collect()
+}
+
+fn is_sign(c: char) -> bool {
+    c == '+' || c == '-'
+}
+
+/// Parses suit modifiers as they're written in the notation, like `P+2,S+1`.
+pub fn parse_modifiers(text: &str) -> Result<Vec<SuitModifier>, NotationError> {
+    text.split(',')
+        .map(|entry| {
+            let mut chars = entry.chars();
+            let suit = chars.next().and_then(Suit::from_letter);
+            let modifier = chars.as_str();
+            match (suit, modifier.starts_with(is_sign), modifier.parse()) {
+                (Some(suit), true, Ok(modifier)) => Ok(SuitModifier { suit, modifier }),
+                _ => Err(invalid("suit modifier", entry)),
+            }
+        })
+        .collect()
+}
+
+fn cards_code(cards: &[i32]) -> String {
+    if cards.is_empty() {
+        String::from("-")
+    } else {
+        cards
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// A move for the player to move, with how the solver rates it.
+#[derive(Serialize, Clone, Debug)]
+pub struct Recommendation {
+    /// Index into the position's `hand`.
+    pub hand_index: usize,
+    pub card: i32,
+    /// The cell as a code, like `B2`.
+    pub cell: String,
+    /// The cell as an index, like in the position's `board`.
+    pub position: usize,
+    pub score: f64,
+    /// Set when the Monte Carlo search had to break a tie between equally scored moves.
+    pub outcomes: Option<Outcomes>,
+}
+
+/// How a move's Monte Carlo playouts turned out, as percentages adding up to 100. Ties are only
+/// counted here, not as part of a win like when the tie is broken.
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Outcomes {
+    pub win_percent: f64,
+    pub tie_percent: f64,
+    pub loss_percent: f64,
+}
+impl From<OutcomeRates> for Outcomes {
+    fn from(rates: OutcomeRates) -> Self {
+        Outcomes {
+            win_percent: rates.win * 100.0,
+            tie_percent: rates.tie * 100.0,
+            loss_percent: rates.loss * 100.0,
+        }
+    }
+}
+
+/// A move in a [`SearchDump`].
+#[derive(Serialize, Clone, Debug)]
+pub struct DumpedBranch {
+    pub player: Player,
+    pub card: i32,
+    pub name: String,
+    /// The cell as a code, like `B2`.
+    pub cell: String,
+    /// What the move is worth to the player the dump was made for, not the one making it.
+    pub score: f64,
+    pub replies: Vec<DumpedBranch>,
+}
+
+/// What the search expected on one turn: the best few moves, the opponent's likeliest replies to
+/// each, and so on, for working out afterwards why a recommendation went wrong.
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchDump {
+    pub player: Player,
+    /// The board described in words, a cell per line.
+    pub board: Vec<String>,
+    pub rules: Vec<&'static str>,
+    pub branches: Vec<DumpedBranch>,
+}
+
+/// How many moves a [`SearchDump`] keeps at each step.
+pub const DUMP_WIDTH: usize = 3;
+
+/// How many moves deep a [`SearchDump`] goes.
+pub const DUMP_PLIES: usize = 4;
+
+/// Dumps what the search expects from `player`'s turn in `game`. This searches several lines
+/// fully, so it takes a few times longer than finding the best move.
+pub fn dump_search(
+    game: &Game,
+    player: Player,
+    data: &Data,
+    search_config: &SearchConfig,
+) -> SearchDump {
+    fn dumped(
+        game: &mut Game,
+        branches: Vec<search::Branch<GameMove>>,
+        data: &Data,
+    ) -> Vec<DumpedBranch> {
+        branches
+            .into_iter()
+            .map(|branch| {
+                let id = game.player_hand_card_id(branch.mv.player, branch.mv.card_idx);
+                game.apply_move(&branch.mv);
+                let replies = dumped(game, branch.replies, data);
+                game.undo_last_moves(1);
+                DumpedBranch {
+                    player: branch.mv.player,
+                    card: id.get(),
+                    name: data.card_display_name(id.get()).to_string(),
+                    cell: game::cell_code(branch.mv.placement),
+                    score: branch.score,
+                    replies,
+                }
+            })
+            .collect()
+    }
+
+    let branches = search::top_branches(game, player, search_config, DUMP_WIDTH, DUMP_PLIES);
+    let mut game = game.truncate_history_and_clone();
+    SearchDump {
+        player,
+        board: game
+            .describe_board(data)
+            .lines()
+            .map(String::from)
+            .collect(),
+        rules: game.rules().active_rule_names(),
+        branches: dumped(&mut game, branches, data),
+    }
+}
+
+const MAX_HAND_SIZE: usize = 5;
+const MAX_OPPONENT_HAND_SIZE: usize = game::HAND_SLOTS;
+
+fn hand_cards(data: &Data, ids: &[i32], max: usize) -> Result<Vec<CardId>, SolveError> {
+    if ids.len() > max {
+        return Err(SolveError::TooManyCards {
+            count: ids.len(),
+            max,
+        });
+    }
+    ids.iter().map(|&id| card_id(data, id)).collect()
+}
+
+fn card_id(data: &Data, id: i32) -> Result<CardId, SolveError> {
+    CardId::new(id)
+        .filter(|&card_id| data.cards.contains(card_id))
+        .ok_or(SolveError::UnknownCard(id))
+}
+
+fn recommendation(
+    hand: &[i32],
+    mv: &GameMove,
+    score: f64,
+    rates: Option<OutcomeRates>,
+) -> Recommendation {
+    Recommendation {
+        hand_index: mv.card_idx,
+        card: hand[mv.card_idx],
+        cell: game::cell_code(mv.placement),
+        position: mv.placement,
+        score,
+        outcomes: rates.map(Outcomes::from),
+    }
+}
+
+/// Sets up a game in the given position, with the position's player treated as the human.
+pub fn game_for_position(data: &Data, position: &Position) -> Result<Game, SolveError> {
+    let mut rules = Rules::default();
+    for name in &position.rules {
+        if !Rules::NAMES.contains(&name.as_str()) {
+            return Err(SolveError::UnknownRule(name.clone()));
+        }
+        rules.add_rule_by_name(name);
+    }
+
+    let player = position.player();
+    let mut builder = GameBuilder::new(player, Arc::clone(&data.cards))
+        .rules(rules)
+        .to_move(position.to_move);
+
+    for id in hand_cards(data, &position.hand, MAX_HAND_SIZE)? {
+        builder = builder.known_card(player, id);
+    }
+
+    let opponent_hand = hand_cards(data, &position.opponent_hand, MAX_OPPONENT_HAND_SIZE)?;
+    let opponent_hand_size = position
+        .opponent_hand_size
+        .unwrap_or(opponent_hand.len())
+        .min(opponent_hand.len());
+    // When the opponent holds fewer cards than were listed, any of them might be the ones held
+    let known = opponent_hand_size == opponent_hand.len();
+    for id in opponent_hand {
+        builder = builder.hand_card(player.other(), HandCard { id, known });
+    }
+    builder = builder.hand_size(player.other(), opponent_hand_size);
+
+    for (pos, placed) in position.board.iter().enumerate() {
+        if let Some(placed) = placed {
+            builder = builder.place(pos, card_id(data, placed.card)?, placed.owner);
+        }
+    }
+
+    // Each card of a suit on the board adds 1 under Ascension and takes 1 away under Descension
+    let step = rules.ascension as i32 - rules.decension as i32;
+    for &SuitModifier { suit, modifier } in &position.modifiers {
+        let count = modifier * step;
+        if suit == Suit::None || count < 0 || (step == 0 && modifier != 0) {
+            return Err(SolveError::InvalidModifier { suit, modifier });
+        }
+        builder = builder.suit_count(suit, count);
+    }
+
+    let game = builder.build()?;
+    Ok(game)
+}
+
+fn game_to_solve(data: &Data, position: &Position) -> Result<Game, SolveError> {
+    if position.player() != position.to_move {
+        return Err(SolveError::OpponentToMove);
+    }
+    game_for_position(data, position)
+}
+
+pub fn solve(
+    data: &Data,
+    position: &Position,
+    search_config: &SearchConfig,
+) -> Result<Recommendation, SolveError> {
+    let game = game_to_solve(data, position)?;
+    let (mv, (score, rates)) =
+        search::get_best_move_for_player(&game, position.to_move, search_config);
+    let mv = mv.ok_or(SolveError::NoMoves)?;
+
+    Ok(recommendation(&position.hand, &mv, score, rates))
+}
+
+/// Every move the player to move could make, best first.
+pub fn rank_moves(
+    data: &Data,
+    position: &Position,
+    search_config: &SearchConfig,
+) -> Result<Vec<Recommendation>, SolveError> {
+    let game = game_to_solve(data, position)?;
+    rank_game_moves(&game, position.to_move, &position.hand, search_config)
+}
+
+/// Like [`rank_moves`], for a game that's already set up. `hand` is the card IDs `player` started
+/// with, in the order they were given to the game.
+pub fn rank_game_moves(
+    game: &Game,
+    player: Player,
+    hand: &[i32],
+    search_config: &SearchConfig,
+) -> Result<Vec<Recommendation>, SolveError> {
+    let ranked = search::rank_moves(game, player, search_config);
+    if ranked.is_empty() {
+        return Err(SolveError::NoMoves);
+    }
+
+    Ok(ranked
+        .into_iter()
+        .map(|(mv, score, rates)| recommendation(hand, &mv, score, rates))
+        .collect())
+}
+
+/// Like [`solve`], but takes the position as JSON and returns the recommendation as JSON.
+pub fn solve_json(
+    data: &Data,
+    position_json: &str,
+    search_config: &SearchConfig,
+) -> Result<String, SolveError> {
+    let position = serde_json::from_str(position_json)?;
+    let recommendation = solve(data, &position, search_config)?;
+    Ok(serde_json::to_string(&recommendation)?)
+}
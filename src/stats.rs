@@ -0,0 +1,361 @@
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{rating::Ratings, store};
+
+#[derive(Debug, Error)]
+pub enum MatchStatsError {
+    #[error("Could not read/write stats file")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Could not parse stats file")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Could not write CSV export")]
+    CsvExport(#[from] csv::Error),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchResult {
+    Win,
+    Tie,
+    Loss,
+}
+impl Display for MatchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                MatchResult::Win => "win",
+                MatchResult::Tie => "tie",
+                MatchResult::Loss => "loss",
+            }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct MatchRecord {
+    pub date: DateTime<Utc>,
+    pub deck: String,
+    pub npc: String,
+    pub rules: Vec<String>,
+    pub result: MatchResult,
+}
+
+/// How a deck did in simulated matches against an NPC, from simulating every deck against every
+/// NPC. These give decks and NPCs a starting point for their ratings before many real matches are
+/// played.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct SimulatedMatchup {
+    pub date: DateTime<Utc>,
+    pub deck: String,
+    pub npc: String,
+    pub win: f64,
+    pub tie: f64,
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct Tally {
+    pub wins: usize,
+    pub ties: usize,
+    pub losses: usize,
+}
+impl Tally {
+    fn add(&mut self, result: MatchResult) {
+        match result {
+            MatchResult::Win => self.wins += 1,
+            MatchResult::Tie => self.ties += 1,
+            MatchResult::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.wins + self.ties + self.losses
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        if self.total() == 0 {
+            0f64
+        } else {
+            self.wins as f64 / self.total() as f64
+        }
+    }
+}
+impl Display for Tally {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}W / {}T / {}L ({:.1}% won)",
+            self.wins,
+            self.ties,
+            self.losses,
+            self.win_rate() * 100f64
+        )
+    }
+}
+
+/// When the game's weekly content, including the challenge log, last reset before `now`: every
+/// Tuesday at 8:00 UTC.
+pub fn last_weekly_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let days_since_tuesday = (now.weekday().num_days_from_monday() + 6) % 7;
+    let reset = (now.date_naive() - Duration::days(days_since_tuesday.into()))
+        .and_hms_opt(8, 0, 0)
+        .unwrap()
+        .and_utc();
+    if reset > now {
+        reset - Duration::days(7)
+    } else {
+        reset
+    }
+}
+
+/// Progress toward the challenge log's Triple Triad entries since the last weekly reset.
+pub struct WeeklyProgress {
+    pub tally: Tally,
+    /// Wins against NPCs that play by their region's rules, which some entries ask for.
+    pub regional_wins: usize,
+    pub next_reset: DateTime<Utc>,
+}
+impl Display for WeeklyProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let until_reset = self.next_reset - Utc::now();
+        write!(
+            f,
+            "{} matches, {} won ({} against NPCs with regional rules); resets in {}d {}h",
+            self.tally.total(),
+            self.tally.wins,
+            self.regional_wins,
+            until_reset.num_days(),
+            until_reset.num_hours() % 24
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MatchStats {
+    matches: Vec<MatchRecord>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    simulated: Vec<SimulatedMatchup>,
+
+    #[serde(skip)]
+    stats_path: PathBuf,
+}
+impl MatchStats {
+    pub fn new(profile_dir: &Path) -> Result<Self, MatchStatsError> {
+        let stats_path = profile_dir.join("stats.json");
+
+        let saved = store::load(&stats_path, |text| {
+            serde_json::from_str::<MatchStats>(text).map_err(MatchStatsError::from)
+        })?;
+        if let Some(mut result) = saved {
+            result.stats_path = stats_path;
+            Ok(result)
+        } else {
+            std::fs::create_dir_all(stats_path.parent().unwrap())?;
+            let result = MatchStats {
+                matches: Vec::new(),
+                simulated: Vec::new(),
+                stats_path,
+            };
+            result.save()?;
+            Ok(result)
+        }
+    }
+
+    pub fn record_match(&mut self, record: MatchRecord) -> Result<(), MatchStatsError> {
+        self.matches.push(record);
+        self.save()
+    }
+
+    pub fn get_match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn totals(&self) -> Tally {
+        let mut tally = Tally::default();
+        for record in &self.matches {
+            tally.add(record.result);
+        }
+        tally
+    }
+
+    /// What has been played since the last weekly reset before `now`. `uses_regional_rules` says
+    /// whether an NPC (by English name) plays by their region's rules.
+    pub fn weekly_progress(
+        &self,
+        now: DateTime<Utc>,
+        uses_regional_rules: impl Fn(&str) -> bool,
+    ) -> WeeklyProgress {
+        let reset = last_weekly_reset(now);
+        let mut progress = WeeklyProgress {
+            tally: Tally::default(),
+            regional_wins: 0,
+            next_reset: reset + Duration::days(7),
+        };
+        for record in self.matches.iter().filter(|record| record.date >= reset) {
+            progress.tally.add(record.result);
+            if record.result == MatchResult::Win && uses_regional_rules(&record.npc) {
+                progress.regional_wins += 1;
+            }
+        }
+        progress
+    }
+
+    /// Returns the tally for every deck that has been used, sorted by name.
+    pub fn by_deck(&self) -> Vec<(String, Tally)> {
+        self.group_by(|record| &record.deck)
+    }
+
+    /// Returns the tally for every NPC that has been played, sorted by name.
+    pub fn by_npc(&self) -> Vec<(String, Tally)> {
+        self.group_by(|record| &record.npc)
+    }
+
+    /// Returns up to `count` NPCs played most recently, the latest first, each once.
+    pub fn recent_npcs(&self, count: usize) -> Vec<&str> {
+        // Imported matches are appended after the ones already here, so go by date
+        let mut by_date = self.matches.iter().collect::<Vec<_>>();
+        by_date.sort_by_key(|record| std::cmp::Reverse(record.date));
+        let mut recent: Vec<&str> = Vec::new();
+        for record in by_date {
+            if recent.len() == count {
+                break;
+            }
+            if !recent.contains(&record.npc.as_str()) {
+                recent.push(&record.npc);
+            }
+        }
+        recent
+    }
+
+    /// Keeps the results of simulating decks against NPCs, in place of any earlier ones for the same
+    /// deck and NPC.
+    pub fn record_simulations(
+        &mut self,
+        simulated: Vec<SimulatedMatchup>,
+    ) -> Result<(), MatchStatsError> {
+        self.simulated.retain(|old| {
+            !simulated
+                .iter()
+                .any(|new| new.deck == old.deck && new.npc == old.npc)
+        });
+        self.simulated.extend(simulated);
+        self.save()
+    }
+
+    /// Rates every deck and NPC: first from the simulated matchups, each counting as one match
+    /// scored by how often the deck won (with ties as half), then from the real matches in the
+    /// order they were played, so the ratings follow how decks do now.
+    pub fn ratings(&self) -> Ratings {
+        let mut ratings = Ratings::default();
+        for matchup in &self.simulated {
+            ratings.record(&matchup.deck, &matchup.npc, matchup.win + matchup.tie / 2.0);
+        }
+        let mut by_date = self.matches.iter().collect::<Vec<_>>();
+        by_date.sort_by_key(|record| record.date);
+        for record in by_date {
+            let score = match record.result {
+                MatchResult::Win => 1.0,
+                MatchResult::Tie => 0.5,
+                MatchResult::Loss => 0.0,
+            };
+            ratings.record(&record.deck, &record.npc, score);
+        }
+        ratings
+    }
+
+    /// Returns the result and length of the run of identical results at the end of the match history.
+    pub fn current_streak(&self) -> Option<(MatchResult, usize)> {
+        let last = self.matches.last()?.result;
+        let length = self
+            .matches
+            .iter()
+            .rev()
+            .take_while(|record| record.result == last)
+            .count();
+        Some((last, length))
+    }
+
+    pub fn longest_win_streak(&self) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        for record in &self.matches {
+            if record.result == MatchResult::Win {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        longest
+    }
+
+    /// Adds the matches from `imported` that aren't recorded here yet, returning how many there were.
+    /// Simulated matchups are added for the decks and NPCs that haven't been simulated here.
+    pub fn merge(&mut self, imported: MatchStats) -> Result<usize, MatchStatsError> {
+        let count = self.matches.len();
+        for record in imported.matches {
+            if !self.matches.contains(&record) {
+                self.matches.push(record);
+            }
+        }
+        for matchup in imported.simulated {
+            if !self
+                .simulated
+                .iter()
+                .any(|old| old.deck == matchup.deck && old.npc == matchup.npc)
+            {
+                self.simulated.push(matchup);
+            }
+        }
+        self.matches.sort_by_key(|record| record.date);
+        self.save()?;
+        Ok(self.matches.len() - count)
+    }
+
+    pub fn export_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), MatchStatsError> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(["date", "deck", "npc", "rules", "result"])?;
+        for record in &self.matches {
+            writer.write_record([
+                record.date.to_rfc3339(),
+                record.deck.clone(),
+                record.npc.clone(),
+                record.rules.join("/"),
+                record.result.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn group_by<F: Fn(&MatchRecord) -> &String>(&self, key: F) -> Vec<(String, Tally)> {
+        let mut tallies: HashMap<&String, Tally> = HashMap::new();
+        for record in &self.matches {
+            tallies.entry(key(record)).or_default().add(record.result);
+        }
+
+        let mut result: Vec<_> = tallies
+            .into_iter()
+            .map(|(name, tally)| (name.clone(), tally))
+            .collect();
+        result.sort_by(|(a, _), (b, _)| a.cmp(b));
+        result
+    }
+
+    fn save(&self) -> Result<(), MatchStatsError> {
+        store::save(
+            &self.stats_path,
+            serde_json::to_string_pretty(self)?.as_bytes(),
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+//! Saving and loading the user's files (settings, profiles, decks, and statistics) so that a crash
+//! or power cut partway through a save can't lose them. Each save goes to a temporary file that's
+//! then renamed into place, and the previous save is kept next to it as a backup (`decks.json.bak`)
+//! to fall back on if the file still turns out to be unreadable.
+
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+fn with_extra_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// Replaces the contents of `path` with `contents`, moving the old contents to the backup.
+pub(crate) fn save(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp_path = with_extra_extension(path, "tmp");
+    {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    // If this is interrupted before the second rename, loading picks up the backup instead
+    if path.exists() {
+        std::fs::rename(path, with_extra_extension(path, "bak"))?;
+    }
+    std::fs::rename(&temp_path, path)
+}
+
+/// Reads and parses `path`, falling back on the backup if it's missing or `parse` fails on it.
+/// A file that couldn't be parsed is moved aside to `<name>.damaged` (so the next save doesn't
+/// replace the backup with it) and the error is returned only if the backup can't be loaded either.
+/// Returns `None` if neither file exists.
+pub(crate) fn load<T, E: From<std::io::Error>>(
+    path: &Path,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<Option<T>, E> {
+    let backup_path = with_extra_extension(path, "bak");
+    let error = if path.exists() {
+        match parse(&std::fs::read_to_string(path)?) {
+            Ok(result) => return Ok(Some(result)),
+            Err(e) => Some(e),
+        }
+    } else {
+        None
+    };
+    if !backup_path.exists() {
+        return error.map_or(Ok(None), Err);
+    }
+
+    let result = match parse(&std::fs::read_to_string(&backup_path)?) {
+        Ok(result) => result,
+        Err(backup_error) => return Err(error.unwrap_or(backup_error)),
+    };
+    if error.is_some() {
+        let damaged_path = with_extra_extension(path, "damaged");
+        std::fs::rename(path, &damaged_path)?;
+        println!(
+            "{} couldn't be read, so the backup from the save before it was loaded instead. The damaged file was kept as {}.",
+            path.display(),
+            damaged_path.display()
+        );
+    }
+    Ok(Some(result))
+}
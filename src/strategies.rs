@@ -0,0 +1,253 @@
+// The `compare` subcommand: plays two ways of picking moves against each other, and each of them
+// against a scripted NPC, over matches sampled the way `tune` samples its corpus (random legal
+// decks under a few common rule sets, each played four times so neither deck nor the first move
+// favors a side). It reports each one's share of the points with a 95% confidence interval, so a
+// change to the search or the weights can be checked against chance before it's kept.
+//
+// A strategy is `npc`, `settings`, or a comma-separated list of changes to the search in the
+// settings, like `opponent=greedy,playouts=0,tie=-10`:
+//   opponent  how the search expects the other side to play: perfect, greedy, or random
+//   playouts  the Monte Carlo games simulated to break ties between moves, per tied move
+//   tie, margin, sides  the evaluation weights (see `EvalWeights`)
+// `npc` is the scripted NPC: it plays one of the moves that flip the most cards, at random.
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rayon::prelude::*;
+use std::sync::Arc;
+use triple_triad_solver::{
+    data::Data,
+    game::{Game, GameMove, Player},
+    search::{
+        self, GamePlayer, Opponent, OpponentModel, SearchConfig, SearchableGame,
+        TranspositionTable, WinState,
+    },
+    settings::Settings,
+};
+
+use crate::tune::{self, Matchup};
+
+pub const DEFAULT_MATCHUPS: usize = 16;
+
+/// A different seed from `tune`'s, so its weights aren't checked on the matches they were tuned on.
+const SEED: u64 = 0xc0de;
+
+/// Entries in each side's transposition table, which it keeps through the match.
+const TABLE_ENTRIES: usize = 1 << 16;
+
+/// How many standard errors either side of the mean a 95% confidence interval reaches.
+const Z_95: f64 = 1.96;
+
+#[derive(Clone)]
+enum Strategy {
+    /// Searches for its moves with this config.
+    Search(SearchConfig),
+    /// Plays the way most NPCs do.
+    Npc,
+}
+
+fn parse_strategy(spec: &str, settings: &Settings) -> Result<Strategy, String> {
+    let mut config = SearchConfig {
+        verbose: false,
+        ..settings.search_config()
+    };
+    match spec {
+        "npc" => return Ok(Strategy::Npc),
+        "settings" => return Ok(Strategy::Search(config)),
+        _ => {}
+    }
+    for change in spec.split(',') {
+        let (name, value) = change
+            .split_once('=')
+            .ok_or_else(|| format!("expected name=value, not {}", change))?;
+        let invalid = || format!("invalid value {} for {}", value, name);
+        match name {
+            "opponent" => {
+                config.opponent = match value {
+                    "perfect" => Opponent::Perfect,
+                    "greedy" => Opponent::GreedyFlips,
+                    "random" => Opponent::Random,
+                    _ => return Err(invalid()),
+                }
+            }
+            "playouts" => config.monte_carlo_iterations = value.parse().map_err(|_| invalid())?,
+            "tie" => config.eval_weights.tie = value.parse().map_err(|_| invalid())?,
+            "margin" => config.eval_weights.margin = value.parse().map_err(|_| invalid())?,
+            "sides" => config.eval_weights.sides = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("unknown setting {}", name)),
+        }
+    }
+    Ok(Strategy::Search(config))
+}
+
+/// Picks `player`'s next move by `strategy`, or `None` if it has none.
+fn next_move(
+    game: &mut Game,
+    player: Player,
+    strategy: &Strategy,
+    rng: &mut StdRng,
+) -> Option<GameMove> {
+    match strategy {
+        Strategy::Search(config) => search::get_best_move_for_player(game, player, config).0,
+        Strategy::Npc => {
+            let mut moves = Vec::with_capacity(50);
+            game.get_possible_moves(player, &mut moves);
+            OpponentModel::<Game>::narrow_moves(&search::GreedyFlips, game, player, &mut moves);
+            moves.choose(rng).copied()
+        }
+    }
+}
+
+/// Plays `matchup` out with each side picking its moves by its own strategy (indexed by player, so
+/// Red's comes first), returning Blue's share of the result: 1 for a win, 0.5 for a tie.
+fn play(
+    data: &Data,
+    matchup: &Matchup,
+    strategies: [&Strategy; 2],
+    first: Player,
+    seed: u64,
+) -> f64 {
+    let mut game = matchup.start(data, first);
+    let mut rng = StdRng::seed_from_u64(seed);
+    // Each side has a table of its own, since they may value positions differently
+    let strategies = strategies.map(|strategy| match strategy {
+        Strategy::Search(config) => Strategy::Search(SearchConfig {
+            transposition_table: Some(Arc::new(TranspositionTable::new(TABLE_ENTRIES))),
+            seed: Some(seed),
+            ..config.clone()
+        }),
+        Strategy::Npc => Strategy::Npc,
+    });
+
+    let mut current_player = first.other();
+    loop {
+        match game.win_state() {
+            WinState::NotFinished => {}
+            WinState::Tie => return 0.5,
+            WinState::Winner(Player::Blue) => return 1.0,
+            WinState::Winner(Player::Red) => return 0.0,
+        }
+        match next_move(
+            &mut game,
+            current_player,
+            &strategies[current_player],
+            &mut rng,
+        ) {
+            Some(mv) => game.apply_move(&mv),
+            None => return 0.5,
+        }
+        current_player = current_player.other();
+    }
+}
+
+/// How one strategy did over a series of matches against another.
+struct SeriesResult {
+    wins: usize,
+    ties: usize,
+    losses: usize,
+}
+impl SeriesResult {
+    fn games(&self) -> usize {
+        self.wins + self.ties + self.losses
+    }
+
+    /// The share of the points, with ties counting half.
+    fn score(&self) -> f64 {
+        (self.wins as f64 + self.ties as f64 / 2.0) / self.games() as f64
+    }
+
+    /// How far either side of the score the true one is with 95% confidence, taking each match as
+    /// an independent sample.
+    fn margin(&self) -> f64 {
+        let games = self.games() as f64;
+        let score = self.score();
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.ties as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / (games - 1.0).max(1.0);
+        Z_95 * (variance / games).sqrt()
+    }
+}
+
+/// Plays `candidate` against `other` over the corpus, with `candidate` on both sides and either side
+/// going first.
+fn series(data: &Data, corpus: &[Matchup], candidate: &Strategy, other: &Strategy) -> SeriesResult {
+    let mut games = Vec::with_capacity(corpus.len() * 4);
+    for matchup in corpus {
+        for side in [Player::Blue, Player::Red] {
+            for first in [Player::Blue, Player::Red] {
+                games.push((matchup, side, first));
+            }
+        }
+    }
+    let scores = games
+        .par_iter()
+        .enumerate()
+        .map(|(idx, &(matchup, side, first))| {
+            let seed = SEED.wrapping_add(idx as u64);
+            match side {
+                Player::Blue => play(data, matchup, [other, candidate], first, seed),
+                Player::Red => 1.0 - play(data, matchup, [candidate, other], first, seed),
+            }
+        })
+        .collect::<Vec<_>>();
+    SeriesResult {
+        wins: scores.iter().filter(|&&score| score == 1.0).count(),
+        ties: scores.iter().filter(|&&score| score == 0.5).count(),
+        losses: scores.iter().filter(|&&score| score == 0.0).count(),
+    }
+}
+
+fn print_series(name: &str, opponent: &str, result: &SeriesResult) {
+    println!(
+        "{} against {}: scored {:.1}% ± {:.1}% ({} wins, {} ties, {} losses)",
+        name,
+        opponent,
+        result.score() * 100.0,
+        result.margin() * 100.0,
+        result.wins,
+        result.ties,
+        result.losses
+    );
+}
+
+pub fn run(data: &Data, settings: &Settings, specs: [&str; 2], matchups: usize) {
+    let mut strategies = Vec::with_capacity(2);
+    for spec in specs {
+        match parse_strategy(spec, settings) {
+            Ok(strategy) => strategies.push(strategy),
+            Err(message) => {
+                println!("Could not read the strategy {}: {}", spec, message);
+                return;
+            }
+        }
+    }
+    let corpus = tune::corpus(data, matchups, SEED);
+    println!(
+        "Comparing {} and {} over {} matches per pairing.",
+        specs[0],
+        specs[1],
+        corpus.len() * 4
+    );
+
+    let head_to_head = series(data, &corpus, &strategies[0], &strategies[1]);
+    print_series(specs[0], specs[1], &head_to_head);
+    for (spec, strategy) in specs.iter().zip(&strategies) {
+        let result = series(data, &corpus, strategy, &Strategy::Npc);
+        print_series(spec, "the scripted NPC", &result);
+    }
+
+    let (score, margin) = (head_to_head.score(), head_to_head.margin());
+    if score - margin > 0.5 {
+        println!(
+            "{} is ahead of {} by more than chance explains.",
+            specs[0], specs[1]
+        );
+    } else if score + margin < 0.5 {
+        println!(
+            "{} is ahead of {} by more than chance explains.",
+            specs[1], specs[0]
+        );
+    } else {
+        println!("Neither is ahead by more than chance explains; try more matchups.");
+    }
+}
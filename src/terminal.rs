@@ -0,0 +1,62 @@
+// Works out what the terminal can show, so boards don't come out as mojibake on consoles that
+// can't handle box drawing or ANSI colors (like the legacy Windows console host).
+
+use std::{env, io::IsTerminal};
+use triple_triad_solver::settings::TerminalOutput;
+
+/// What the terminal can show, going by the environment: one of the settings other than `Auto`.
+pub fn detect() -> TerminalOutput {
+    match (supports_unicode(), supports_ansi()) {
+        (false, _) => TerminalOutput::Ascii,
+        (true, false) => TerminalOutput::Unicode,
+        (true, true) => TerminalOutput::UnicodeAndColors,
+    }
+}
+
+/// The setting, with `Auto` replaced by what was detected.
+pub fn resolve(setting: TerminalOutput) -> TerminalOutput {
+    match setting {
+        TerminalOutput::Auto => detect(),
+        setting => setting,
+    }
+}
+
+/// Terminals known to understand escape codes on Windows, where the old console host shows them
+/// as is: Windows Terminal, ConEmu, VS Code, and mintty and the like (which set `TERM`).
+#[cfg(windows)]
+fn modern_windows_terminal() -> bool {
+    env::var_os("WT_SESSION").is_some()
+        || env::var_os("TERM_PROGRAM").is_some()
+        || env::var_os("TERM").is_some()
+        || env::var("ConEmuANSI").is_ok_and(|value| value == "ON")
+}
+
+#[cfg(windows)]
+fn supports_unicode() -> bool {
+    // The console host's default code page mangles UTF-8, and changing it needs the Windows API
+    modern_windows_terminal()
+}
+
+#[cfg(not(windows))]
+fn supports_unicode() -> bool {
+    // The first locale variable that's set decides the character set. With none set, assume UTF-8,
+    // as nearly every terminal emulator uses it.
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+        .next()
+        .is_none_or(|locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+#[cfg(windows)]
+fn supports_ansi() -> bool {
+    std::io::stdout().is_terminal() && modern_windows_terminal()
+}
+
+#[cfg(not(windows))]
+fn supports_ansi() -> bool {
+    std::io::stdout().is_terminal() && env::var("TERM").map_or(true, |term| term != "dumb")
+}
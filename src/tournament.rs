@@ -0,0 +1,133 @@
+//! Getting ready for a Gold Saucer tournament: finding a deck that does well under the tournament's
+//! rules against a field of strong opponents, and saving it tagged with the rules it was built for.
+
+use inquire::{MultiSelect, Text};
+use std::fmt::{Display, Formatter};
+
+use triple_triad_solver::{
+    data::Data,
+    decks::SavedDecks,
+    game::Rules,
+    legality::DeckLegality,
+    optimize::{self, Field},
+    prompt::{Confirm, Select},
+};
+
+/// How many of the strongest NPCs stand in for the other players in the tournament.
+const FIELD_SIZE: usize = 8;
+
+/// Rule combinations tournaments are played under. Each tournament announces its rules ahead of
+/// time, so anything that isn't listed can be picked by hand.
+const PRESETS: [(&str, &[&str]); 6] = [
+    ("Plus and Same", &["Plus", "Same"]),
+    ("Reverse and Fallen Ace", &["Reverse", "Fallen Ace"]),
+    ("Ascension", &["Ascension"]),
+    ("Descension", &["Descension"]),
+    ("Order", &["Order"]),
+    ("No special rules", &[]),
+];
+
+enum RulesChoice {
+    Preset(&'static str, &'static [&'static str]),
+    Custom,
+}
+impl Display for RulesChoice {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            RulesChoice::Preset(name, _) => write!(f, "{}", name),
+            RulesChoice::Custom => write!(f, "Other rules"),
+        }
+    }
+}
+
+pub fn prepare(data: &Data, saved_decks: &mut SavedDecks) {
+    if saved_decks.get_deck_count() == 0 {
+        println!("You must have at least 1 registered deck to start from!");
+        return;
+    }
+
+    let choices = PRESETS
+        .iter()
+        .map(|&(name, rules)| RulesChoice::Preset(name, rules))
+        .chain(std::iter::once(RulesChoice::Custom))
+        .collect();
+    let mut rules = Rules::default();
+    let rules_name = match Select::new("Which rules is the tournament played under?", choices)
+        .prompt()
+        .unwrap()
+    {
+        RulesChoice::Preset(name, names) => {
+            for name in names {
+                rules.add_rule_by_name(name);
+            }
+            name.to_string()
+        }
+        RulesChoice::Custom => {
+            for name in MultiSelect::new("Which rules?", Rules::NAMES.to_vec())
+                .prompt()
+                .unwrap()
+            {
+                rules.add_rule_by_name(name);
+            }
+            match rules.active_rule_names() {
+                names if names.is_empty() => String::from("No special rules"),
+                names => names.join(", "),
+            }
+        }
+    };
+
+    let legality = Select::new("Which decks does it allow?", DeckLegality::all())
+        .prompt()
+        .unwrap();
+
+    let start_name = crate::select_deck("Which deck should the search start from?", saved_decks);
+    let start = saved_decks.get_deck(&start_name).unwrap();
+    if let Err(e) = legality.check(data, &start) {
+        println!("{} can't be played in this tournament: {}", start_name, e);
+        return;
+    }
+
+    let field = Field::strongest_npcs(data, rules, FIELD_SIZE);
+    println!(
+        "Trying decks against {}. This can take a minute...",
+        field
+            .npc_names
+            .iter()
+            .map(|name| data.npc_display_name(name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let optimized = optimize::optimize_deck(data, &field, start, &legality);
+
+    println!(
+        "\n{} scores about {:.0}% against the field (wins, with draws counting half).",
+        start_name,
+        optimized.start_score * 100.0
+    );
+    if optimized.deck == start {
+        println!("No changes to it did better.");
+        return;
+    }
+    println!("This deck scores about {:.0}%:", optimized.score * 100.0);
+    crate::print_deck(&optimized.deck.map(Some), data);
+
+    if !Confirm::new("Save it?")
+        .with_default(true)
+        .prompt()
+        .unwrap()
+    {
+        return;
+    }
+    let name = Text::new("Deck name:")
+        .with_default(&format!("{} tournament", rules_name))
+        .prompt()
+        .unwrap();
+    saved_decks
+        .add_tagged_deck(
+            name,
+            optimized.deck,
+            Some(format!("tournament: {}", rules_name)),
+        )
+        .unwrap();
+    println!("Deck saved!");
+}
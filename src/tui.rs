@@ -0,0 +1,541 @@
+// Full-screen match view, compiled in with the `tui` feature. The board sits in the middle with a
+// hand on either side, and the move list and recommendation underneath; moves are entered by
+// picking a card with the number keys and a cell with the arrow keys.
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use triple_triad_solver::{
+    data::Data,
+    game::{self, CellChange, Game, GameMove, HandCardStatus, Player},
+    search::{
+        self, CancelToken, GamePlayer, OutcomeRates, ProgressReporter, SearchConfig,
+        SearchProgress, SearchableGame, WinState,
+    },
+    stats::MatchResult,
+};
+
+const CELL_WIDTH: u16 = 11;
+const CELL_HEIGHT: u16 = 5;
+
+// How often keys are checked for while the search runs
+const SEARCH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+const HELP: &str =
+    "Number keys or Tab: pick a card. Arrow keys: pick a cell. Enter: play it. U: undo. Esc: quit.";
+
+struct MatchView<'a> {
+    npc_name: &'a str,
+    current_player: Player,
+    cursor: usize,
+    selected_slot: Option<usize>,
+    recommendation: Option<String>,
+    /// The NPC's likely reply to the recommended move, and the answer to it.
+    expected_line: Option<String>,
+    log: Vec<String>,
+    status: String,
+}
+
+/// Plays out a match in the full-screen view. Returns `None` if the player quit before the end.
+pub fn play_match(
+    game: &mut Game,
+    first_player: Player,
+    data: &Data,
+    npc_name: &str,
+    search_config: &SearchConfig,
+) -> io::Result<Option<MatchResult>> {
+    let mut terminal = ratatui::try_init()?;
+    let result = run(
+        &mut terminal,
+        game,
+        first_player,
+        data,
+        npc_name,
+        search_config,
+    );
+    ratatui::restore();
+    result
+}
+
+fn run(
+    terminal: &mut DefaultTerminal,
+    game: &mut Game,
+    first_player: Player,
+    data: &Data,
+    npc_name: &str,
+    search_config: &SearchConfig,
+) -> io::Result<Option<MatchResult>> {
+    // The search would otherwise print its progress over the screen
+    let search_config = SearchConfig {
+        verbose: false,
+        ..search_config.clone()
+    };
+    let mut view = MatchView {
+        npc_name,
+        current_player: first_player,
+        cursor: 4,
+        selected_slot: None,
+        recommendation: None,
+        expected_line: None,
+        log: Vec::new(),
+        status: HELP.to_string(),
+    };
+    let mut moves = Vec::with_capacity(100);
+
+    loop {
+        let result = match game.win_state() {
+            WinState::NotFinished => None,
+            WinState::Tie => Some(MatchResult::Tie),
+            WinState::Winner(Player::Blue) => Some(MatchResult::Win),
+            WinState::Winner(Player::Red) => Some(MatchResult::Loss),
+        };
+        if let Some(result) = result {
+            view.status = format!(
+                "{} Press any key to continue.",
+                match result {
+                    MatchResult::Win => "You win!",
+                    MatchResult::Tie => "Tie!",
+                    MatchResult::Loss => "You lose!",
+                }
+            );
+            terminal.draw(|frame| draw(frame, game, &view, data))?;
+            wait_for_key()?;
+            return Ok(Some(result));
+        }
+
+        game.get_possible_moves(view.current_player, &mut moves);
+
+        if view.current_player == Player::Blue && view.recommendation.is_none() {
+            let ((recommended_move, (score, rates)), line, stopped) =
+                search_in_background(terminal, game, &mut view, data, &search_config)?;
+            if let Some(mv) = recommended_move {
+                view.recommendation = Some(format!(
+                    "Play your {} card in {} (score {}{}).",
+                    game.player_hand_card_name(Player::Blue, mv.card_idx, data),
+                    game::cell_code(mv.placement),
+                    score,
+                    rates
+                        .map(|rates| format!("; random playouts: {}", rates))
+                        .unwrap_or_default()
+                ));
+                view.expected_line =
+                    game.describe_expected_line(&line, data.npc_display_name(view.npc_name), data);
+                view.selected_slot = Some(mv.card_idx);
+                view.cursor = mv.placement;
+            }
+            view.status = if stopped {
+                String::from("Search stopped early, so this is the best move it found so far.")
+            } else {
+                HELP.to_string()
+            };
+        }
+
+        terminal.draw(|frame| draw(frame, game, &view, data))?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => key.code,
+            _ => continue,
+        };
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Up if view.cursor >= 3 => view.cursor -= 3,
+            KeyCode::Down if view.cursor < 6 => view.cursor += 3,
+            KeyCode::Left if !view.cursor.is_multiple_of(3) => view.cursor -= 1,
+            KeyCode::Right if view.cursor % 3 < 2 => view.cursor += 1,
+            KeyCode::Char(digit @ '0'..='9') => {
+                // The slots are numbered from 1, with 0 for the tenth
+                let slot = (digit as usize - '0' as usize + 9) % 10;
+                if moves.iter().any(|mv| mv.card_idx == slot) {
+                    view.selected_slot = Some(slot);
+                } else {
+                    view.status = String::from("That card can't be played right now.");
+                }
+            }
+            KeyCode::Tab => {
+                let mut slots = moves.iter().map(|mv| mv.card_idx).collect::<Vec<_>>();
+                slots.sort_unstable();
+                slots.dedup();
+                view.selected_slot = view
+                    .selected_slot
+                    .and_then(|selected| slots.iter().find(|&&slot| slot > selected))
+                    .or_else(|| slots.first())
+                    .copied();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let mv = view.selected_slot.and_then(|slot| {
+                    moves
+                        .iter()
+                        .find(|mv| mv.card_idx == slot && mv.placement == view.cursor)
+                });
+                match mv {
+                    Some(mv) => {
+                        view.log.push(describe_move(game, mv, &view, data));
+                        game.apply_move(mv);
+                        view.current_player = view.current_player.other();
+                        view.selected_slot = None;
+                        view.recommendation = None;
+                        view.expected_line = None;
+                        view.status = HELP.to_string();
+                    }
+                    None => {
+                        view.status = String::from("Pick a card and an empty cell first.");
+                    }
+                }
+            }
+            KeyCode::Char('u') if !view.log.is_empty() => {
+                game.undo_last_moves(1);
+                view.log.pop();
+                view.current_player = view.current_player.other();
+                view.selected_slot = None;
+                view.recommendation = None;
+                view.expected_line = None;
+                view.status = HELP.to_string();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The best move for the player as the search returns it, the line expected after it, and whether
+/// the search was stopped early.
+type SearchResult = (
+    (Option<GameMove>, (f64, Option<OutcomeRates>)),
+    Vec<GameMove>,
+    bool,
+);
+
+/// Finds the player's best move and the line expected after it on a worker thread, so that Esc or
+/// Ctrl-C can stop the search early meanwhile. A stopped search settles for the best move it found.
+fn search_in_background(
+    terminal: &mut DefaultTerminal,
+    game: &Game,
+    view: &mut MatchView,
+    data: &Data,
+    search_config: &SearchConfig,
+) -> io::Result<SearchResult> {
+    let cancel = CancelToken::new();
+    // The latest progress update, shown whenever the screen is redrawn
+    let progress = Arc::new(Mutex::new(None::<SearchProgress>));
+    let search_config = SearchConfig {
+        cancel: Some(cancel.clone()),
+        progress: Some(ProgressReporter::new({
+            let progress = Arc::clone(&progress);
+            move |update| *progress.lock().unwrap() = Some(update.clone())
+        })),
+        ..search_config.clone()
+    };
+
+    let (best, line) = std::thread::scope(|scope| {
+        let search = scope.spawn(|| {
+            let best = search::get_best_move_for_player(game, Player::Blue, &search_config);
+            let line = best
+                .0
+                .as_ref()
+                .map(|mv| search::expected_line(game, Player::Blue, mv, &search_config))
+                .unwrap_or_default();
+            (best, line)
+        });
+        while !search.is_finished() {
+            if !cancel.is_cancelled() {
+                view.status = match &*progress.lock().unwrap() {
+                    Some(update) => {
+                        format!("Finding the best move ({})... Esc: stop early.", update)
+                    }
+                    None => String::from("Finding the best move... Esc: stop early."),
+                };
+            }
+            terminal.draw(|frame| draw(frame, game, view, data))?;
+            if !event::poll(SEARCH_POLL_INTERVAL)? {
+                continue;
+            }
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+            // The terminal is in raw mode, so Ctrl-C arrives as a key instead of a signal
+            let ctrl_c =
+                key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if key.code == KeyCode::Esc || ctrl_c {
+                cancel.cancel();
+                view.status = String::from("Stopping the search...");
+            }
+        }
+        io::Result::Ok(search.join().unwrap())
+    })?;
+    Ok((best, line, cancel.is_cancelled()))
+}
+
+fn wait_for_key() -> io::Result<()> {
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Describes a move for the move list, like `You: Dodo to B2, flipping A2`.
+fn describe_move(game: &Game, mv: &GameMove, view: &MatchView, data: &Data) -> String {
+    let flips = game.preview_move(mv);
+    format!(
+        "{}: {} to {}{}",
+        player_name(mv.player, view),
+        game.player_hand_card_name(mv.player, mv.card_idx, data),
+        game::cell_code(mv.placement),
+        if flips.is_empty() {
+            String::new()
+        } else {
+            format!(
+                ", flipping {}",
+                flips
+                    .iter()
+                    .map(|&pos| game::cell_code(pos))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    )
+}
+
+fn player_name<'a>(player: Player, view: &MatchView<'a>) -> &'a str {
+    match player {
+        Player::Blue => "You",
+        Player::Red => view.npc_name,
+    }
+}
+
+fn player_color(player: Player) -> Color {
+    match player {
+        Player::Blue => Color::LightBlue,
+        Player::Red => Color::LightRed,
+    }
+}
+
+fn draw(frame: &mut Frame, game: &Game, view: &MatchView, data: &Data) {
+    let explanations = game.rules().explanations();
+    let [title_area, hints_area, middle_area, bottom_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(explanations.len() as u16),
+        Constraint::Min(CELL_HEIGHT * 3),
+        Constraint::Length(9),
+    ])
+    .areas(frame.area());
+    let [blue_area, board_area, red_area] = Layout::horizontal([
+        Constraint::Min(24),
+        Constraint::Length(CELL_WIDTH * 3 + 2),
+        Constraint::Min(24),
+    ])
+    .areas(middle_area);
+    let [log_area, info_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(bottom_area);
+
+    let scores = game.scores();
+    let rules = game.rules().active_rule_names();
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!("You {}", scores[Player::Blue]),
+                Style::default().fg(player_color(Player::Blue)),
+            ),
+            Span::raw(" – "),
+            Span::styled(
+                format!("{} {}", scores[Player::Red], view.npc_name),
+                Style::default().fg(player_color(Player::Red)),
+            ),
+            Span::raw(if rules.is_empty() {
+                String::new()
+            } else {
+                format!("   Rules: {}", rules.join(", "))
+            }),
+        ]))
+        .alignment(Alignment::Center),
+        title_area,
+    );
+    // A reminder of what each rule in play does
+    frame.render_widget(
+        Paragraph::new(
+            explanations
+                .iter()
+                .map(|explanation| {
+                    Line::styled(
+                        format!("{}: {}", explanation.name, explanation.summary),
+                        Style::default().add_modifier(Modifier::DIM),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        )
+        .alignment(Alignment::Center),
+        hints_area,
+    );
+
+    draw_hand(frame, game, view, data, Player::Blue, blue_area);
+    draw_board(frame, game, view, board_area);
+    draw_hand(frame, game, view, data, Player::Red, red_area);
+
+    let log_height = log_area.height.saturating_sub(2) as usize;
+    let log = view
+        .log
+        .iter()
+        .enumerate()
+        .skip(view.log.len().saturating_sub(log_height))
+        .map(|(idx, entry)| ListItem::new(format!("{}. {}", idx + 1, entry)))
+        .collect::<Vec<_>>();
+    frame.render_widget(List::new(log).block(bordered().title(" Moves ")), log_area);
+
+    let mut info = Vec::new();
+    if let Some(recommendation) = &view.recommendation {
+        info.push(Line::styled(
+            recommendation.as_str(),
+            Style::default().add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(expected_line) = &view.expected_line {
+        info.push(Line::raw(expected_line.as_str()));
+    }
+    info.push(Line::raw(view.status.as_str()));
+    frame.render_widget(
+        Paragraph::new(info)
+            .wrap(Wrap { trim: true })
+            .block(bordered().title(format!(
+                " {} to move ",
+                player_name(view.current_player, view)
+            ))),
+        info_area,
+    );
+}
+
+/// A bordered block, drawn in plain ASCII if that's turned on.
+fn bordered() -> Block<'static> {
+    let chars = game::box_chars();
+    Block::bordered().border_set(border::Set {
+        top_left: chars.joins[0],
+        top_right: chars.joins[2],
+        bottom_left: chars.joins[6],
+        bottom_right: chars.joins[8],
+        vertical_left: chars.vertical,
+        vertical_right: chars.vertical,
+        horizontal_top: chars.horizontal,
+        horizontal_bottom: chars.horizontal,
+    })
+}
+
+fn draw_hand(
+    frame: &mut Frame,
+    game: &Game,
+    view: &MatchView,
+    data: &Data,
+    player: Player,
+    area: Rect,
+) {
+    let items = game
+        .hand_overview(player)
+        .into_iter()
+        .map(|(slot, id, card, status)| {
+            let mut style = Style::default().fg(match status {
+                HandCardStatus::InHand => player_color(player),
+                HandCardStatus::Possible => Color::Yellow,
+                HandCardStatus::Played | HandCardStatus::RuledOut => Color::DarkGray,
+            });
+            if player == view.current_player && view.selected_slot == Some(slot) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let mut text = format!(
+                "{} {} ({})",
+                (slot + 1) % 10,
+                data.card_display_name(id.get()),
+                card
+            );
+            if status != HandCardStatus::InHand {
+                text.push_str(&format!(" — {}", status.label()));
+            }
+            ListItem::new(text).style(style)
+        })
+        .collect::<Vec<_>>();
+
+    let title = format!(
+        " {} ({} left) ",
+        player_name(player, view),
+        game.hand_size(player)
+    );
+    frame.render_widget(List::new(items).block(bordered().title(title)), area);
+}
+
+fn draw_board(frame: &mut Frame, game: &Game, view: &MatchView, area: Rect) {
+    let changes = game.last_move_changes();
+    let preview_move = view.selected_slot.map(|slot| GameMove {
+        player: view.current_player,
+        card_idx: slot,
+        placement: view.cursor,
+    });
+    let flips = preview_move
+        .as_ref()
+        .filter(|_| game.board_cell(view.cursor).is_none())
+        .map(|mv| game.preview_move(mv))
+        .unwrap_or_default();
+
+    let origin_x = area.x + area.width.saturating_sub(CELL_WIDTH * 3) / 2;
+    let origin_y = area.y + area.height.saturating_sub(CELL_HEIGHT * 3) / 2;
+    for (pos, change) in changes.iter().enumerate() {
+        let cell_area = Rect {
+            x: origin_x + (pos % 3) as u16 * CELL_WIDTH,
+            y: origin_y + (pos / 3) as u16 * CELL_HEIGHT,
+            width: CELL_WIDTH,
+            height: CELL_HEIGHT,
+        }
+        .intersection(area);
+
+        let border_style = if pos == view.cursor {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else if flips.contains(&pos) {
+            Style::default().fg(Color::Magenta)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let mut title = match change {
+            CellChange::Unchanged => game::cell_code(pos),
+            change => format!("{}{}", change.marker(), game::cell_code(pos)),
+        };
+        // Without relying on colors, the owner is shown by letter too
+        if let Some((_, _, owner)) = game.board_cell(pos).filter(|_| game::plain_text_enabled()) {
+            title.push_str(&format!(" {}", owner.letter()));
+        }
+        let block = bordered().title(title).border_style(border_style);
+
+        let lines = match game.board_cell(pos) {
+            Some(([north, east, south, west], suit, owner)) => {
+                let mut style = Style::default().fg(player_color(owner));
+                if *change != CellChange::Unchanged {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                vec![
+                    Line::styled(north, style),
+                    Line::styled(format!("{} {} {}", west, suit, east), style),
+                    Line::styled(south, style),
+                ]
+            }
+            None => vec![Line::raw(""), Line::raw(""), Line::raw("")],
+        };
+        frame.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(block),
+            cell_area,
+        );
+    }
+}
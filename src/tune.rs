@@ -0,0 +1,234 @@
+// The `tune` subcommand: looks for evaluation weights (see `EvalWeights`) that win more than the
+// ones in the settings, by playing the solver against itself. Each candidate changes one weight by
+// a step and plays a fixed corpus of matches against the current best. A candidate that clearly
+// wins the series takes over; when no step does, the steps are halved. The best weights found are
+// saved to the settings, so every search uses them from then on.
+//
+// Both sides search the way they would against an NPC that flips as many cards as it can: averaging
+// over its likely replies is where the tie and margin weights make a difference (against a perfect
+// opponent, any tie value between a loss and a win picks the same moves), and that search stops
+// short of the end of the match early on, which is where the side weight does.
+//
+// The corpus is random decks under a few common rule sets, seeded so every run plays the same
+// matches. Each is played four times, with the candidate on both sides and either side going first,
+// so neither deck nor the first move favors one set of weights. The first move of each is random,
+// since searching the empty board takes as long as the rest of the match together.
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::{convert::TryInto, sync::Arc};
+use triple_triad_solver::{
+    data::Data,
+    game::{CardId, Game, GameMove, Player, Rules},
+    legality::DeckLegality,
+    search::{
+        self, EvalWeights, GamePlayer, Opponent, SearchConfig, SearchableGame, TranspositionTable,
+        WinState,
+    },
+    settings::Settings,
+};
+
+pub const DEFAULT_ROUNDS: usize = 4;
+
+const SEED: u64 = 0x7e57;
+const MATCHUPS: usize = 32;
+
+/// Entries in each side's transposition table, which it keeps through the match.
+const TABLE_ENTRIES: usize = 1 << 16;
+
+/// The share of the series (wins, with ties counting half) a candidate needs to take over. Above
+/// half, so a change that only wins by the luck of the corpus isn't kept.
+const ACCEPT_SCORE: f64 = 0.53;
+
+/// The rules the corpus is played under, skipping the ones left to chance or to who's human.
+const RULE_SETS: [&[&str]; 8] = [
+    &[],
+    &["Plus"],
+    &["Same"],
+    &["Plus", "Same"],
+    &["Reverse"],
+    &["Fallen Ace"],
+    &["Ascension"],
+    &["Descension"],
+];
+
+/// Each weight the search tunes, with the step it starts from.
+const WEIGHTS: [(&str, f64); 3] = [("tie", 10.0), ("margin", 2.0), ("sides", 0.1)];
+
+fn weight_mut(weights: &mut EvalWeights, idx: usize) -> &mut f64 {
+    match idx {
+        0 => &mut weights.tie,
+        1 => &mut weights.margin,
+        _ => &mut weights.sides,
+    }
+}
+
+/// Two decks and the rules to play them under, which the `compare` command also samples its
+/// matches from.
+pub struct Matchup {
+    blue: [CardId; 5],
+    red: [CardId; 5],
+    rules: Rules,
+    /// The hand slot and the cell of the first move, for whichever side makes it.
+    opening: (usize, usize),
+}
+impl Matchup {
+    /// The match with the opening move made by `first`.
+    pub fn start(&self, data: &Data, first: Player) -> Game {
+        let mut game = Game::new(Player::Blue, Arc::clone(&data.cards));
+        game.set_cards_in_hand(Player::Blue, &self.blue, 5);
+        game.set_cards_in_hand(Player::Red, &self.red, 5);
+        game.set_rules(self.rules);
+        let (card_idx, placement) = self.opening;
+        game.apply_move(&GameMove {
+            player: first,
+            card_idx,
+            placement,
+        });
+        game
+    }
+}
+
+/// A legal deck of five different cards from the whole card list.
+fn random_deck(data: &Data, ids: &[i32], rng: &mut StdRng) -> [CardId; 5] {
+    let legality = DeckLegality::standard();
+    loop {
+        let picked = ids.choose_multiple(rng, 5).copied().collect::<Vec<_>>();
+        let deck: [i32; 5] = picked.try_into().unwrap();
+        if legality.is_legal(data, &deck) {
+            return deck.map(|id| CardId::new(id).unwrap());
+        }
+    }
+}
+
+/// `count` matchups, the same ones every time for the same `seed`.
+pub fn corpus(data: &Data, count: usize, seed: u64) -> Vec<Matchup> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut ids = data.card_info.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+    (0..count)
+        .map(|idx| {
+            let mut rules = Rules::default();
+            for name in RULE_SETS[idx % RULE_SETS.len()] {
+                rules.add_rule_by_name(name);
+            }
+            Matchup {
+                blue: random_deck(data, &ids, &mut rng),
+                red: random_deck(data, &ids, &mut rng),
+                rules,
+                opening: (rng.gen_range(0..5), rng.gen_range(0..9)),
+            }
+        })
+        .collect()
+}
+
+/// Plays `matchup` out with Blue picking its moves by `blue` and Red by `red`, returning Blue's
+/// share of the result: 1 for a win, 0.5 for a tie.
+fn play(
+    data: &Data,
+    matchup: &Matchup,
+    blue: &EvalWeights,
+    red: &EvalWeights,
+    first: Player,
+) -> f64 {
+    let mut game = matchup.start(data, first);
+    let mut weights = [blue; 2];
+    weights[Player::Red] = red;
+    // Values worked out with one side's weights would be wrong for the other's, so each side
+    // has its own table
+    let tables = [(); 2].map(|_| Arc::new(TranspositionTable::new(TABLE_ENTRIES)));
+
+    let mut current_player = first.other();
+    loop {
+        match game.win_state() {
+            WinState::NotFinished => {}
+            WinState::Tie => return 0.5,
+            WinState::Winner(Player::Blue) => return 1.0,
+            WinState::Winner(Player::Red) => return 0.0,
+        }
+        let config = SearchConfig {
+            // Moves tied for the best are told apart by the weights or not at all
+            monte_carlo_iterations: 0,
+            verbose: false,
+            transposition_table: Some(Arc::clone(&tables[current_player])),
+            opponent: Opponent::GreedyFlips,
+            eval_weights: *weights[current_player],
+            ..SearchConfig::default()
+        };
+        match search::get_best_move_for_player(&game, current_player, &config).0 {
+            Some(mv) => game.apply_move(&mv),
+            None => return 0.5,
+        }
+        current_player = current_player.other();
+    }
+}
+
+/// `candidate`'s share of the series against `best` over the corpus.
+fn series(data: &Data, corpus: &[Matchup], candidate: &EvalWeights, best: &EvalWeights) -> f64 {
+    let mut games = Vec::with_capacity(corpus.len() * 4);
+    for matchup in corpus {
+        for side in [Player::Blue, Player::Red] {
+            for first in [Player::Blue, Player::Red] {
+                games.push((matchup, side, first));
+            }
+        }
+    }
+    let total: f64 = games
+        .par_iter()
+        .map(|&(matchup, side, first)| match side {
+            Player::Blue => play(data, matchup, candidate, best, first),
+            Player::Red => 1.0 - play(data, matchup, best, candidate, first),
+        })
+        .sum();
+    total / games.len() as f64
+}
+
+pub fn run(data: &Data, settings: &mut Settings, rounds: usize) {
+    let corpus = corpus(data, MATCHUPS, SEED);
+    let start = settings.eval_weights;
+    let mut best = start;
+    let mut steps = WEIGHTS.map(|(_, step)| step);
+    println!("Tuning from {}, over {} matches.", best, corpus.len() * 4);
+
+    for round in 1..=rounds {
+        let mut improved = false;
+        for (idx, (name, _)) in WEIGHTS.iter().enumerate() {
+            for sign in [1.0, -1.0] {
+                let mut candidate = best;
+                let weight = weight_mut(&mut candidate, idx);
+                *weight += sign * steps[idx];
+                // A tie has to stay between a loss and a win
+                if idx == 0 && !(-100.0 < *weight && *weight < 100.0) {
+                    continue;
+                }
+                let score = series(data, &corpus, &candidate, &best);
+                println!(
+                    "Round {}: {} {:+} scored {:.1}%",
+                    round,
+                    name,
+                    sign * steps[idx],
+                    score * 100.0
+                );
+                if score >= ACCEPT_SCORE {
+                    best = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+        if !improved {
+            for step in &mut steps {
+                *step /= 2.0;
+            }
+        }
+        println!("After round {}: {}", round, best);
+    }
+
+    if best == start {
+        println!("Nothing did better than the current weights, so they're kept.");
+        return;
+    }
+    settings.eval_weights = best;
+    settings.save().unwrap();
+    println!("Saved {} to the settings.", best);
+}
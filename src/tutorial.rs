@@ -0,0 +1,172 @@
+//! The tutorial, for players new to Triple Triad itself: scripted matches that say what's going on
+//! before each move and why each of the player's moves is good. The lessons are in
+//! `tutorial.toml`, written like scenario files so the board is set up the same way.
+
+use serde::Deserialize;
+
+use triple_triad_solver::{
+    data::Data,
+    game::{self, Game, GameMove, Player},
+    prompt::Confirm,
+    scenario::{self, Scenario, ScenarioError},
+    search::{GamePlayer, SearchableGame, WinState},
+};
+
+const LESSONS: &str = include_str!("tutorial.toml");
+
+#[derive(Deserialize)]
+struct Tutorial {
+    lessons: Vec<Lesson>,
+}
+
+#[derive(Deserialize)]
+struct Lesson {
+    /// The hands, rules, and the moves played before the lesson starts. The player is Blue.
+    #[serde(flatten)]
+    setup: Scenario,
+    turns: Vec<Turn>,
+}
+
+#[derive(Deserialize)]
+struct Turn {
+    /// The move, like `2-7-7-2@A1`.
+    play: String,
+    /// Said before the move.
+    say: String,
+    /// Moves played on a copy of the board before `say`, to show a mistake.
+    #[serde(default)]
+    what_if: Vec<String>,
+    /// Said after the `what_if` moves.
+    #[serde(default)]
+    what_if_say: String,
+}
+
+pub fn run() {
+    // The lessons are compiled in, so they can only be wrong if this build is
+    let tutorial = toml::from_str::<Tutorial>(LESSONS).unwrap();
+    for (number, lesson) in tutorial.lessons.iter().enumerate() {
+        if number > 0
+            && !Confirm::new(&format!(
+                "Go on to lesson {}, {}?",
+                number + 1,
+                lesson.setup.name
+            ))
+            .with_default(true)
+            .prompt()
+            .unwrap()
+        {
+            return;
+        }
+        println!();
+        println!("Lesson {}: {}", number + 1, lesson.setup.name);
+        if let Err(e) = play_lesson(lesson) {
+            println!("The lesson couldn't be set up: {}", e);
+            return;
+        }
+    }
+    println!("That's the tutorial! Register a deck with your own cards to play an NPC.");
+}
+
+fn play_lesson(lesson: &Lesson) -> Result<(), ScenarioError> {
+    let data = lesson.setup.card_data()?;
+    let mut game = Game::from_scenario(&lesson.setup)?;
+
+    // Every move up front, so the explanations can look ahead along the script
+    let mut moves = Vec::with_capacity(lesson.turns.len());
+    let mut scripted = game.clone();
+    let mut player = lesson.setup.to_move();
+    for turn in &lesson.turns {
+        let mv = scenario::find_move(&scripted, player, &turn.play)?;
+        scripted.apply_move(&mv);
+        moves.push(mv);
+        player = player.other();
+    }
+
+    println!("{}", lesson.setup.description.trim());
+    crate::print_rule_hints(game.rules());
+    let mut possible_moves = Vec::with_capacity(100);
+    for (idx, (turn, &mv)) in lesson.turns.iter().zip(&moves).enumerate() {
+        print!("{}", game.display_with_hand_panel(Player::Red, &data));
+        if !turn.what_if.is_empty() {
+            show_what_if(&game, mv.player, turn, &data)?;
+        }
+        println!("{}", turn.say.trim());
+
+        if mv.player == Player::Blue {
+            game.get_possible_moves(Player::Blue, &mut possible_moves);
+            while !same_move(
+                &game,
+                &possible_moves[crate::pick_move(&possible_moves, &game, &data)],
+                &mv,
+            ) {
+                println!(
+                    "For this lesson, play your {} card in {}.",
+                    game.player_hand_card_name(Player::Blue, mv.card_idx, &data),
+                    game::cell_code(mv.placement)
+                );
+            }
+            println!(
+                "Why: {}",
+                game.explain_move(&mv, &moves[idx + 1..], "Red", &data)
+            );
+        } else {
+            println!(
+                "Red plays its {} card in {}.",
+                game.player_hand_card_name(Player::Red, mv.card_idx, &data),
+                game::cell_code(mv.placement)
+            );
+        }
+        game.apply_move(&mv);
+    }
+
+    print!("{}", game.display_with_hand_panel(Player::Red, &data));
+    match game.win_state() {
+        WinState::Winner(Player::Blue) => println!("You win!"),
+        WinState::Winner(Player::Red) => println!("Red wins."),
+        WinState::Tie => println!("It's a tie."),
+        WinState::NotFinished => {}
+    }
+    println!();
+    Ok(())
+}
+
+/// Whether `picked` plays the same card (by its values, since a hand can hold several of one card)
+/// in the same cell as `scripted`.
+fn same_move(game: &Game, picked: &GameMove, scripted: &GameMove) -> bool {
+    let card = |mv: &GameMove| {
+        game.hand_card(mv.player, mv.card_idx)
+            .map(|card| card.to_string())
+    };
+    picked.placement == scripted.placement && card(picked) == card(scripted)
+}
+
+/// Plays the turn's `what_if` moves on a copy of the board, starting with `player`, and shows how
+/// they end up.
+fn show_what_if(
+    game: &Game,
+    player: Player,
+    turn: &Turn,
+    data: &Data,
+) -> Result<(), ScenarioError> {
+    let mut what_if = game.clone();
+    let mut player = player;
+    for text in &turn.what_if {
+        let mv = scenario::find_move(&what_if, player, text)?;
+        println!(
+            "What if {} {} card in {}?",
+            match player {
+                Player::Blue => "you played your",
+                Player::Red => "Red played its",
+            },
+            what_if.player_hand_card_name(player, mv.card_idx, data),
+            game::cell_code(mv.placement)
+        );
+        what_if.apply_move(&mv);
+        print!("{}", what_if.display_with_hand_panel(Player::Red, data));
+        player = player.other();
+    }
+    println!("{}", turn.what_if_say.trim());
+    println!("Back to the real board:");
+    print!("{}", game.display_with_hand_panel(Player::Red, data));
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! Reads a position from a screenshot of the Triple Triad window, by comparing each card slot
+//! against images of the card faces.
+//!
+//! The card images are PNGs named by card ID (`12.png`), like the card icons SaintCoinach exports
+//! from the game. Where the board and hands are in the screenshot is described by a
+//! [`BoardLayout`], in fractions of the image's size so it doesn't depend on the resolution.
+
+use image::{imageops::FilterType, DynamicImage, GrayImage, RgbImage};
+use serde::Deserialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    game::Player,
+    solve::{PlacedCard, Position},
+};
+
+// Every image is shrunk to this size before comparing, which is plenty to tell the faces apart and
+// irons out small differences in scaling.
+const TEMPLATE_WIDTH: u32 = 48;
+const TEMPLATE_HEIGHT: u32 = 64;
+
+/// How closely (from -1 to 1) a slot has to match a card image to count as that card. Anything
+/// lower is taken to be an empty slot or a face-down card.
+const MATCH_THRESHOLD: f32 = 0.7;
+
+#[derive(thiserror::Error, Debug)]
+pub enum VisionError {
+    #[error("could not read {}", .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("could not load image {}", .0.display())]
+    Image(PathBuf, #[source] image::ImageError),
+
+    #[error("invalid layout file {}", .0.display())]
+    Layout(PathBuf, #[source] serde_json::Error),
+
+    #[error("no card images in {}", .0.display())]
+    NoCardImages(PathBuf),
+}
+
+/// A rectangle in fractions of the image's width and height, from its top left corner.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+impl Region {
+    fn crop(&self, image: &RgbImage) -> RgbImage {
+        let (image_width, image_height) = image.dimensions();
+        let x = ((self.x * image_width as f32) as u32).min(image_width - 1);
+        let y = ((self.y * image_height as f32) as u32).min(image_height - 1);
+        let width = ((self.width * image_width as f32) as u32).clamp(1, image_width - x);
+        let height = ((self.height * image_height as f32) as u32).clamp(1, image_height - y);
+        image::imageops::crop_imm(image, x, y, width, height).to_image()
+    }
+}
+
+/// Where each card slot is in a screenshot.
+#[derive(Deserialize, Clone, Debug)]
+pub struct BoardLayout {
+    /// Row by row from the top left, like the board everywhere else.
+    pub cells: [Region; 9],
+    /// Your hand, which is blue, from the top.
+    pub hand: [Region; 5],
+    /// The opponent's hand, which is red, from the top.
+    pub opponent_hand: [Region; 5],
+}
+impl BoardLayout {
+    /// Reads a layout from a JSON file with the same fields as the struct.
+    pub fn load(path: &Path) -> Result<Self, VisionError> {
+        let text = fs::read_to_string(path).map_err(|e| VisionError::Io(path.to_path_buf(), e))?;
+        serde_json::from_str(&text).map_err(|e| VisionError::Layout(path.to_path_buf(), e))
+    }
+}
+impl Default for BoardLayout {
+    /// A rough layout for a screenshot cropped to just the match window, with the opponent's hand
+    /// in a column on the left, yours on the right, and the board in the middle. UI scales and
+    /// crops vary, so screenshots that don't line up with it need a layout file.
+    fn default() -> Self {
+        const CARD_WIDTH: f32 = 0.14;
+        const CARD_HEIGHT: f32 = 0.3;
+        const HAND_CARD_HEIGHT: f32 = 0.17;
+
+        let hand = |x: f32| {
+            let mut slots = [Region {
+                x,
+                y: 0.0,
+                width: CARD_WIDTH,
+                height: HAND_CARD_HEIGHT,
+            }; 5];
+            for (idx, slot) in slots.iter_mut().enumerate() {
+                slot.y = 0.075 + idx as f32 * HAND_CARD_HEIGHT;
+            }
+            slots
+        };
+        let mut cells = [Region {
+            x: 0.0,
+            y: 0.0,
+            width: CARD_WIDTH,
+            height: CARD_HEIGHT,
+        }; 9];
+        for (idx, cell) in cells.iter_mut().enumerate() {
+            cell.x = 0.29 + (idx % 3) as f32 * CARD_WIDTH;
+            cell.y = 0.05 + (idx / 3) as f32 * CARD_HEIGHT;
+        }
+
+        BoardLayout {
+            cells,
+            hand: hand(0.83),
+            opponent_hand: hand(0.03),
+        }
+    }
+}
+
+/// Card face images to compare the slots against.
+pub struct CardImages {
+    images: Vec<(i32, Vec<f32>)>,
+}
+impl CardImages {
+    /// Loads every image in `dir` named by a card ID, like `12.png`. Other files are ignored.
+    pub fn load_dir(dir: &Path) -> Result<Self, VisionError> {
+        let mut images = vec![];
+        for entry in fs::read_dir(dir).map_err(|e| VisionError::Io(dir.to_path_buf(), e))? {
+            let path = entry
+                .map_err(|e| VisionError::Io(dir.to_path_buf(), e))?
+                .path();
+            let id = match path
+                .file_stem()
+                .and_then(|stem| stem.to_str()?.parse().ok())
+            {
+                Some(id) => id,
+                None => continue,
+            };
+            let image = image::open(&path).map_err(|e| VisionError::Image(path.clone(), e))?;
+            images.push((id, normalized(&image)));
+        }
+
+        if images.is_empty() {
+            return Err(VisionError::NoCardImages(dir.to_path_buf()));
+        }
+        images.sort_by_key(|(id, _)| *id);
+        Ok(CardImages { images })
+    }
+
+    /// The card that best matches the image, if any match well enough.
+    fn identify(&self, image: &DynamicImage) -> Option<i32> {
+        let target = normalized(image);
+        self.images
+            .iter()
+            .map(|(id, card)| {
+                let correlation = card.iter().zip(&target).map(|(a, b)| a * b).sum::<f32>();
+                (*id, correlation / target.len() as f32)
+            })
+            .filter(|(_, score)| *score >= MATCH_THRESHOLD)
+            .max_by(|(_, score1), (_, score2)| score1.total_cmp(score2))
+            .map(|(id, _)| id)
+    }
+}
+
+/// Shrinks the image to the comparison size in grayscale, then shifts and scales the pixels to a
+/// mean of 0 and a standard deviation of 1, so that brightness and contrast don't matter.
+fn normalized(image: &DynamicImage) -> Vec<f32> {
+    let gray: GrayImage = image
+        .resize_exact(TEMPLATE_WIDTH, TEMPLATE_HEIGHT, FilterType::Triangle)
+        .to_luma8();
+    let pixels = gray.pixels().map(|p| p.0[0] as f32).collect::<Vec<_>>();
+
+    let mean = pixels.iter().sum::<f32>() / pixels.len() as f32;
+    let variance = pixels.iter().map(|p| (p - mean).powi(2)).sum::<f32>() / pixels.len() as f32;
+    // A flat slot (like an empty cell) can't match anything
+    let std_dev = variance.sqrt().max(f32::EPSILON);
+    pixels.iter().map(|p| (p - mean) / std_dev).collect()
+}
+
+/// Which side owns a card on the board, going by whether its background is redder or bluer.
+fn owner(image: &RgbImage) -> Player {
+    let (red, blue) = image.pixels().fold((0u64, 0u64), |(red, blue), p| {
+        (red + p.0[0] as u64, blue + p.0[2] as u64)
+    });
+    if red > blue {
+        Player::Red
+    } else {
+        Player::Blue
+    }
+}
+
+/// Reads the cards on the board and in both hands, as a position for you (blue) to move. The
+/// opponent's hand is only filled in with the cards that are face up.
+pub fn read_position(
+    screenshot: &Path,
+    layout: &BoardLayout,
+    card_images: &CardImages,
+) -> Result<Position, VisionError> {
+    let image = image::open(screenshot)
+        .map_err(|e| VisionError::Image(screenshot.to_path_buf(), e))?
+        .to_rgb8();
+    let identify = |region: &Region| {
+        let slot = region.crop(&image);
+        card_images
+            .identify(&DynamicImage::ImageRgb8(slot.clone()))
+            .map(|id| (id, slot))
+    };
+
+    let mut board: [Option<PlacedCard>; 9] = Default::default();
+    for (cell, region) in board.iter_mut().zip(&layout.cells) {
+        *cell = identify(region).map(|(card, slot)| PlacedCard {
+            card,
+            owner: owner(&slot),
+        });
+    }
+    let hand_cards = |regions: &[Region; 5]| {
+        regions
+            .iter()
+            .filter_map(|region| identify(region).map(|(id, _)| id))
+            .collect::<Vec<_>>()
+    };
+
+    Ok(Position {
+        board,
+        hand: hand_cards(&layout.hand),
+        opponent_hand: hand_cards(&layout.opponent_hand),
+        opponent_hand_size: None,
+        to_move: Player::Blue,
+        player: None,
+        rules: vec![],
+        modifiers: vec![],
+    })
+}
@@ -0,0 +1,57 @@
+//! The browser-facing API for the WebAssembly build. The page fetches the game sheets itself and
+//! hands them over as CSV text, since there's no cache or network access to load them from here.
+
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{data::Data, search::SearchConfig, solve};
+
+// The search can't print or read the clock in a browser, and it runs on the page's only thread,
+// so it gets a much smaller budget than the terminal version.
+const DEFAULT_MONTE_CARLO_ITERATIONS: usize = 10_000;
+
+#[wasm_bindgen]
+pub struct Solver {
+    data: Data,
+    search_config: SearchConfig,
+}
+
+#[wasm_bindgen]
+impl Solver {
+    /// Takes a JSON object mapping sheet names (like `TripleTriadCard`) to their CSV contents.
+    #[wasm_bindgen(constructor)]
+    pub fn new(sheets_json: &str) -> Result<Solver, JsError> {
+        let sheets: HashMap<String, String> = serde_json::from_str(sheets_json)?;
+        let sheets = sheets
+            .into_iter()
+            .map(|(name, csv)| (name, csv.into_bytes()))
+            .collect();
+        let data = crate::data::load_from_bytes(&sheets)?;
+
+        Ok(Solver {
+            data,
+            search_config: SearchConfig {
+                monte_carlo_iterations: DEFAULT_MONTE_CARLO_ITERATIONS,
+                time_budget: None,
+                verbose: false,
+                ..SearchConfig::default()
+            },
+        })
+    }
+
+    #[wasm_bindgen(js_name = setMonteCarloIterations)]
+    pub fn set_monte_carlo_iterations(&mut self, iterations: usize) {
+        self.search_config.monte_carlo_iterations = iterations;
+    }
+
+    /// Takes a position as JSON (see `solve::Position`) and returns the recommended move as JSON
+    /// (see `solve::Recommendation`).
+    pub fn solve(&self, position_json: &str) -> Result<String, JsError> {
+        Ok(solve::solve_json(
+            &self.data,
+            position_json,
+            &self.search_config,
+        )?)
+    }
+}
@@ -0,0 +1,78 @@
+// Watches the clipboard for positions written in the one-line notation (see the `solve` module), so
+// an external capture tool only has to copy the board to get a recommendation.
+
+use arboard::Clipboard;
+use std::{
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::Duration,
+};
+use triple_triad_solver::{
+    data::Data,
+    game,
+    search::{GamePlayer, SearchConfig},
+    solve::{self, Position},
+};
+
+use crate::PossiblePlacement;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn watch_clipboard(data: &Data, search_config: &SearchConfig) {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            println!("Could not open the clipboard: {}", e);
+            return;
+        }
+    };
+
+    println!("Watching the clipboard for positions. Press Enter to stop.");
+    let (stop_sender, stop) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = std::io::stdin().read_line(&mut String::new());
+        let _ = stop_sender.send(());
+    });
+
+    let mut last_text = None;
+    while stop.try_recv() == Err(TryRecvError::Empty) {
+        // Anything that isn't text, or doesn't parse as a position, is just someone else's copy
+        if let Ok(text) = clipboard.get_text() {
+            let text = text.trim().to_string();
+            if last_text.as_ref() != Some(&text) {
+                if let Ok(position) = text.parse::<Position>() {
+                    solve_position(&position, data, search_config);
+                }
+                last_text = Some(text);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn solve_position(position: &Position, data: &Data, search_config: &SearchConfig) {
+    let game = match solve::game_for_position(data, position) {
+        Ok(game) => game,
+        Err(e) => {
+            println!("Copied a position that can't be set up: {}", e);
+            return;
+        }
+    };
+    print!(
+        "{}",
+        game.display_with_hand_panel(position.to_move.other(), data)
+    );
+
+    println!("Finding optimal move...");
+    match solve::solve(data, position, search_config) {
+        Ok(recommendation) => println!(
+            "Recommended move: Play your {} card in the {} ({}). (Score: {})",
+            data.card_display_name(recommendation.card),
+            PossiblePlacement(recommendation.position),
+            game::cell_code(recommendation.position),
+            recommendation.score
+        ),
+        Err(e) => println!("Could not solve the copied position: {}", e),
+    }
+    println!("Waiting for the next position (press Enter to stop)...");
+}